@@ -0,0 +1,31 @@
+//! Compare the deserialization of a listing page parsed directly into the typed response with the previous parsing
+//! through a `serde_json::Value`, run it with `cargo bench --features test-util`
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use nft_storage::{test_util::fixtures, types::ListNftResponse, MAX_LIST_LIMIT};
+use serde_json::Value;
+
+/// listing page with `MAX_LIST_LIMIT` nfts, made repeating the first nft of the captured listing
+fn listing_page() -> String {
+    let mut list: Value = serde_json::from_str(fixtures::LIST_RESPONSE).unwrap();
+    let nft = list["value"][0].clone();
+    list["value"] = (0..MAX_LIST_LIMIT).map(|_| nft.clone()).collect();
+    list.to_string()
+}
+
+fn deserialize(c: &mut Criterion) {
+    let page = listing_page();
+    let mut group = c.benchmark_group("listing page");
+    group.bench_function("through Value", |b| {
+        b.iter(|| {
+            let value: Value = serde_json::from_str(black_box(&page)).unwrap();
+            serde_json::from_value::<ListNftResponse>(value).unwrap()
+        })
+    });
+    group.bench_function("direct", |b| {
+        b.iter(|| serde_json::from_str::<ListNftResponse>(black_box(&page)).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, deserialize);
+criterion_main!(benches);
@@ -0,0 +1,27 @@
+use anyhow::Result;
+use nft_storage::NftStorage;
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    // provide the url and as second argument the token generated from nft storage dashboard
+    let nft_storage = NftStorage::new(
+        "https://api.nft.storage",
+        "token generated from nft storage",
+    );
+    // every task gets its own clone, the clones share the connection pool and the rate limiter
+    let mut tasks = Vec::new();
+    for i in 0..10 {
+        let nft_storage = nft_storage.clone();
+        tasks.push(tokio::spawn(async move {
+            let file = format!("hello from task {i}").into_bytes();
+            nft_storage.upload_file(file).await
+        }));
+    }
+    // wait for all the uploads
+    for task in tasks {
+        let store_file = task.await??;
+        println!("{}", store_file.value.cid);
+    }
+
+    Ok(())
+}
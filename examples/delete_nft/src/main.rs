@@ -1,6 +1,5 @@
 use anyhow::Result;
-use nft_storage::{types::DeleteNftResponse, NftStorage};
-use serde_json::to_string_pretty;
+use nft_storage::{types::DeleteAllSummary, NftStorage};
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -9,12 +8,18 @@ async fn main() -> Result<()> {
         "https://api.nft.storage",
         "token generated from nft storage",
     );
-    // store an nft
-    let deleted_nft: DeleteNftResponse = nft_storage
+    // delete all nfts printing the progress
+    let summary: DeleteAllSummary = nft_storage
         // .delete_nft("bafybeibo4rijplqlv6o6j7jcftx4ckgzjv43jd2whqeluc5dnxslutsdda")
-        .delete_all_nft()
+        .delete_all_nft_with_progress(|progress| {
+            println!(
+                "deleting CID: {} deleted: {}",
+                progress.cid, progress.deleted
+            )
+        })
         .await?;
-    println!("{}", to_string_pretty(&deleted_nft)?);
+    println!("deleted: {:?}", summary.deleted);
+    println!("failed: {:?}", summary.failed);
 
     Ok(())
 }
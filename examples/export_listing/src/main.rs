@@ -0,0 +1,30 @@
+use anyhow::Result;
+use nft_storage::{types::ExportFormat, NftStorage};
+
+/// export the stored nfts to stdout, `--format csv` (the default) or `--format ndjson`
+#[tokio::main]
+async fn main() -> Result<()> {
+    let mut format = ExportFormat::Csv;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--format" => format = args.next().unwrap_or_default().parse()?,
+            _ => anyhow::bail!(
+                "unknown argument '{}', usage: export_listing [--format csv|ndjson]",
+                arg
+            ),
+        }
+    }
+    // provide the url and as second argument the token generated from nft storage dashboard
+    let nft_storage = NftStorage::new(
+        "https://api.nft.storage",
+        "token generated from nft storage",
+    );
+    // write every upload on a line of stdout
+    let exported = nft_storage
+        .export_listing(format, tokio::io::stdout())
+        .await?;
+    eprintln!("{} nfts exported", exported);
+
+    Ok(())
+}
@@ -1,5 +1,8 @@
 use anyhow::Result;
-use nft_storage::{types::ListNftResponse, NftStorage};
+use nft_storage::{
+    types::{ListNftResponse, ListOptions},
+    NftStorage,
+};
 use serde_json::to_string_pretty;
 
 #[tokio::main]
@@ -9,10 +12,14 @@ async fn main() -> Result<()> {
         "https://api.nft.storage",
         "token generated from nft storage",
     );
-    // store an nft
+    // list the nfts containing only the metadata
+    let options = ListOptions {
+        only_metadata: true,
+        // limit: Some(100),
+        ..Default::default()
+    };
     let list_nft: ListNftResponse = nft_storage
-        .list_all_stored_nft(None, None, true)
-        // .list_all_stored_nft(None, Some("100"), true)
+        .list_with_options(options)
         // .get_nft("bafybeibjt6afd4u7or3olgfhy7cc2t2zfpf436w6limuiyp3347t23andy")
         .await?;
     println!("{}", to_string_pretty(&list_nft)?);
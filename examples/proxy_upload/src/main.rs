@@ -0,0 +1,46 @@
+use anyhow::Result;
+use axum::{
+    extract::{DefaultBodyLimit, Multipart, State},
+    http::StatusCode,
+    routing::post,
+    Json, Router,
+};
+use nft_storage::{multipart::from_axum_multipart, types::StoreNftResponse, NftStorage};
+
+/// max size of the forwarded forms
+const MAX_BODY_SIZE: usize = 100 * 1024 * 1024;
+
+/// forward the files of the form to nft storage in a directory
+async fn upload(
+    State(nft_storage): State<NftStorage>,
+    multipart: Multipart,
+) -> Result<Json<StoreNftResponse>, (StatusCode, String)> {
+    let files = from_axum_multipart(multipart)
+        .await
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    let response = nft_storage
+        .upload_directory(files)
+        .await
+        .map_err(|e| (StatusCode::BAD_GATEWAY, e.to_string()))?;
+    Ok(Json(response))
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    // provide the url and as second argument the token generated from nft storage dashboard
+    let nft_storage = NftStorage::new(
+        "https://api.nft.storage",
+        "token generated from nft storage",
+    );
+    let app = Router::new()
+        .route("/upload", post(upload))
+        .layer(DefaultBodyLimit::max(MAX_BODY_SIZE))
+        .with_state(nft_storage);
+
+    // try it with `curl -F file=@hello.txt -F file=@ciao.txt http://127.0.0.1:3000/upload`
+    axum::Server::bind(&"127.0.0.1:3000".parse()?)
+        .serve(app.into_make_service())
+        .await?;
+
+    Ok(())
+}
@@ -1,5 +1,8 @@
 use anyhow::Result;
-use nft_storage::{types::StoreNftResponse, NftStorage};
+use nft_storage::{
+    types::{StoreNftResponse, UploadFile},
+    NftStorage,
+};
 use serde_json::to_string_pretty;
 
 #[tokio::main]
@@ -9,17 +12,14 @@ async fn main() -> Result<()> {
         "https://api.nft.storage",
         "token generated from nft storage",
     );
-    let file_names = vec!["hello.txt", "ciao.txt"];
-    // read multiple files
-    let file = std::fs::read(file_names[0])?;
-    let file2 = std::fs::read(file_names[1])?;
-    // collect the files in a vec
-    let file_bytes_vec = vec![file, file2];
+    // read multiple files and pair them with their names
+    let files = vec![
+        UploadFile::new("hello.txt", std::fs::read("hello.txt")?),
+        UploadFile::new("ciao.txt", std::fs::read("ciao.txt")?),
+    ];
 
     // upload file in a directory
-    let store_file: StoreNftResponse = nft_storage
-        .upload_file_in_directory(file_bytes_vec, file_names)
-        .await?;
+    let store_file: StoreNftResponse = nft_storage.upload_directory(files).await?;
 
     println!("{}", to_string_pretty(&store_file)?);
 
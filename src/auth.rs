@@ -0,0 +1,124 @@
+use crate::{NFTStorageError, NftStorage};
+use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::fmt;
+
+/// header containing the did of the agent using a ucan
+const AGENT_DID_HEADER: &str = "x-agent-did";
+
+/// Credentials used to authorize the api requests
+///
+/// The api key gives access to the whole account, while a ucan can be minted by a backend and given to untrusted
+/// clients to upload on its behalf
+#[derive(Clone, PartialEq, Eq)]
+pub enum Auth {
+    /// api token generated from nft storage dashboard
+    ApiKey(String),
+    /// ucan token sent with the did of the agent using it
+    Ucan {
+        /// ucan token
+        token: String,
+        /// did of the agent the ucan is delegated to
+        agent_did: String,
+    },
+}
+
+impl Auth {
+    /// set the authorization headers, they are marked as sensitive
+    pub(crate) fn apply(&self, headers: &mut HeaderMap) -> Result<(), NFTStorageError> {
+        let token = match self {
+            // a local ipfs node doesn't need a token
+            Auth::ApiKey(token) if token.is_empty() => return Ok(()),
+            Auth::ApiKey(token) => token,
+            Auth::Ucan { token, agent_did } => {
+                let agent_did = HeaderValue::from_str(agent_did).map_err(|_| {
+                    NFTStorageError::InvalidToken(
+                        "the agent did contains invalid characters".to_string(),
+                    )
+                })?;
+                headers.insert(AGENT_DID_HEADER, agent_did);
+                token
+            }
+        };
+        let mut value = HeaderValue::from_str(&format!("Bearer {}", token)).map_err(|_| {
+            NFTStorageError::InvalidToken("the token contains invalid characters".to_string())
+        })?;
+        value.set_sensitive(true);
+        headers.insert(AUTHORIZATION, value);
+        Ok(())
+    }
+}
+
+/// the tokens are redacted so they can't end up in the logs
+impl fmt::Debug for Auth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Auth::ApiKey(_) => f.debug_tuple("ApiKey").field(&"***redacted***").finish(),
+            Auth::Ucan { agent_did, .. } => f
+                .debug_struct("Ucan")
+                .field("token", &"***redacted***")
+                .field("agent_did", agent_did)
+                .finish(),
+        }
+    }
+}
+
+/// response containing the ucan token
+#[derive(Debug, Deserialize)]
+struct UcanTokenResponse {
+    value: String,
+}
+
+/// UCAN delegation methods
+impl NftStorage {
+    /// Use `auth` to authorize the requests of this client, by default the api key is used
+    /// ```no_run
+    /// use nft_storage::{Auth, NftStorage};
+    /// use anyhow::Result;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     // the backend registers its did and requests a root ucan with the api key
+    ///     let nft_storage = NftStorage::new("https://api.nft.storage", "token generated from nft storage");
+    ///     nft_storage.register_did("did:key:z6MkgTrzFkcDxbhvGkbFXpgo9Cn8c5mujKnQ3uf9bTPzpZNm").await?;
+    ///     let token = nft_storage.request_ucan_token().await?;
+    ///
+    ///     // a client authorized with the ucan, it doesn't know the api key
+    ///     let nft_storage = NftStorage::new("https://api.nft.storage", "").with_auth(Auth::Ucan {
+    ///         token,
+    ///         agent_did: "did:key:z6MkgTrzFkcDxbhvGkbFXpgo9Cn8c5mujKnQ3uf9bTPzpZNm".to_string(),
+    ///     });
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    pub fn with_auth(mut self, auth: Auth) -> NftStorage {
+        self.auth = auth;
+        self
+    }
+
+    /// Register the `did` of the account, it is required before requesting a ucan token
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn register_did(&self, did: &str) -> Result<(), NFTStorageError> {
+        if !did.starts_with("did:") {
+            return Err(NFTStorageError::InvalidInput(format!(
+                "invalid did '{}', it must start with 'did:'",
+                did
+            )));
+        }
+        let url = self.endpoint("user/did");
+        let request = self.client.post(url).json(&json!({ "did": did }));
+        self.request::<Value>(request).await.map(|_| ())
+    }
+
+    /// Request a root ucan token of the account, it can be delegated to other agents to let them upload
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn request_ucan_token(&self) -> Result<String, NFTStorageError> {
+        let url = self.endpoint("ucan/token");
+        self.request::<UcanTokenResponse>(self.client.post(url))
+            .await
+            .map(|response| response.value)
+    }
+}
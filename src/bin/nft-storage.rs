@@ -0,0 +1,210 @@
+//! Command line client of nft storage, available with the `cli` feature
+//!
+//! The token is read from `--token` or from the `NFT_STORAGE_API_TOKEN` environment variable, the results are
+//! printed as pretty json or, with `--quiet`, as the cids only
+use clap::{Parser, Subcommand};
+use nft_storage::{
+    types::{ListOptions, StoreNftResponse},
+    NFTStorageError, NftStorage, API_TOKEN_ENV, API_URL_ENV, DEFAULT_API_URL,
+};
+use serde::Serialize;
+use serde_json::json;
+use std::{path::PathBuf, process::ExitCode};
+
+/// Store and manage nfts on nft storage
+#[derive(Debug, Parser)]
+#[command(name = "nft-storage", version)]
+struct Cli {
+    /// api token generated from the nft storage dashboard
+    #[arg(long, global = true, env = API_TOKEN_ENV, hide_env_values = true)]
+    token: Option<String>,
+    /// nft storage rest api endpoint
+    #[arg(long, global = true, env = API_URL_ENV, default_value = DEFAULT_API_URL)]
+    url: String,
+    /// print only the cids instead of the json responses
+    #[arg(long, short, global = true)]
+    quiet: bool,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Upload a file
+    Upload {
+        /// file to upload
+        path: PathBuf,
+    },
+    /// Upload the files of a directory
+    UploadDir {
+        /// directory to upload
+        dir: PathBuf,
+        /// upload the sub directories too
+        #[arg(long, short)]
+        recursive: bool,
+    },
+    /// Store a file with its erc-721 metadata
+    Store {
+        /// file of the nft
+        path: PathBuf,
+        /// name of the nft
+        #[arg(long)]
+        name: String,
+        /// description of the nft
+        #[arg(long, default_value = "")]
+        description: String,
+    },
+    /// List the stored nfts
+    List {
+        /// number of nfts to list, between 1 and 100
+        #[arg(long)]
+        limit: Option<u32>,
+        /// list only the nfts stored with their metadata
+        #[arg(long)]
+        only_metadata: bool,
+    },
+    /// Get a stored nft
+    Get {
+        /// cid of the nft
+        cid: String,
+    },
+    /// Check if an nft is stored
+    Check {
+        /// cid of the nft
+        cid: String,
+    },
+    /// Delete a stored nft
+    Delete {
+        /// cid of the nft
+        cid: String,
+    },
+    /// Delete all the stored nfts
+    DeleteAll {
+        /// confirm the deletion, nothing is deleted without it
+        #[arg(long)]
+        yes: bool,
+    },
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match run(cli).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            // nft storage support asks for the id of the failed request
+            if let Some(request_id) = e.request_id() {
+                eprintln!("request id: {}", request_id);
+            }
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// run the subcommand printing its result on stdout
+async fn run(cli: Cli) -> Result<(), NFTStorageError> {
+    let token = cli
+        .token
+        .filter(|token| !token.trim().is_empty())
+        .ok_or_else(|| NFTStorageError::MissingToken(API_TOKEN_ENV.to_string()))?;
+    let nft_storage = NftStorage::builder(cli.url, token).build()?;
+    let quiet = cli.quiet;
+    match cli.command {
+        Command::Upload { path } => {
+            print_upload(nft_storage.upload_file_from_path(path).await?, quiet)
+        }
+        Command::UploadDir { dir, recursive } => print_upload(
+            nft_storage
+                .upload_directory_from_path(dir, recursive)
+                .await?,
+            quiet,
+        ),
+        Command::Store {
+            path,
+            name,
+            description,
+        } => {
+            let file = tokio::fs::read(path).await?;
+            let stored = nft_storage.store_nft(file, name, description).await?;
+            print_upload(stored, quiet)
+        }
+        Command::List {
+            limit,
+            only_metadata,
+        } => {
+            let options = ListOptions {
+                limit,
+                only_metadata,
+                // only the cids are printed when quiet
+                decorate_links: !quiet,
+                ..Default::default()
+            };
+            let list = nft_storage.list_with_options(options).await?;
+            if quiet {
+                list.value.iter().for_each(|nft| println!("{}", nft.cid));
+                Ok(())
+            } else {
+                print_json(&list)
+            }
+        }
+        Command::Get { cid } => {
+            let nft = nft_storage.get_nft(cid.as_str()).await?;
+            print_or_cid(&nft, &nft.value.cid, quiet)
+        }
+        Command::Check { cid } => {
+            let check = nft_storage.check_nft(cid.as_str()).await?;
+            print_or_cid(&check, &check.value.cid, quiet)
+        }
+        Command::Delete { cid } => {
+            let deleted = nft_storage.delete_nft(cid.as_str()).await?;
+            print_or_cid(&deleted, &cid, quiet)
+        }
+        Command::DeleteAll { yes } => {
+            if !yes {
+                return Err(NFTStorageError::InvalidInput(
+                    "pass --yes to delete all the stored nfts".to_string(),
+                ));
+            }
+            let summary = nft_storage.delete_all_nft().await?;
+            if quiet {
+                summary.deleted.iter().for_each(|cid| println!("{}", cid));
+                return Ok(());
+            }
+            let failed = summary
+                .failed
+                .iter()
+                .map(|(cid, e)| json!({ "cid": cid, "error": e.to_string() }))
+                .collect::<Vec<_>>();
+            print_json(&json!({ "deleted": summary.deleted, "failed": failed }))
+        }
+    }
+}
+
+/// print the upload response or its cid
+fn print_upload(upload: StoreNftResponse, quiet: bool) -> Result<(), NFTStorageError> {
+    print_or_cid(&upload, &upload.value.cid, quiet)
+}
+
+/// print `value` as pretty json, or only `cid` when quiet
+fn print_or_cid<T, C>(value: &T, cid: &C, quiet: bool) -> Result<(), NFTStorageError>
+where
+    T: Serialize,
+    C: std::fmt::Display,
+{
+    if quiet {
+        println!("{}", cid);
+        Ok(())
+    } else {
+        print_json(value)
+    }
+}
+
+/// print `value` as pretty json
+fn print_json<T>(value: &T) -> Result<(), NFTStorageError>
+where
+    T: Serialize,
+{
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
@@ -0,0 +1,741 @@
+//! Blocking client, available with the `blocking` feature
+//!
+//! Every method runs the method with the same name of the async [`crate::NftStorage`] on an internal single
+//! threaded runtime, so the two clients share all the request, response and link decoration logic.
+//!
+//! The methods must not be called from an async context, they would panic when blocking the runtime
+//! ```no_run
+//! use nft_storage::{blocking::NftStorage, types::*};
+//! use anyhow::Result;
+//!
+//! fn main() -> Result<()> {
+//!     // provide the url and as second argument the token generated from nft storage dashboard
+//!     let nft_storage = NftStorage::new("https://api.nft.storage", "token generated from nft storage")?;
+//!     let upload_file: StoreNftResponse = nft_storage.upload_file(std::fs::read("my_nft.jpg")?)?;
+//!
+//!     Ok(())
+//! }
+//! ```
+use crate::{pins::*, types::*, NFTStorageError, RateLimiter, RawBody, ResponseMeta};
+use bytes::Bytes;
+use reqwest::{Method, StatusCode};
+use serde::Serialize;
+use serde_json::Value;
+use std::{path::Path, sync::Arc, time::Duration};
+use tokio::runtime::{Builder, Runtime};
+
+/// Blocking NftStorage client
+///
+/// Cloning it is cheap, the clones share the runtime, the connection pool and the rate limiter
+#[derive(Clone)]
+pub struct NftStorage {
+    /// async client running the requests
+    inner: crate::NftStorage,
+    /// runtime used to wait for the requests
+    runtime: Arc<Runtime>,
+}
+
+impl NftStorage {
+    /// Create a new instance of the blocking client, see [`crate::NftStorage::new`]
+    pub fn new<S>(url: S, token: S) -> Result<NftStorage, NFTStorageError>
+    where
+        S: Into<String>,
+    {
+        NftStorage::from_async(crate::NftStorage::new(url, token))
+    }
+
+    /// Create a new instance of the blocking client from environment variables, see [`crate::NftStorage::from_env`]
+    pub fn from_env() -> Result<NftStorage, NFTStorageError> {
+        NftStorage::from_async(crate::NftStorage::from_env()?)
+    }
+
+    /// Create a blocking client from an async one, use it to configure the client with [`crate::NftStorage::builder`]
+    pub fn from_async(inner: crate::NftStorage) -> Result<NftStorage, NFTStorageError> {
+        let runtime = Builder::new_current_thread().enable_all().build()?;
+        Ok(NftStorage {
+            inner,
+            runtime: Arc::new(runtime),
+        })
+    }
+
+    /// The async client used to run the requests
+    pub fn inner(&self) -> &crate::NftStorage {
+        &self.inner
+    }
+
+    /// Blocking version of [`crate::NftStorage::validate_token`]
+    pub fn validate_token(&self) -> Result<(), NFTStorageError> {
+        self.runtime.block_on(self.inner.validate_token())
+    }
+
+    /// Blocking version of [`crate::NftStorage::list_all_stored_nft`]
+    #[deprecated(note = "use `list_with_options` with `ListOptions` instead")]
+    pub fn list_all_stored_nft(
+        &self,
+        before: Option<Timestamp>,
+        limit: Option<u32>,
+        only_metadata: bool,
+    ) -> Result<ListNftResponse, NFTStorageError> {
+        let options = ListOptions {
+            before,
+            limit,
+            only_metadata,
+            ..Default::default()
+        };
+        self.list_with_options(options)
+    }
+
+    /// Blocking version of [`crate::NftStorage::list_with_options`]
+    pub fn list_with_options(
+        &self,
+        options: ListOptions,
+    ) -> Result<ListNftResponse, NFTStorageError> {
+        self.runtime.block_on(self.inner.list_with_options(options))
+    }
+
+    /// Blocking version of [`crate::NftStorage::list_lenient`]
+    pub fn list_lenient(
+        &self,
+        options: ListOptions,
+    ) -> Result<LenientListResponse, NFTStorageError> {
+        self.runtime.block_on(self.inner.list_lenient(options))
+    }
+
+    /// Blocking version of [`crate::NftStorage::list_filtered`]
+    pub fn list_filtered(
+        &self,
+        before: Option<Timestamp>,
+        limit: Option<u32>,
+        filter: &ListFilter,
+    ) -> Result<ListNftResponse, NFTStorageError> {
+        self.runtime
+            .block_on(self.inner.list_filtered(before, limit, filter))
+    }
+
+    /// Blocking version of [`crate::NftStorage::list_all_pages`]
+    pub fn list_all_pages(
+        &self,
+        page_size: u32,
+        only_metadata: bool,
+    ) -> Result<Vec<NftValue>, NFTStorageError> {
+        self.runtime
+            .block_on(self.inner.list_all_pages(page_size, only_metadata))
+    }
+
+    /// Blocking version of [`crate::NftStorage::storage_usage`]
+    pub fn storage_usage(&self, since: Option<Timestamp>) -> Result<UsageSummary, NFTStorageError> {
+        self.runtime.block_on(self.inner.storage_usage(since))
+    }
+
+    /// Blocking version of [`crate::NftStorage::store_nft`]
+    pub fn store_nft<N, D>(
+        &self,
+        file: Vec<u8>,
+        nft_name: N,
+        description: D,
+    ) -> Result<StoreNftResponse, NFTStorageError>
+    where
+        N: AsRef<str>,
+        D: AsRef<str>,
+    {
+        self.runtime
+            .block_on(self.inner.store_nft(file, nft_name, description))
+    }
+
+    /// Blocking version of [`crate::NftStorage::store_nft_erc721`]
+    pub fn store_nft_erc721<N, D>(
+        &self,
+        file: Vec<u8>,
+        nft_name: N,
+        description: D,
+        options: Erc721Options,
+    ) -> Result<StoreNftResponse, NFTStorageError>
+    where
+        N: AsRef<str>,
+        D: AsRef<str>,
+    {
+        self.runtime.block_on(
+            self.inner
+                .store_nft_erc721(file, nft_name, description, options),
+        )
+    }
+
+    /// Blocking version of [`crate::NftStorage::store_nft_with_metadata`]
+    pub fn store_nft_with_metadata(
+        &self,
+        file: Vec<u8>,
+        metadata: NftMetadata,
+    ) -> Result<StoreNftResponse, NFTStorageError> {
+        self.runtime
+            .block_on(self.inner.store_nft_with_metadata(file, metadata))
+    }
+
+    /// Blocking version of [`crate::NftStorage::store_nft_detailed`]
+    pub fn store_nft_detailed(
+        &self,
+        file: Vec<u8>,
+        metadata: NftMetadata,
+    ) -> Result<StoreNftResult, NFTStorageError> {
+        self.runtime
+            .block_on(self.inner.store_nft_detailed(file, metadata))
+    }
+
+    /// Blocking version of [`crate::NftStorage::reupload_metadata`]
+    pub fn reupload_metadata(
+        &self,
+        asset_cid: &str,
+        metadata: NftMetadata,
+        old_metadata_cid: Option<&str>,
+    ) -> Result<String, NFTStorageError> {
+        self.runtime.block_on(
+            self.inner
+                .reupload_metadata(asset_cid, metadata, old_metadata_cid),
+        )
+    }
+
+    /// Blocking version of [`crate::NftStorage::store_collection`]
+    pub fn store_collection(
+        &self,
+        items: Vec<CollectionItem>,
+        options: CollectionOptions,
+    ) -> Result<StoreCollectionResponse, NFTStorageError> {
+        self.runtime
+            .block_on(self.inner.store_collection(items, options))
+    }
+
+    /// Blocking version of [`crate::NftStorage::store_nft_metaplex`]
+    pub fn store_nft_metaplex(
+        &self,
+        image: UploadFile,
+        metadata: MetaplexMetadata,
+    ) -> Result<StoreMetaplexResponse, NFTStorageError> {
+        self.runtime
+            .block_on(self.inner.store_nft_metaplex(image, metadata))
+    }
+
+    /// Blocking version of [`crate::NftStorage::store_erc1155`]
+    pub fn store_erc1155(
+        &self,
+        metadata: NftMetadata,
+        image: UploadFile,
+        extra_files: Vec<UploadFile>,
+    ) -> Result<StoreResponse, NFTStorageError> {
+        self.runtime
+            .block_on(self.inner.store_erc1155(metadata, image, extra_files))
+    }
+
+    /// Blocking version of [`crate::NftStorage::store_nft_directory`]
+    pub fn store_nft_directory<N, D>(
+        &self,
+        files: Vec<UploadFile>,
+        nft_name: N,
+        description: D,
+    ) -> Result<StoreNftResponse, NFTStorageError>
+    where
+        N: AsRef<str>,
+        D: AsRef<str>,
+    {
+        self.runtime
+            .block_on(self.inner.store_nft_directory(files, nft_name, description))
+    }
+
+    /// Blocking version of [`crate::NftStorage::store_nft_directory_with_options`]
+    pub fn store_nft_directory_with_options<N, D>(
+        &self,
+        files: Vec<UploadFile>,
+        nft_name: N,
+        description: D,
+        options: NftDirectoryOptions,
+    ) -> Result<StoreNftDirectoryResponse, NFTStorageError>
+    where
+        N: AsRef<str>,
+        D: AsRef<str>,
+    {
+        self.runtime
+            .block_on(self.inner.store_nft_directory_with_options(
+                files,
+                nft_name,
+                description,
+                options,
+            ))
+    }
+
+    /// Blocking version of [`crate::NftStorage::delete_nft`]
+    pub fn delete_nft<C>(&self, cid: C) -> Result<DeleteNftResponse, NFTStorageError>
+    where
+        C: TryInto<Cid>,
+        NFTStorageError: From<C::Error>,
+    {
+        self.runtime.block_on(self.inner.delete_nft(cid))
+    }
+
+    /// Blocking version of [`crate::NftStorage::delete_nft_idempotent`]
+    pub fn delete_nft_idempotent<C>(&self, cid: C) -> Result<DeleteNftResponse, NFTStorageError>
+    where
+        C: TryInto<Cid>,
+        NFTStorageError: From<C::Error>,
+    {
+        self.runtime.block_on(self.inner.delete_nft_idempotent(cid))
+    }
+
+    /// Blocking version of [`crate::NftStorage::delete_all_nft`]
+    pub fn delete_all_nft(&self) -> Result<DeleteAllSummary, NFTStorageError> {
+        self.runtime.block_on(self.inner.delete_all_nft())
+    }
+
+    /// Blocking version of [`crate::NftStorage::delete_all_nft_with_progress`]
+    pub fn delete_all_nft_with_progress<F>(
+        &self,
+        on_progress: F,
+    ) -> Result<DeleteAllSummary, NFTStorageError>
+    where
+        F: FnMut(DeleteProgress),
+    {
+        self.runtime
+            .block_on(self.inner.delete_all_nft_with_progress(on_progress))
+    }
+
+    /// Blocking version of [`crate::NftStorage::delete_all_nft_with_options`]
+    pub fn delete_all_nft_with_options<F>(
+        &self,
+        options: DeleteAllOptions,
+        on_progress: F,
+    ) -> Result<DeleteAllSummary, NFTStorageError>
+    where
+        F: FnMut(DeleteProgress),
+    {
+        self.runtime
+            .block_on(self.inner.delete_all_nft_with_options(options, on_progress))
+    }
+
+    /// Blocking version of [`crate::NftStorage::get_nft`]
+    pub fn get_nft<C>(&self, cid: C) -> Result<GetNftResponse, NFTStorageError>
+    where
+        C: TryInto<Cid>,
+        NFTStorageError: From<C::Error>,
+    {
+        self.runtime.block_on(self.inner.get_nft(cid))
+    }
+
+    /// Blocking version of [`crate::NftStorage::get_nft_with_metadata`]
+    pub fn get_nft_with_metadata<C>(&self, cid: C) -> Result<GetNftDetailed, NFTStorageError>
+    where
+        C: TryInto<Cid>,
+        NFTStorageError: From<C::Error>,
+    {
+        self.runtime.block_on(self.inner.get_nft_with_metadata(cid))
+    }
+
+    /// Blocking version of [`crate::NftStorage::get_nft_with_options`]
+    #[cfg(feature = "cache")]
+    pub fn get_nft_with_options<C>(
+        &self,
+        cid: C,
+        options: &ReadOptions,
+    ) -> Result<GetNftResponse, NFTStorageError>
+    where
+        C: TryInto<Cid>,
+        NFTStorageError: From<C::Error>,
+    {
+        self.runtime
+            .block_on(self.inner.get_nft_with_options(cid, options))
+    }
+
+    /// Blocking version of [`crate::NftStorage::check_nft`]
+    pub fn check_nft<C>(&self, cid: C) -> Result<CheckCidNftResponse, NFTStorageError>
+    where
+        C: TryInto<Cid>,
+        NFTStorageError: From<C::Error>,
+    {
+        self.runtime.block_on(self.inner.check_nft(cid))
+    }
+
+    /// Blocking version of [`crate::NftStorage::check_nft_with_options`]
+    #[cfg(feature = "cache")]
+    pub fn check_nft_with_options<C>(
+        &self,
+        cid: C,
+        options: &ReadOptions,
+    ) -> Result<CheckCidNftResponse, NFTStorageError>
+    where
+        C: TryInto<Cid>,
+        NFTStorageError: From<C::Error>,
+    {
+        self.runtime
+            .block_on(self.inner.check_nft_with_options(cid, options))
+    }
+
+    /// Blocking version of [`crate::NftStorage::wait_for_pin`]
+    pub fn wait_for_pin<C>(
+        &self,
+        cid: C,
+        options: WaitOptions,
+    ) -> Result<CheckCidNftResponse, NFTStorageError>
+    where
+        C: TryInto<Cid>,
+        NFTStorageError: From<C::Error>,
+    {
+        self.runtime.block_on(self.inner.wait_for_pin(cid, options))
+    }
+
+    /// Blocking version of [`crate::NftStorage::check_deals`]
+    pub fn check_deals<C>(
+        &self,
+        cid: C,
+        min_active_deals: usize,
+    ) -> Result<DealStatus, NFTStorageError>
+    where
+        C: TryInto<Cid>,
+        NFTStorageError: From<C::Error>,
+    {
+        self.runtime
+            .block_on(self.inner.check_deals(cid, min_active_deals))
+    }
+
+    /// Blocking version of [`crate::NftStorage::wait_for_deal`]
+    pub fn wait_for_deal<C>(
+        &self,
+        cid: C,
+        min_active_deals: usize,
+        timeout: Duration,
+    ) -> Result<DealStatus, NFTStorageError>
+    where
+        C: TryInto<Cid>,
+        NFTStorageError: From<C::Error>,
+    {
+        self.runtime
+            .block_on(self.inner.wait_for_deal(cid, min_active_deals, timeout))
+    }
+
+    /// Blocking version of [`crate::NftStorage::download`]
+    pub fn download(&self, cid: &str) -> Result<Bytes, NFTStorageError> {
+        self.runtime.block_on(self.inner.download(cid))
+    }
+
+    /// Blocking version of [`crate::NftStorage::download_file`]
+    pub fn download_file(&self, cid: &str, path_in_dir: &str) -> Result<Bytes, NFTStorageError> {
+        self.runtime
+            .block_on(self.inner.download_file(cid, path_in_dir))
+    }
+
+    /// Blocking version of [`crate::NftStorage::download_to_path`]
+    pub fn download_to_path<P>(
+        &self,
+        cid: &str,
+        path_in_dir: Option<&str>,
+        output: P,
+    ) -> Result<u64, NFTStorageError>
+    where
+        P: AsRef<Path>,
+    {
+        self.runtime
+            .block_on(self.inner.download_to_path(cid, path_in_dir, output))
+    }
+
+    /// Blocking version of [`crate::NftStorage::download_race`]
+    pub fn download_race(
+        &self,
+        cid: &str,
+        gateways: &[String],
+        timeout_after: Duration,
+    ) -> Result<Bytes, NFTStorageError> {
+        self.runtime
+            .block_on(self.inner.download_race(cid, gateways, timeout_after))
+    }
+
+    /// Blocking version of [`crate::NftStorage::upload_file`]
+    pub fn upload_file(&self, file: Vec<u8>) -> Result<StoreNftResponse, NFTStorageError> {
+        self.runtime.block_on(self.inner.upload_file(file))
+    }
+
+    /// Blocking version of [`crate::NftStorage::upload_file_with_meta`]
+    pub fn upload_file_with_meta(
+        &self,
+        file: Vec<u8>,
+    ) -> Result<(StoreNftResponse, ResponseMeta), NFTStorageError> {
+        self.runtime
+            .block_on(self.inner.upload_file_with_meta(file))
+    }
+
+    /// Blocking version of [`crate::NftStorage::upload_and_verify`]
+    pub fn upload_and_verify(
+        &self,
+        file: Vec<u8>,
+        options: VerifyOptions,
+    ) -> Result<VerifiedUpload, NFTStorageError> {
+        self.runtime
+            .block_on(self.inner.upload_and_verify(file, options))
+    }
+
+    /// Blocking version of [`crate::NftStorage::upload_json`]
+    pub fn upload_json<T>(
+        &self,
+        value: &T,
+        file_name: Option<&str>,
+    ) -> Result<UploadJsonResponse, NFTStorageError>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.runtime
+            .block_on(self.inner.upload_json(value, file_name))
+    }
+
+    /// Blocking version of [`crate::NftStorage::upload_json_with_options`]
+    pub fn upload_json_with_options<T>(
+        &self,
+        value: &T,
+        options: JsonUploadOptions,
+    ) -> Result<UploadJsonResponse, NFTStorageError>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.runtime
+            .block_on(self.inner.upload_json_with_options(value, options))
+    }
+
+    /// Blocking version of [`crate::NftStorage::upload_text`]
+    pub fn upload_text(
+        &self,
+        text: &str,
+        file_name: Option<&str>,
+    ) -> Result<StoreNftResponse, NFTStorageError> {
+        self.runtime
+            .block_on(self.inner.upload_text(text, file_name))
+    }
+
+    /// Blocking version of [`crate::NftStorage::upload_string`]
+    pub fn upload_string(
+        &self,
+        text: String,
+        file_name: Option<&str>,
+    ) -> Result<StoreNftResponse, NFTStorageError> {
+        self.runtime
+            .block_on(self.inner.upload_string(text, file_name))
+    }
+
+    /// Blocking version of [`crate::NftStorage::upload_html`]
+    pub fn upload_html(
+        &self,
+        html: &str,
+        file_name: Option<&str>,
+    ) -> Result<StoreNftResponse, NFTStorageError> {
+        self.runtime
+            .block_on(self.inner.upload_html(html, file_name))
+    }
+
+    /// Blocking version of [`crate::NftStorage::upload_file_verified`]
+    pub fn upload_file_verified(&self, file: Vec<u8>) -> Result<StoreNftResponse, NFTStorageError> {
+        self.runtime.block_on(self.inner.upload_file_verified(file))
+    }
+
+    /// Blocking version of [`crate::NftStorage::upload_file_if_absent`]
+    pub fn upload_file_if_absent(
+        &self,
+        file: Vec<u8>,
+    ) -> Result<UploadIfAbsentResponse, NFTStorageError> {
+        self.runtime
+            .block_on(self.inner.upload_file_if_absent(file))
+    }
+
+    /// Blocking version of [`crate::NftStorage::upload_file_with_options`]
+    pub fn upload_file_with_options(
+        &self,
+        file: Vec<u8>,
+        options: UploadOptions,
+    ) -> Result<StoreNftResponse, NFTStorageError> {
+        self.runtime
+            .block_on(self.inner.upload_file_with_options(file, options))
+    }
+
+    /// Blocking version of [`crate::NftStorage::upload_file_from_path`]
+    pub fn upload_file_from_path<P>(&self, path: P) -> Result<StoreNftResponse, NFTStorageError>
+    where
+        P: AsRef<Path>,
+    {
+        self.runtime
+            .block_on(self.inner.upload_file_from_path(path))
+    }
+
+    /// Blocking version of [`crate::NftStorage::upload_car`]
+    pub fn upload_car(&self, car: Vec<u8>) -> Result<StoreNftResponse, NFTStorageError> {
+        self.runtime.block_on(self.inner.upload_car(car))
+    }
+
+    /// Blocking version of [`crate::NftStorage::upload_car_from_path`]
+    pub fn upload_car_from_path<P>(&self, path: P) -> Result<StoreNftResponse, NFTStorageError>
+    where
+        P: AsRef<Path>,
+    {
+        self.runtime.block_on(self.inner.upload_car_from_path(path))
+    }
+
+    /// Blocking version of [`crate::NftStorage::upload_directory_deduplicated`]
+    #[cfg(feature = "car")]
+    pub fn upload_directory_deduplicated(
+        &self,
+        files: Vec<UploadFile>,
+    ) -> Result<DeduplicatedUpload, NFTStorageError> {
+        self.runtime
+            .block_on(self.inner.upload_directory_deduplicated(files))
+    }
+
+    /// Blocking version of [`crate::NftStorage::upload_large`]
+    #[cfg(feature = "car")]
+    pub fn upload_large<P, F>(
+        &self,
+        source: P,
+        chunk_size: usize,
+        on_progress: F,
+    ) -> Result<Cid, NFTStorageError>
+    where
+        P: AsRef<Path>,
+        F: FnMut(CarUploadProgress),
+    {
+        self.runtime
+            .block_on(self.inner.upload_large(source, chunk_size, on_progress))
+    }
+
+    /// Blocking version of [`crate::NftStorage::upload_files_concurrent`]
+    pub fn upload_files_concurrent(
+        &self,
+        files: Vec<UploadFile>,
+        concurrency: usize,
+        rate_limiter: Option<&RateLimiter>,
+    ) -> Vec<Result<StoreNftResponse, NFTStorageError>> {
+        self.runtime.block_on(
+            self.inner
+                .upload_files_concurrent(files, concurrency, rate_limiter),
+        )
+    }
+
+    /// Blocking version of [`crate::NftStorage::upload_files_concurrent_with_options`]
+    pub fn upload_files_concurrent_with_options(
+        &self,
+        files: Vec<UploadFile>,
+        options: ConcurrentUploadOptions,
+    ) -> Result<Vec<Result<StoreNftResponse, NFTStorageError>>, NFTStorageError> {
+        self.runtime.block_on(
+            self.inner
+                .upload_files_concurrent_with_options(files, options),
+        )
+    }
+
+    /// Blocking version of [`crate::NftStorage::upload_directory`]
+    pub fn upload_directory(
+        &self,
+        files: Vec<UploadFile>,
+    ) -> Result<StoreNftResponse, NFTStorageError> {
+        self.runtime.block_on(self.inner.upload_directory(files))
+    }
+
+    /// Blocking version of [`crate::NftStorage::upload_directory_detailed`]
+    pub fn upload_directory_detailed(
+        &self,
+        files: Vec<UploadFile>,
+    ) -> Result<DirectoryUploadResult, NFTStorageError> {
+        self.runtime
+            .block_on(self.inner.upload_directory_detailed(files))
+    }
+
+    /// Blocking version of [`crate::NftStorage::upload_directory_with_options`]
+    pub fn upload_directory_with_options(
+        &self,
+        files: Vec<UploadFile>,
+        options: UploadOptions,
+    ) -> Result<StoreNftResponse, NFTStorageError> {
+        self.runtime
+            .block_on(self.inner.upload_directory_with_options(files, options))
+    }
+
+    /// Blocking version of [`crate::NftStorage::upload_directory_from_path`]
+    pub fn upload_directory_from_path<P>(
+        &self,
+        dir: P,
+        recursive: bool,
+    ) -> Result<StoreNftResponse, NFTStorageError>
+    where
+        P: AsRef<Path>,
+    {
+        self.runtime
+            .block_on(self.inner.upload_directory_from_path(dir, recursive))
+    }
+
+    /// Blocking version of [`crate::NftStorage::upload_directory_from_path_with_options`]
+    pub fn upload_directory_from_path_with_options<P>(
+        &self,
+        dir: P,
+        options: DirectoryOptions,
+    ) -> Result<StoreNftResponse, NFTStorageError>
+    where
+        P: AsRef<Path>,
+    {
+        self.runtime.block_on(
+            self.inner
+                .upload_directory_from_path_with_options(dir, options),
+        )
+    }
+
+    /// Blocking version of [`crate::NftStorage::register_did`]
+    pub fn register_did(&self, did: &str) -> Result<(), NFTStorageError> {
+        self.runtime.block_on(self.inner.register_did(did))
+    }
+
+    /// Blocking version of [`crate::NftStorage::request_ucan_token`]
+    pub fn request_ucan_token(&self) -> Result<String, NFTStorageError> {
+        self.runtime.block_on(self.inner.request_ucan_token())
+    }
+
+    /// Blocking version of [`crate::NftStorage::raw_request`]
+    pub fn raw_request(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<RawBody>,
+    ) -> Result<(StatusCode, Value), NFTStorageError> {
+        self.runtime
+            .block_on(self.inner.raw_request(method, path, body))
+    }
+
+    /// Blocking version of [`crate::NftStorage::pin_cid`]
+    pub fn pin_cid<C>(
+        &self,
+        cid: C,
+        name: Option<&str>,
+    ) -> Result<PinStatusResponse, NFTStorageError>
+    where
+        C: TryInto<Cid>,
+        NFTStorageError: From<C::Error>,
+    {
+        self.runtime.block_on(self.inner.pin_cid(cid, name))
+    }
+
+    /// Blocking version of [`crate::NftStorage::pin`]
+    pub fn pin(&self, pin: PinObject) -> Result<PinStatusResponse, NFTStorageError> {
+        self.runtime.block_on(self.inner.pin(pin))
+    }
+
+    /// Blocking version of [`crate::NftStorage::list_pins`]
+    pub fn list_pins(&self, query: PinListQuery) -> Result<PinResults, NFTStorageError> {
+        self.runtime.block_on(self.inner.list_pins(query))
+    }
+
+    /// Blocking version of [`crate::NftStorage::get_pin`]
+    pub fn get_pin(&self, request_id: &str) -> Result<PinStatusResponse, NFTStorageError> {
+        self.runtime.block_on(self.inner.get_pin(request_id))
+    }
+
+    /// Blocking version of [`crate::NftStorage::replace_pin`]
+    pub fn replace_pin(
+        &self,
+        request_id: &str,
+        pin: PinObject,
+    ) -> Result<PinStatusResponse, NFTStorageError> {
+        self.runtime
+            .block_on(self.inner.replace_pin(request_id, pin))
+    }
+
+    /// Blocking version of [`crate::NftStorage::unpin`]
+    pub fn unpin(&self, request_id: &str) -> Result<(), NFTStorageError> {
+        self.runtime.block_on(self.inner.unpin(request_id))
+    }
+}
@@ -0,0 +1,603 @@
+use crate::{
+    types::{Gateway, MetadataLimits},
+    Auth, HttpTransport, NFTStorageError, NftStorage, RateLimiter, ReqwestTransport, RetryPolicy,
+    DEFAULT_GATEWAY_URL, DEFAULT_MAX_REQUEST_SIZE, DEFAULT_USER_AGENT,
+};
+use reqwest::{
+    header::{HeaderMap, HeaderName, HeaderValue, USER_AGENT},
+    Client, Url,
+};
+use std::{fmt, num::NonZeroU32, sync::Arc, time::Duration};
+
+/// Builder used to configure an [`NftStorage`] instance
+///
+/// It allows to provide an already configured `reqwest::Client` or to let the builder create one with
+/// a connect timeout, a total request timeout and a default user-agent.
+///
+/// When a custom `client` is provided it is used as it is, so `connect_timeout`, `timeout` and `user_agent`
+/// must be configured directly on that client.
+///
+/// On `wasm32` the requests are sent with the browser fetch api, so `connect_timeout`, `timeout` and `user_agent`
+/// are not available.
+/// ```no_run
+/// use nft_storage::NftStorage;
+/// use std::time::Duration;
+/// use anyhow::Result;
+///
+/// #[tokio::main]
+/// async fn main() -> Result<()> {
+///     // provide the url and as second argument the token generated from nft storage dashboard
+///     let nft_storage = NftStorage::builder("https://api.nft.storage", "token generated from nft storage")
+///         .connect_timeout(Duration::from_secs(10))
+///         .timeout(Duration::from_secs(60 * 60))
+///         .build()?;
+///
+///     Ok(())
+/// }
+/// ```
+///
+/// The `Debug` output never contains the token nor the values of the default headers
+/// ```
+/// use nft_storage::NftStorage;
+/// use reqwest::header::{HeaderName, HeaderValue};
+///
+/// let builder = NftStorage::builder("https://api.nft.storage", "secret-token")
+///     .default_header(HeaderName::from_static("x-proxy-token"), HeaderValue::from_static("secret-header"));
+/// let debug = format!("{:?}", builder);
+/// assert!(!debug.contains("secret-token"));
+/// assert!(!debug.contains("secret-header"));
+/// assert!(debug.contains("x-proxy-token"));
+/// ```
+#[derive(Clone)]
+pub struct NftStorageBuilder {
+    /// nft storage rest api endpoint
+    url: String,
+    /// headers sent with every api request
+    default_headers: HeaderMap,
+    /// nft storage api token
+    token: String,
+    /// credentials used instead of the api token
+    auth: Option<Auth>,
+    /// custom reqwest client instance
+    client: Option<Client>,
+    /// custom transport used to send the requests
+    transport: Option<Arc<dyn HttpTransport>>,
+    #[cfg(not(target_arch = "wasm32"))]
+    /// max time to wait for the connection to be established
+    connect_timeout: Option<Duration>,
+    #[cfg(not(target_arch = "wasm32"))]
+    /// max time to wait for a request to complete
+    timeout: Option<Duration>,
+    #[cfg(not(target_arch = "wasm32"))]
+    /// user-agent sent with every request
+    user_agent: Option<String>,
+    /// identifier of the application appended to the user-agent
+    app_identifier: Option<String>,
+    /// policy used to retry rate limited and failed requests
+    retry_policy: RetryPolicy,
+    /// rate limiter awaited before sending every request
+    rate_limiter: Option<Arc<RateLimiter>>,
+    /// wait for the reset of the rate limit window when no requests are left
+    wait_on_rate_limit: bool,
+    /// ipfs gateway used to download files
+    gateway: String,
+    /// additional gateways used to create the nft links
+    link_gateways: Vec<Gateway>,
+    /// limits checked on the metadata before storing an nft
+    metadata_limits: MetadataLimits,
+    /// max size of the body of a single upload request
+    max_request_size: u64,
+    #[cfg(feature = "cache")]
+    /// time after which the cached responses expire, the cache is disabled if not set
+    cache_ttl: Option<Duration>,
+}
+
+impl fmt::Debug for NftStorageBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug = f.debug_struct("NftStorageBuilder");
+        debug
+            .field("url", &self.url)
+            .field(
+                "default_headers",
+                &self.default_headers.keys().collect::<Vec<_>>(),
+            )
+            .field("token", &"***redacted***")
+            .field("auth", &self.auth)
+            .field("client", &self.client.as_ref().map(|_| "reqwest::Client"))
+            .field(
+                "transport",
+                &self.transport.as_ref().map(|_| "dyn HttpTransport"),
+            );
+        #[cfg(not(target_arch = "wasm32"))]
+        debug
+            .field("connect_timeout", &self.connect_timeout)
+            .field("timeout", &self.timeout)
+            .field("user_agent", &self.user_agent);
+        debug
+            .field("app_identifier", &self.app_identifier)
+            .field("retry_policy", &self.retry_policy)
+            .field("rate_limiter", &self.rate_limiter)
+            .field("wait_on_rate_limit", &self.wait_on_rate_limit)
+            .field("gateway", &self.gateway)
+            .field("link_gateways", &self.link_gateways)
+            .field("metadata_limits", &self.metadata_limits)
+            .field("max_request_size", &self.max_request_size);
+        #[cfg(feature = "cache")]
+        debug.field("cache_ttl", &self.cache_ttl);
+        debug.finish()
+    }
+}
+
+impl NftStorageBuilder {
+    /// Create a new builder from the api `url` and the jwt `token` generated from nft storage dashboard
+    pub fn new<S>(url: S, token: S) -> NftStorageBuilder
+    where
+        S: Into<String>,
+    {
+        NftStorageBuilder {
+            url: url.into(),
+            default_headers: HeaderMap::new(),
+            token: token.into(),
+            auth: None,
+            client: None,
+            transport: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            connect_timeout: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            timeout: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            user_agent: None,
+            app_identifier: None,
+            retry_policy: RetryPolicy::default(),
+            rate_limiter: None,
+            wait_on_rate_limit: false,
+            gateway: DEFAULT_GATEWAY_URL.to_string(),
+            link_gateways: Vec::new(),
+            metadata_limits: MetadataLimits::default(),
+            max_request_size: DEFAULT_MAX_REQUEST_SIZE,
+            #[cfg(feature = "cache")]
+            cache_ttl: None,
+        }
+    }
+
+    /// Authorize the requests with `auth` instead of the api token, like a ucan delegated by a backend
+    pub fn auth(mut self, auth: Auth) -> NftStorageBuilder {
+        self.auth = Some(auth);
+        self
+    }
+
+    /// Send the header `name` with every api request, like the token required by a proxy in front of the api
+    ///
+    /// The default headers aren't sent to the ipfs gateways, a header set by a request, like its content type, takes
+    /// precedence. The api url can contain a base path, the endpoints are joined after it
+    /// ```
+    /// # #[cfg(feature = "test-util")]
+    /// # #[tokio::main]
+    /// # async fn main() -> anyhow::Result<()> {
+    /// use nft_storage::{test_util::MockTransport, types::*, NftStorage, RetryPolicy};
+    /// use reqwest::header::{HeaderName, HeaderValue};
+    ///
+    /// for (base, prefix) in [("https://h", "https://h"), ("https://h/", "https://h"), ("https://h/a/b", "https://h/a/b")] {
+    ///     let mock = MockTransport::new();
+    ///     let nft_storage = NftStorage::builder(base, "token")
+    ///         .default_header(HeaderName::from_static("x-corp-proxy-token"), HeaderValue::from_static("secret"))
+    ///         .retry_policy(RetryPolicy::none())
+    ///         .transport(mock.clone())
+    ///         .build()?;
+    ///     let cid = "bafkreidvbhs33ighmljlvr7zbv2ywwzcmp5adtf4kqvlly67cy56bdtmve";
+    ///     // no response is queued, only the requests are checked
+    ///     let _ = nft_storage.list_with_options(ListOptions::default()).await;
+    ///     let _ = nft_storage.upload_file(b"hello".to_vec()).await;
+    ///     let image = UploadFile::new("image.png", b"image".to_vec());
+    ///     let _ = nft_storage.store_erc1155(NftMetadata::new("name", "description"), image, vec![]).await;
+    ///     let _ = nft_storage.get_nft(cid).await;
+    ///     let _ = nft_storage.check_nft(cid).await;
+    ///     let _ = nft_storage.delete_nft(cid).await;
+    ///
+    ///     let urls = mock.requests().iter().map(|request| request.url.to_string()).collect::<Vec<_>>();
+    ///     assert_eq!(urls, [
+    ///         format!("{}/", prefix),
+    ///         format!("{}/upload", prefix),
+    ///         format!("{}/store", prefix),
+    ///         format!("{}/{}", prefix, cid),
+    ///         format!("{}/check/{}", prefix, cid),
+    ///         format!("{}/{}", prefix, cid),
+    ///     ]);
+    ///     assert!(mock.requests().iter().all(|request| request.headers["x-corp-proxy-token"] == "secret"));
+    /// }
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "test-util"))]
+    /// # fn main() {}
+    /// ```
+    pub fn default_header(mut self, name: HeaderName, value: HeaderValue) -> NftStorageBuilder {
+        self.default_headers.insert(name, value);
+        self
+    }
+
+    /// Use an already configured `reqwest::Client`
+    pub fn client(mut self, client: Client) -> NftStorageBuilder {
+        self.client = Some(client);
+        self
+    }
+
+    /// Send the requests with a custom transport instead of the `reqwest::Client`
+    ///
+    /// The client is still used to build the requests, see [`HttpTransport`]
+    pub fn transport<T>(mut self, transport: T) -> NftStorageBuilder
+    where
+        T: HttpTransport + 'static,
+    {
+        self.transport = Some(Arc::new(transport));
+        self
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    /// Set the max time to wait for the connection to the api to be established
+    pub fn connect_timeout(mut self, timeout: Duration) -> NftStorageBuilder {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    /// Set the max time a request can take, from the connection until the end of the response body
+    ///
+    /// Keep in mind that uploading big files can take a long time
+    pub fn timeout(mut self, timeout: Duration) -> NftStorageBuilder {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    /// Set the user-agent sent with every request, replacing `DEFAULT_USER_AGENT`
+    pub fn user_agent<S>(mut self, user_agent: S) -> NftStorageBuilder
+    where
+        S: Into<String>,
+    {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+
+    /// Append the identifier of the application to the user-agent, like `mint-service/2.3`
+    ///
+    /// The requests are sent with `nft-storage-rs/<crate version> <app identifier>`, so the api operators can tell
+    /// which client and application sent them. A `user-agent` set with `default_header` takes precedence
+    /// ```
+    /// # #[cfg(feature = "test-util")]
+    /// # #[tokio::main]
+    /// # async fn main() -> anyhow::Result<()> {
+    /// use nft_storage::{test_util::MockTransport, types::*, NftStorage, RetryPolicy, DEFAULT_USER_AGENT};
+    /// use reqwest::header::USER_AGENT;
+    ///
+    /// let expected = format!("nft-storage-rs/{} mint-service/2.3", env!("CARGO_PKG_VERSION"));
+    /// for (app_identifier, user_agent) in [(None, DEFAULT_USER_AGENT.to_string()), (Some("mint-service/2.3"), expected)] {
+    ///     let mock = MockTransport::new();
+    ///     let mut builder = NftStorage::builder("https://api.nft.storage", "token");
+    ///     if let Some(app_identifier) = app_identifier {
+    ///         builder = builder.app_identifier(app_identifier);
+    ///     }
+    ///     let nft_storage = builder.retry_policy(RetryPolicy::none()).transport(mock.clone()).build()?;
+    ///     let cid = "bafkreidvbhs33ighmljlvr7zbv2ywwzcmp5adtf4kqvlly67cy56bdtmve";
+    ///     // no response is queued, only the requests are checked
+    ///     let _ = nft_storage.list_with_options(ListOptions::default()).await;
+    ///     let _ = nft_storage.upload_file(b"hello".to_vec()).await;
+    ///     let _ = nft_storage.upload_directory(vec![UploadFile::new("a.txt", b"a".to_vec())]).await;
+    ///     // the image upload fails, so the metadata isn't uploaded
+    ///     let _ = nft_storage.store_nft(b"image".to_vec(), "name", "description").await;
+    ///     let image = UploadFile::new("image.png", b"image".to_vec());
+    ///     let _ = nft_storage.store_erc1155(NftMetadata::new("name", "description"), image, vec![]).await;
+    ///     let _ = nft_storage.get_nft(cid).await;
+    ///     let _ = nft_storage.check_nft(cid).await;
+    ///     let _ = nft_storage.delete_nft(cid).await;
+    ///
+    ///     assert_eq!(mock.requests().len(), 8);
+    ///     assert!(mock.requests().iter().all(|request| request.headers[USER_AGENT] == user_agent.as_str()));
+    /// }
+    /// assert!(NftStorage::builder("https://api.nft.storage", "token").app_identifier("bad\napp").build().is_err());
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "test-util"))]
+    /// # fn main() {}
+    /// ```
+    pub fn app_identifier<S>(mut self, app_identifier: S) -> NftStorageBuilder
+    where
+        S: Into<String>,
+    {
+        self.app_identifier = Some(app_identifier.into());
+        self
+    }
+
+    /// Set the policy used to retry rate limited and failed requests
+    ///
+    /// By default a request is retried 3 times on 429, 500, 502, 503 and 504 responses, use `RetryPolicy::none()`
+    /// to disable the retries
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> NftStorageBuilder {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Limit the requests to `permits` every `interval`
+    ///
+    /// Use `NFT_STORAGE_RATE_LIMIT_PERMITS` and `NFT_STORAGE_RATE_LIMIT_INTERVAL` to match the nft storage limits,
+    /// by default no rate limiter is used. `permits` can't be zero, see [`RateLimiter::new`].
+    ///
+    /// The clones of the client share the same rate limiter
+    /// ```
+    /// # #[cfg(feature = "test-util")]
+    /// # #[tokio::main]
+    /// # async fn main() -> anyhow::Result<()> {
+    /// use nft_storage::{test_util::{fixtures, MockTransport}, NftStorage, RetryPolicy};
+    /// use std::{num::NonZeroU32, time::{Duration, Instant}};
+    ///
+    /// let mock = MockTransport::new();
+    /// for _ in 0..60 {
+    ///     mock.push_response(200, vec![], fixtures::UPLOAD_RESPONSE);
+    /// }
+    /// // the interval of the rate limiter starts when it is created
+    /// let start = Instant::now();
+    /// let nft_storage = NftStorage::builder("https://api.nft.storage", "token")
+    ///     .rate_limit(NonZeroU32::new(30).unwrap(), Duration::from_millis(500))
+    ///     .retry_policy(RetryPolicy::none())
+    ///     .transport(mock.clone())
+    ///     .build()?;
+    /// let clone = nft_storage.clone();
+    ///
+    /// for client in [&nft_storage, &clone] {
+    ///     for _ in 0..30 {
+    ///         client.upload_file(b"hello".to_vec()).await?;
+    ///     }
+    /// }
+    /// // the first 30 requests use the whole budget, the clone waits for the refill
+    /// assert_eq!(mock.requests().len(), 60);
+    /// assert!(start.elapsed() >= Duration::from_millis(500));
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "test-util"))]
+    /// # fn main() {}
+    /// ```
+    pub fn rate_limit(self, permits: NonZeroU32, interval: Duration) -> NftStorageBuilder {
+        self.rate_limiter(Arc::new(RateLimiter::new(permits, interval)))
+    }
+
+    /// Use a rate limiter shared with other clients
+    pub fn rate_limiter(mut self, rate_limiter: Arc<RateLimiter>) -> NftStorageBuilder {
+        self.rate_limiter = Some(rate_limiter);
+        self
+    }
+
+    /// Disable the rate limiter, useful for self-hosted or mock endpoints
+    pub fn without_rate_limit(mut self) -> NftStorageBuilder {
+        self.rate_limiter = None;
+        self
+    }
+
+    /// Wait for the reset of the rate limit window before sending a request when the last api response reported that
+    /// no requests are left, instead of sending a request that would be rate limited. It is disabled by default
+    ///
+    /// The state is read from the rate limit headers, see [`NftStorage::rate_limit_status`]
+    /// ```
+    /// # #[cfg(feature = "test-util")]
+    /// # #[tokio::main]
+    /// # async fn main() -> anyhow::Result<()> {
+    /// use nft_storage::{test_util::MockTransport, NftStorage};
+    /// use serde_json::json;
+    /// use std::time::{Duration, Instant};
+    ///
+    /// let mock = MockTransport::new();
+    /// let headers = vec![
+    ///     ("x-ratelimit-remaining".to_string(), "0".to_string()),
+    ///     ("x-ratelimit-reset".to_string(), "1".to_string()),
+    /// ];
+    /// let body = json!({ "ok": true, "value": [] }).to_string();
+    /// mock.push_response(200, headers, body.clone());
+    /// mock.push_response(200, vec![], body);
+    /// let nft_storage = NftStorage::builder("https://api.nft.storage", "token")
+    ///     .wait_on_rate_limit(true)
+    ///     .transport(mock)
+    ///     .build()?;
+    ///
+    /// nft_storage.list_with_options(Default::default()).await?;
+    /// // no requests are left, the next one is sent after the reset
+    /// let start = Instant::now();
+    /// nft_storage.list_with_options(Default::default()).await?;
+    /// assert!(start.elapsed() >= Duration::from_millis(900));
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "test-util"))]
+    /// # fn main() {}
+    /// ```
+    pub fn wait_on_rate_limit(mut self, wait_on_rate_limit: bool) -> NftStorageBuilder {
+        self.wait_on_rate_limit = wait_on_rate_limit;
+        self
+    }
+
+    /// Set the ipfs gateway used to download files, by default `https://nftstorage.link/ipfs/`
+    pub fn gateway<S>(mut self, gateway: S) -> NftStorageBuilder
+    where
+        S: Into<String>,
+    {
+        self.gateway = gateway.into();
+        self
+    }
+
+    /// Add a gateway used to create the `custom` links of the listed and fetched nfts
+    ///
+    /// The `gateway` url must end before the cid, like `https://<subdomain>.nftstorage.link/ipfs`
+    pub fn link_gateway<S>(mut self, gateway: S) -> NftStorageBuilder
+    where
+        S: AsRef<str>,
+    {
+        self.link_gateways.push(Gateway::path(gateway));
+        self
+    }
+
+    /// Set the gateways used to create the `custom` links of the nfts, replacing the ones already added
+    ///
+    /// The first gateway is used also for the gateway urls of the exported nfts and of the directory uploads, by
+    /// default they use the download gateway
+    /// ```
+    /// # #[cfg(feature = "test-util")]
+    /// # #[tokio::main]
+    /// # async fn main() -> anyhow::Result<()> {
+    /// use nft_storage::{test_util::MockTransport, types::*, NftStorage, RetryPolicy};
+    /// use serde_json::json;
+    ///
+    /// let mock = MockTransport::new();
+    /// let v0 = "QmWATWQ7fVPP2EFGu71UkfnqhYXDYH566qy47CnJDgvs8u";
+    /// mock.push_json(200, json!({ "ok": true, "value": { "cid": v0 } }));
+    /// let nft_storage = NftStorage::builder("https://api.nft.storage", "token")
+    ///     .gateways(vec![
+    ///         Gateway::subdomain("https://nftstorage.link"),
+    ///         Gateway::path("https://dedicated.example/ipfs"),
+    ///     ])
+    ///     .retry_policy(RetryPolicy::none())
+    ///     .transport(mock)
+    ///     .build()?;
+    ///
+    /// let nft = nft_storage.get_nft(v0).await?;
+    /// let v1 = nft.value.cid.to_v1()?;
+    /// assert_eq!(nft.value.links.custom, [
+    ///     format!("https://{}.ipfs.nftstorage.link", v1),
+    ///     format!("https://dedicated.example/ipfs/{}", v0),
+    /// ]);
+    /// assert_eq!(nft.value.links.preferred(), nft.value.links.custom[0]);
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "test-util"))]
+    /// # fn main() {}
+    /// ```
+    pub fn gateways(mut self, gateways: Vec<Gateway>) -> NftStorageBuilder {
+        self.link_gateways = gateways;
+        self
+    }
+
+    /// Set the limits checked on the nft name and description before storing an nft
+    ///
+    /// By default the name can be 256 characters long and the description 10000
+    pub fn metadata_limits(mut self, metadata_limits: MetadataLimits) -> NftStorageBuilder {
+        self.metadata_limits = metadata_limits;
+        self
+    }
+
+    /// Set the max size of the body of a single upload request, by default `DEFAULT_MAX_REQUEST_SIZE` (100 MiB)
+    ///
+    /// Bigger uploads return a `PayloadTooLarge` error before sending them, the streamed uploads without a known
+    /// length are aborted as soon as they exceed it. The content is never allowed above `MAX_CONTENT_SIZE` (31 GiB)
+    /// ```
+    /// # #[cfg(feature = "test-util")]
+    /// # #[tokio::main]
+    /// # async fn main() -> anyhow::Result<()> {
+    /// use nft_storage::{test_util::MockTransport, NFTStorageError, NftStorage};
+    ///
+    /// let mock = MockTransport::new();
+    /// let nft_storage = NftStorage::builder("https://api.nft.storage", "token")
+    ///     .max_request_size(10)
+    ///     .transport(mock.clone())
+    ///     .build()?;
+    ///
+    /// let result = nft_storage.upload_file(b"hello world".to_vec()).await;
+    /// assert!(matches!(result, Err(NFTStorageError::PayloadTooLarge { size: 11, limit: 10 })));
+    /// assert!(mock.requests().is_empty());
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "test-util"))]
+    /// # fn main() {}
+    /// ```
+    pub fn max_request_size(mut self, max_request_size: u64) -> NftStorageBuilder {
+        self.max_request_size = max_request_size;
+        self
+    }
+
+    #[cfg(feature = "cache")]
+    /// Cache the `get_nft` and `check_nft` responses for `ttl`, by default the responses aren't cached
+    ///
+    /// The cached responses of a cid are removed when it is deleted or uploaded with the client or its clones, use
+    /// `get_nft_with_options` and `check_nft_with_options` to bypass the cache
+    pub fn cache_ttl(mut self, ttl: Duration) -> NftStorageBuilder {
+        self.cache_ttl = Some(ttl);
+        self
+    }
+
+    /// Create the `NftStorage` instance
+    ///
+    /// It will return an error if the url isn't an http or https url without query and fragment, or if the
+    /// `reqwest::Client` can't be created
+    pub fn build(self) -> Result<NftStorage, NFTStorageError> {
+        // remove the trailing slash so joining paths doesn't produce `//upload`
+        let url = self.url.trim().trim_end_matches('/').to_string();
+        // the endpoints are joined after the url, so a query or a fragment would end up in the middle of them
+        let valid = Url::parse(&url).is_ok_and(|parsed| {
+            matches!(parsed.scheme(), "http" | "https")
+                && parsed.query().is_none()
+                && parsed.fragment().is_none()
+        });
+        if !valid {
+            return Err(NFTStorageError::InvalidUrl(self.url));
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        let user_agent = self
+            .user_agent
+            .unwrap_or_else(|| DEFAULT_USER_AGENT.to_string());
+        #[cfg(target_arch = "wasm32")]
+        let user_agent = DEFAULT_USER_AGENT.to_string();
+        let user_agent = match self.app_identifier {
+            Some(app_identifier) => format!("{} {}", user_agent, app_identifier.trim()),
+            None => user_agent,
+        };
+        let user_agent = HeaderValue::from_str(&user_agent).map_err(|_| {
+            NFTStorageError::InvalidInput(format!("invalid user-agent '{}'", user_agent))
+        })?;
+        let mut default_headers = self.default_headers;
+        default_headers
+            .entry(USER_AGENT)
+            .or_insert_with(|| user_agent.clone());
+
+        let client = match self.client {
+            Some(client) => client,
+            #[cfg(not(target_arch = "wasm32"))]
+            None => {
+                let mut builder = Client::builder();
+                if let Some(timeout) = self.connect_timeout {
+                    builder = builder.connect_timeout(timeout);
+                }
+                if let Some(timeout) = self.timeout {
+                    builder = builder.timeout(timeout);
+                }
+                // the gateway downloads don't get the default headers, so they are identified by the client
+                builder.user_agent(user_agent).build()?
+            }
+            // the browser fetch api doesn't allow to configure timeouts and the user-agent
+            #[cfg(target_arch = "wasm32")]
+            None => Client::builder().build()?,
+        };
+
+        let transport = self
+            .transport
+            .unwrap_or_else(|| Arc::new(ReqwestTransport::new(client.clone())));
+
+        Ok(NftStorage {
+            client,
+            transport,
+            url,
+            default_headers,
+            auth: self.auth.unwrap_or(Auth::ApiKey(self.token)),
+            retry_policy: self.retry_policy,
+            rate_limiter: self.rate_limiter,
+            rate_limit_status: Arc::default(),
+            wait_on_rate_limit: self.wait_on_rate_limit,
+            gateway: self.gateway,
+            link_gateways: self.link_gateways,
+            request_hooks: Vec::new(),
+            response_hooks: Vec::new(),
+            metadata_limits: self.metadata_limits,
+            max_request_size: self.max_request_size,
+            #[cfg(not(target_arch = "wasm32"))]
+            request_options: crate::types::RequestOptions::default(),
+            #[cfg(feature = "cache")]
+            cache: self
+                .cache_ttl
+                .map(|ttl| Arc::new(crate::cache::ResponseCache::new(ttl))),
+        })
+    }
+}
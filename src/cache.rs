@@ -0,0 +1,134 @@
+use crate::{
+    sync::lock,
+    time::Instant,
+    types::{CheckCidNftResponse, Cid, GetNftResponse},
+};
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::Duration,
+};
+
+/// Hits and misses of the cache, see [`crate::NftStorage::cache_stats`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub struct CacheStats {
+    /// responses returned from the cache
+    pub hits: u64,
+    /// responses not cached or expired, requested to the api
+    pub misses: u64,
+}
+
+/// In-memory cache of the `get_nft` and `check_nft` responses, keyed by cid
+///
+/// It is shared between the clones of the client, the entries expire after `ttl`
+pub(crate) struct ResponseCache {
+    /// time after which an entry expires
+    ttl: Duration,
+    /// responses of `get_nft`
+    nfts: Mutex<HashMap<String, (Instant, GetNftResponse)>>,
+    /// responses of `check_nft`
+    checks: Mutex<HashMap<String, (Instant, CheckCidNftResponse)>>,
+    /// responses returned from the cache
+    hits: AtomicU64,
+    /// responses not found in the cache
+    misses: AtomicU64,
+}
+
+/// the cached responses aren't printed, they can be many
+impl fmt::Debug for ResponseCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ResponseCache")
+            .field("ttl", &self.ttl)
+            .field("stats", &self.stats())
+            .finish()
+    }
+}
+
+impl ResponseCache {
+    /// Create an empty cache whose entries expire after `ttl`
+    pub(crate) fn new(ttl: Duration) -> ResponseCache {
+        ResponseCache {
+            ttl,
+            nfts: Mutex::new(HashMap::new()),
+            checks: Mutex::new(HashMap::new()),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// Cached `get_nft` response of `cid`
+    pub(crate) fn nft(&self, cid: &Cid) -> Option<GetNftResponse> {
+        self.lookup(&self.nfts, cid)
+    }
+
+    /// Cache the `get_nft` response of `cid`
+    pub(crate) fn insert_nft(&self, cid: &Cid, nft: GetNftResponse) {
+        insert(&self.nfts, cid, nft)
+    }
+
+    /// Cached `check_nft` response of `cid`
+    pub(crate) fn check(&self, cid: &Cid) -> Option<CheckCidNftResponse> {
+        self.lookup(&self.checks, cid)
+    }
+
+    /// Cache the `check_nft` response of `cid`
+    pub(crate) fn insert_check(&self, cid: &Cid, check: CheckCidNftResponse) {
+        insert(&self.checks, cid, check)
+    }
+
+    /// Remove the responses of `cid`, called after the requests changing it
+    pub(crate) fn invalidate(&self, cid: &Cid) {
+        let key = key(cid);
+        lock(&self.nfts).remove(&key);
+        lock(&self.checks).remove(&key);
+    }
+
+    /// Hits and misses since the cache was created
+    pub(crate) fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        }
+    }
+
+    /// get the entry of `cid` if it isn't expired, the expired one is removed
+    fn lookup<T>(&self, entries: &Mutex<HashMap<String, (Instant, T)>>, cid: &Cid) -> Option<T>
+    where
+        T: Clone,
+    {
+        let key = key(cid);
+        let mut entries = lock(entries);
+        let value = match entries.get(&key) {
+            Some((inserted_at, value)) if inserted_at.elapsed() < self.ttl => Some(value.clone()),
+            Some(_) => {
+                entries.remove(&key);
+                None
+            }
+            None => None,
+        };
+        let counter = if value.is_some() {
+            &self.hits
+        } else {
+            &self.misses
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        value
+    }
+}
+
+/// store `value` replacing the previous entry of `cid`
+fn insert<T>(entries: &Mutex<HashMap<String, (Instant, T)>>, cid: &Cid, value: T) {
+    lock(entries).insert(key(cid), (Instant::now(), value));
+}
+
+/// the entries are keyed by the CIDv1 so a cid requested as v0 matches the one returned by the uploads
+fn key(cid: &Cid) -> String {
+    cid.to_v1()
+        .map(|cid| cid.0)
+        .unwrap_or_else(|_| cid.0.clone())
+}
@@ -0,0 +1,320 @@
+use crate::{
+    cid::{block_cid, DAG_PB_CODEC, MAX_RAW_BLOCK_SIZE, RAW_CODEC},
+    types::{Cid, UploadFile},
+    NFTStorageError,
+};
+use ::cid::Cid as RawCid;
+use std::collections::{BTreeMap, HashSet};
+#[cfg(not(target_arch = "wasm32"))]
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+/// max size of a CAR sent in a single request
+pub const MAX_CAR_SHARD_SIZE: usize = 100 * 1024 * 1024;
+
+/// max links of a UnixFS file node, the same used by the go and js importers
+const MAX_LINKS: usize = 174;
+/// UnixFS node types
+const UNIXFS_DIRECTORY: u64 = 1;
+const UNIXFS_FILE: u64 = 2;
+
+/// block of a dag with its cid
+#[derive(Debug, Clone)]
+pub(crate) struct Block {
+    pub(crate) cid: RawCid,
+    pub(crate) data: Vec<u8>,
+}
+
+/// node of a file dag, `tsize` is the size of all the blocks below it
+#[derive(Debug, Clone)]
+struct FileNode {
+    cid: RawCid,
+    file_size: u64,
+    tsize: u64,
+}
+
+/// Build the UnixFS dag of a file from its chunks
+///
+/// The chunks are stored as raw leaves, when there is more than one chunk they are linked by balanced dag-pb nodes
+#[derive(Debug, Default)]
+pub(crate) struct FileBuilder {
+    leaves: Vec<FileNode>,
+}
+
+impl FileBuilder {
+    /// add the next chunk of the file returning its raw block
+    pub(crate) fn push_chunk(&mut self, chunk: Vec<u8>) -> Block {
+        let cid = block_cid(RAW_CODEC, &chunk);
+        let size = chunk.len() as u64;
+        self.leaves.push(FileNode {
+            cid,
+            file_size: size,
+            tsize: size,
+        });
+        Block { cid, data: chunk }
+    }
+
+    /// create the nodes linking the chunks returning the root cid, its `tsize` and the new blocks
+    pub(crate) fn finish(mut self) -> (RawCid, u64, Vec<Block>) {
+        let mut blocks = Vec::new();
+        // an empty file is a single empty raw block
+        if self.leaves.is_empty() {
+            blocks.push(self.push_chunk(Vec::new()));
+        }
+        let mut level = self.leaves;
+        while level.len() > 1 {
+            level = level
+                .chunks(MAX_LINKS)
+                .map(|children| {
+                    let file_size = children.iter().map(|c| c.file_size).sum();
+                    let block_sizes = children.iter().map(|c| c.file_size).collect::<Vec<_>>();
+                    let links = children
+                        .iter()
+                        .map(|c| (c.cid, String::new(), c.tsize))
+                        .collect::<Vec<_>>();
+                    let data = pb_node(
+                        &links,
+                        &unixfs_data(UNIXFS_FILE, Some(file_size), &block_sizes),
+                    );
+                    let cid = block_cid(DAG_PB_CODEC, &data);
+                    let tsize = data.len() as u64 + children.iter().map(|c| c.tsize).sum::<u64>();
+                    blocks.push(Block { cid, data });
+                    FileNode {
+                        cid,
+                        file_size,
+                        tsize,
+                    }
+                })
+                .collect();
+        }
+        let root = &level[0];
+        (root.cid, root.tsize, blocks)
+    }
+}
+
+/// entry of a directory being packed
+enum Entry {
+    File { cid: RawCid, tsize: u64 },
+    Directory(BTreeMap<String, Entry>),
+}
+
+/// Pack the files in a CAR as a UnixFS directory returning the CAR bytes and the cid of the directory
+///
+/// The names can contain `/` to put the files in sub directories, the files are split in chunks of 1 MiB.
+/// The root cid is known before uploading, so it can be compared with the one returned by `upload_car`
+/// ```
+/// use nft_storage::{car::pack_files_to_car, types::UploadFile};
+///
+/// let files = vec![
+///     UploadFile::new("hello.txt", b"hello".to_vec()),
+///     UploadFile::new("nested/world.txt", b"world".to_vec()),
+/// ];
+/// let (car, root) = pack_files_to_car(files).unwrap();
+/// assert_eq!(root.version(), Some(1));
+/// ```
+pub fn pack_files_to_car(files: Vec<UploadFile>) -> Result<(Vec<u8>, Cid), NFTStorageError> {
+    if files.is_empty() {
+        return Err(NFTStorageError::InvalidInput(
+            "at least one file is required".to_string(),
+        ));
+    }
+    let mut blocks = Vec::new();
+    let mut root = BTreeMap::new();
+    for file in files {
+        let path = file.name.split('/').collect::<Vec<_>>();
+        if path.iter().any(|p| p.is_empty()) {
+            return Err(NFTStorageError::InvalidInput(format!(
+                "invalid file name '{}'",
+                file.name
+            )));
+        }
+        let mut builder = FileBuilder::default();
+        let mut content = file.content;
+        while content.len() > MAX_RAW_BLOCK_SIZE {
+            let rest = content.split_off(MAX_RAW_BLOCK_SIZE);
+            blocks.push(builder.push_chunk(content));
+            content = rest;
+        }
+        if !content.is_empty() {
+            blocks.push(builder.push_chunk(content));
+        }
+        let (cid, tsize, file_blocks) = builder.finish();
+        blocks.extend(file_blocks);
+        insert_entry(&mut root, &path, Entry::File { cid, tsize }, &file.name)?;
+    }
+    let (root_cid, _) = directory_node(root, &mut blocks);
+
+    let mut car = car_header(&root_cid);
+    // the same content can be present more than once
+    let mut written = HashSet::new();
+    for block in blocks.iter().filter(|b| written.insert(b.cid)) {
+        car_block(block, &mut car);
+    }
+
+    Ok((car, Cid(root_cid.to_string())))
+}
+
+/// add the entry following its path, creating the sub directories
+fn insert_entry(
+    directory: &mut BTreeMap<String, Entry>,
+    path: &[&str],
+    entry: Entry,
+    name: &str,
+) -> Result<(), NFTStorageError> {
+    let duplicated = || NFTStorageError::InvalidInput(format!("duplicated file name '{}'", name));
+    match path {
+        [file_name] => {
+            if directory.contains_key(*file_name) {
+                return Err(duplicated());
+            }
+            directory.insert(file_name.to_string(), entry);
+            Ok(())
+        }
+        [directory_name, rest @ ..] => {
+            match directory
+                .entry(directory_name.to_string())
+                .or_insert_with(|| Entry::Directory(BTreeMap::new()))
+            {
+                Entry::Directory(sub_directory) => insert_entry(sub_directory, rest, entry, name),
+                Entry::File { .. } => Err(duplicated()),
+            }
+        }
+        [] => Err(duplicated()),
+    }
+}
+
+/// create the dag-pb node of a directory and its sub directories returning its cid and `tsize`
+fn directory_node(entries: BTreeMap<String, Entry>, blocks: &mut Vec<Block>) -> (RawCid, u64) {
+    // the BTreeMap keeps the links sorted by name like UnixFS requires
+    let links = entries
+        .into_iter()
+        .map(|(name, entry)| {
+            let (cid, tsize) = match entry {
+                Entry::File { cid, tsize } => (cid, tsize),
+                Entry::Directory(entries) => directory_node(entries, blocks),
+            };
+            (cid, name, tsize)
+        })
+        .collect::<Vec<_>>();
+    let data = pb_node(&links, &unixfs_data(UNIXFS_DIRECTORY, None, &[]));
+    let cid = block_cid(DAG_PB_CODEC, &data);
+    let tsize = data.len() as u64 + links.iter().map(|(_, _, tsize)| tsize).sum::<u64>();
+    blocks.push(Block { cid, data });
+    (cid, tsize)
+}
+
+/// CARv1 header with a single `root`, it is a varint prefixed dag-cbor map `{ roots: [root], version: 1 }`
+pub(crate) fn car_header(root: &RawCid) -> Vec<u8> {
+    let mut cid_bytes = vec![0];
+    cid_bytes.extend(root.to_bytes());
+
+    let mut header = vec![0xa2];
+    header.push(0x65);
+    header.extend(b"roots");
+    header.push(0x81);
+    // cids are tag 42 byte strings
+    header.extend([0xd8, 0x2a]);
+    cbor_bytes_len(&mut header, cid_bytes.len());
+    header.extend(cid_bytes);
+    header.push(0x67);
+    header.extend(b"version");
+    header.push(0x01);
+
+    let mut out = Vec::with_capacity(header.len() + 2);
+    put_varint(&mut out, header.len() as u64);
+    out.extend(header);
+    out
+}
+
+/// append a varint prefixed block to the CAR
+pub(crate) fn car_block(block: &Block, out: &mut Vec<u8>) {
+    let cid_bytes = block.cid.to_bytes();
+    put_varint(out, (cid_bytes.len() + block.data.len()) as u64);
+    out.extend(cid_bytes);
+    out.extend(&block.data);
+}
+
+/// encode a dag-pb node, the links are written before the data
+fn pb_node(links: &[(RawCid, String, u64)], data: &[u8]) -> Vec<u8> {
+    let mut node = Vec::new();
+    for (cid, name, tsize) in links {
+        let mut link = Vec::new();
+        put_bytes_field(&mut link, 1, &cid.to_bytes());
+        put_bytes_field(&mut link, 2, name.as_bytes());
+        put_varint_field(&mut link, 3, *tsize);
+        put_bytes_field(&mut node, 2, &link);
+    }
+    put_bytes_field(&mut node, 1, data);
+    node
+}
+
+/// encode the UnixFS data of a node
+fn unixfs_data(node_type: u64, file_size: Option<u64>, block_sizes: &[u64]) -> Vec<u8> {
+    let mut data = Vec::new();
+    put_varint_field(&mut data, 1, node_type);
+    if let Some(file_size) = file_size {
+        put_varint_field(&mut data, 3, file_size);
+    }
+    for block_size in block_sizes {
+        put_varint_field(&mut data, 4, *block_size);
+    }
+    data
+}
+
+fn put_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn put_varint_field(out: &mut Vec<u8>, field: u64, value: u64) {
+    put_varint(out, field << 3);
+    put_varint(out, value);
+}
+
+fn put_bytes_field(out: &mut Vec<u8>, field: u64, bytes: &[u8]) {
+    put_varint(out, field << 3 | 2);
+    put_varint(out, bytes.len() as u64);
+    out.extend(bytes);
+}
+
+/// cbor header of a byte string of `len` bytes
+fn cbor_bytes_len(out: &mut Vec<u8>, len: usize) {
+    match len {
+        0..=23 => out.push(0x40 | len as u8),
+        24..=0xff => out.extend([0x58, len as u8]),
+        _ => {
+            out.push(0x59);
+            out.extend((len as u16).to_be_bytes());
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+/// read the next chunk of at most `MAX_RAW_BLOCK_SIZE` bytes, it is empty at the end of the reader
+pub(crate) async fn read_chunk<R>(reader: &mut R) -> Result<Vec<u8>, NFTStorageError>
+where
+    R: AsyncRead + Unpin,
+{
+    let mut chunk = vec![0; MAX_RAW_BLOCK_SIZE];
+    let mut filled = 0;
+    while filled < MAX_RAW_BLOCK_SIZE {
+        let read = reader.read(&mut chunk[filled..]).await?;
+        if read == 0 {
+            break;
+        }
+        filled += read;
+    }
+    chunk.truncate(filled);
+    Ok(chunk)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+/// size of a block once written in the CAR
+pub(crate) fn car_block_len(block: &Block) -> usize {
+    let len = block.cid.encoded_len() + block.data.len();
+    let mut varint = Vec::new();
+    put_varint(&mut varint, len as u64);
+    varint.len() + len
+}
@@ -0,0 +1,48 @@
+use crate::types::Cid;
+use ::cid::{multihash::Multihash, Cid as RawCid};
+use sha2::{Digest, Sha256};
+
+/// multicodec code of the raw binary codec
+pub(crate) const RAW_CODEC: u64 = 0x55;
+/// multicodec code of the dag-pb codec used by the UnixFS files and directories
+#[cfg_attr(not(feature = "car"), allow(dead_code))]
+pub(crate) const DAG_PB_CODEC: u64 = 0x70;
+/// multihash code of sha2-256
+const SHA2_256: u64 = 0x12;
+
+/// max size of a file whose cid can be computed locally
+///
+/// nft storage splits the uploaded files in chunks of 1 MiB, a file up to this size is stored as a single raw
+/// block and its cid is the one returned by `compute_cid_v1_raw`. Bigger files are stored as a UnixFS dag whose
+/// root cid depends on the server side chunking, so it can't be predicted
+pub const MAX_RAW_BLOCK_SIZE: usize = 1024 * 1024;
+
+/// Compute the CIDv1 of `bytes` stored as a single raw block (sha2-256)
+///
+/// It matches the cid assigned by nft storage only for files up to `MAX_RAW_BLOCK_SIZE` bytes
+/// ```
+/// use nft_storage::cid::compute_cid_v1_raw;
+///
+/// let cid = compute_cid_v1_raw(b"hello world");
+/// assert_eq!(cid.as_str(), "bafkreifzjut3te2nhyekklss27nh3k72ysco7y32koao5eei66wof36n5e");
+/// ```
+pub fn compute_cid_v1_raw(bytes: &[u8]) -> Cid {
+    Cid(block_cid(RAW_CODEC, bytes).to_string())
+}
+
+/// Hex encoded sha2-256 digest of `bytes`, used to compare the content read back from a gateway
+pub(crate) fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// CIDv1 of a block encoded with `codec` (sha2-256)
+pub(crate) fn block_cid(codec: u64, bytes: &[u8]) -> RawCid {
+    let digest = Sha256::digest(bytes);
+    // a sha2-256 digest always fits in the 64 bytes multihash
+    let hash =
+        Multihash::<64>::wrap(SHA2_256, &digest).expect("sha2-256 digest fits in a multihash");
+    RawCid::new_v1(codec, hash)
+}
@@ -0,0 +1,60 @@
+//! Gzip compression of the uploads, enabled by the `gzip` feature
+//!
+//! Nft storage stores the compressed bytes as they are sent, so the cid is the one of the compressed content and the
+//! gateways serve it compressed, use [`gunzip`] after downloading it.
+//! ```
+//! use nft_storage::compress::{gunzip, gzip};
+//!
+//! let document = br#"{"name": "My NFT"}"#.repeat(100);
+//! let compressed = gzip(&document)?;
+//! assert!(compressed.len() < document.len());
+//! assert_eq!(gunzip(&compressed)?, document);
+//! # Ok::<(), std::io::Error>(())
+//! ```
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use std::io::{self, Read, Write};
+
+/// value of the `Content-Encoding` header of the compressed uploads
+#[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+pub(crate) const GZIP_ENCODING: &str = "gzip";
+
+/// Check if a content of type `mime` is worth compressing
+///
+/// Text, json, xml and javascript are compressed, the other types are usually already compressed (images, videos,
+/// archives). When the type is unknown the content is compressed only if it is valid utf-8, since the type of the
+/// text files can't be detected from their content
+/// ```
+/// use nft_storage::compress::is_compressible;
+///
+/// assert!(is_compressible(Some("application/json"), b"{}"));
+/// assert!(is_compressible(Some("text/csv; charset=utf-8"), b"a,b"));
+/// assert!(is_compressible(None, b"plain text"));
+/// assert!(!is_compressible(Some("image/png"), b"\x89PNG"));
+/// assert!(!is_compressible(None, &[0xff, 0xfe, 0x00]));
+/// ```
+pub fn is_compressible(mime: Option<&str>, content: &[u8]) -> bool {
+    match mime {
+        Some(mime) => {
+            let essence = mime.split(';').next().unwrap_or_default().trim();
+            essence.starts_with("text/")
+                || essence.ends_with("json")
+                || essence.ends_with("xml")
+                || essence == "application/javascript"
+        }
+        None => std::str::from_utf8(content).is_ok(),
+    }
+}
+
+/// Compress `content` with gzip
+pub fn gzip(content: &[u8]) -> io::Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(content)?;
+    encoder.finish()
+}
+
+/// Decompress a gzip `content`, like a compressed upload downloaded from a gateway
+pub fn gunzip(content: &[u8]) -> io::Result<Vec<u8>> {
+    let mut decompressed = Vec::new();
+    GzDecoder::new(content).read_to_end(&mut decompressed)?;
+    Ok(decompressed)
+}
@@ -1,14 +1,271 @@
+use crate::{
+    pins::PinningStatus,
+    types::{ApiErrorBody, Cid, PartialResult},
+    RateLimitInfo,
+};
 use serde_json::Value;
+use std::{convert::Infallible, time::Duration};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
+#[non_exhaustive]
 pub enum NFTStorageError {
     #[error("{0}")]
     InvalidRequest(#[from] reqwest::Error),
     #[error("Unable to parse json, {0}")]
     InvalidJson(#[from] serde_json::Error),
+    #[error("{}", api_error_message(*.status, .body, .error.as_deref()))]
+    ApiError {
+        status: u16,
+        /// raw json body of the failure
+        body: Value,
+        /// error object of the body, if it has the shape of the api failures
+        error: Option<Box<ApiErrorBody>>,
+        retry_after: Option<Duration>,
+        request_id: Option<String>,
+        /// rate limit state of a rate limited (429) request
+        rate_limit: Option<Box<RateLimitInfo>>,
+    },
+    #[error("Unauthorized, check that the api token is correct and not expired")]
+    Unauthorized,
+    #[error("Forbidden, check that the api token has access to this resource")]
+    Forbidden,
+    #[error("Unexpected response with status {status}: {body}")]
+    UnexpectedResponse { status: u16, body: String },
     #[error("{0}")]
-    ApiError(Value),
-    #[error("{0}")]
-    AnyhowError(#[from] anyhow::Error),
+    Io(#[from] std::io::Error),
+    #[error("Invalid input, {0}")]
+    InvalidInput(String),
+    #[error("Duplicated file name '{0}'")]
+    DuplicateFileName(String),
+    #[error("Invalid multipart form, {0}")]
+    Multipart(String),
+    #[error("Delete all stopped after {passes} listings without new nfts, {deleted} deleted and {failed} failed")]
+    DeleteAllStalled {
+        passes: usize,
+        deleted: usize,
+        failed: usize,
+    },
+    #[error("Nft not found: '{cid}'")]
+    NotFound { cid: String },
+    #[error("Invalid cid {0}")]
+    InvalidCid(String),
+    #[error("Not found on the gateway: '{0}'")]
+    GatewayNotFound(String),
+    #[error("Gateway timed out: '{0}'")]
+    GatewayTimeout(String),
+    #[error("Gateway responded with status {status}: '{url}'")]
+    GatewayError { status: u16, url: String },
+    #[error("All gateways failed, {}", display_failures(.0))]
+    AllGatewaysFailed(Vec<(String, NFTStorageError)>),
+    #[error("Uploaded cid '{actual}' doesn't match the expected cid '{expected}'")]
+    CidMismatch { expected: Cid, actual: Cid },
+    #[error("Pin of '{cid}' failed with status '{status}'")]
+    PinFailed { cid: String, status: PinningStatus },
+    #[error(
+        "Timed out after {elapsed:?} waiting for '{cid}' to be pinned, last status '{status}'"
+    )]
+    Timeout {
+        cid: String,
+        status: String,
+        elapsed: Duration,
+    },
+    #[error("Timed out after {elapsed:?} waiting for '{cid}' deals, {active_deals} of {min_active_deals} active")]
+    DealTimeout {
+        cid: String,
+        active_deals: usize,
+        min_active_deals: usize,
+        elapsed: Duration,
+    },
+    #[error("Content of '{cid}' read back from '{gateway}' has sha2-256 digest {actual}, expected {expected}")]
+    DigestMismatch {
+        cid: Cid,
+        gateway: String,
+        expected: String,
+        actual: String,
+    },
+    #[error("Cancelled, {0}")]
+    Cancelled(Box<PartialResult>),
+    #[error("Request timed out after {0:?}")]
+    RequestTimeout(Duration),
+    #[error("Deadline exceeded, {0}")]
+    DeadlineExceeded(Box<PartialResult>),
+    #[error("Invalid api url: '{0}'")]
+    InvalidUrl(String),
+    #[error("Missing api token, set the {0} environment variable")]
+    MissingToken(String),
+    #[error("Invalid api token, {0}")]
+    InvalidToken(String),
+    #[error("The service is read only, {message}")]
+    ServiceReadOnly { message: String },
+    #[error("Payload of {size} bytes exceeds the limit of {limit} bytes, upload big files in CAR shards with `upload_large`")]
+    PayloadTooLarge { size: u64, limit: u64 },
+}
+
+/// allow the conversions that can't fail where a `TryInto<Cid>` is accepted
+impl From<Infallible> for NFTStorageError {
+    fn from(e: Infallible) -> NFTStorageError {
+        match e {}
+    }
+}
+
+impl NFTStorageError {
+    /// http status code of the api response, if the error comes from one
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            NFTStorageError::ApiError { status, .. }
+            | NFTStorageError::UnexpectedResponse { status, .. } => Some(*status),
+            NFTStorageError::Unauthorized => Some(401),
+            NFTStorageError::Forbidden => Some(403),
+            _ => None,
+        }
+    }
+
+    /// id of the failed request assigned by the server, nft storage support asks for it when reporting a failure
+    pub fn request_id(&self) -> Option<&str> {
+        match self {
+            NFTStorageError::ApiError { request_id, .. } => request_id.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Error object of an api failure, with the name and the message reported by the api
+    ///
+    /// It is `None` if the body doesn't have the `{"ok": false, "error": {"name", "message"}}` shape, the raw json
+    /// is still in the `body` of the `ApiError`
+    /// ```
+    /// # #[cfg(feature = "test-util")]
+    /// # #[tokio::main]
+    /// # async fn main() -> anyhow::Result<()> {
+    /// use nft_storage::{test_util::MockTransport, NFTStorageError};
+    ///
+    /// let mock = MockTransport::new();
+    /// mock.push_response(400, vec![], r#"{"ok": false, "error": {"name": "HTTPError", "message": "Bad Request"}}"#);
+    /// mock.push_response(400, vec![], r#"{"message": "bad request"}"#);
+    /// let nft_storage = mock.client();
+    ///
+    /// let error = nft_storage.upload_file(b"hello".to_vec()).await.unwrap_err();
+    /// let body = error.api_error().unwrap();
+    /// assert_eq!((body.ok, body.name.as_str(), body.message.as_str()), (false, "HTTPError", "Bad Request"));
+    /// assert_eq!(error.to_string(), "HTTPError: Bad Request (status 400)");
+    ///
+    /// // a body with another shape is kept as raw json
+    /// let error = nft_storage.upload_file(b"hello".to_vec()).await.unwrap_err();
+    /// assert!(error.api_error().is_none());
+    /// assert!(matches!(&error, NFTStorageError::ApiError { body, .. } if body["message"] == "bad request"));
+    /// assert_eq!(error.to_string(), r#"Api error 400: {"message":"bad request"}"#);
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "test-util"))]
+    /// # fn main() {}
+    /// ```
+    pub fn api_error(&self) -> Option<&ApiErrorBody> {
+        match self {
+            NFTStorageError::ApiError { error, .. } => error.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Rate limit state reported by the api when the request was rate limited (429), use
+    /// [`RateLimitInfo::reset_in`] to schedule the retry
+    pub fn rate_limit(&self) -> Option<&RateLimitInfo> {
+        match self {
+            NFTStorageError::ApiError { rate_limit, .. } => rate_limit.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// true if the api rejected the token (401)
+    pub fn is_unauthorized(&self) -> bool {
+        self.status() == Some(401)
+    }
+
+    /// true if the api accepts only the read requests, like during a maintenance or the decommission of the service
+    ///
+    /// The list, get and check requests keep working, the uploads can fail over to another [`IpfsStorageProvider`]
+    /// ```
+    /// # #[cfg(feature = "test-util")]
+    /// # #[tokio::main]
+    /// # async fn main() -> anyhow::Result<()> {
+    /// use nft_storage::{test_util::{fixtures, MockTransport}, types::*, NftStorage, RetryPolicy};
+    ///
+    /// let mock = MockTransport::new();
+    /// mock.push_response(503, vec![], fixtures::MAINTENANCE_RESPONSE);
+    /// mock.push_response(200, vec![], fixtures::LIST_RESPONSE);
+    /// let nft_storage = NftStorage::builder("https://api.nft.storage", "token")
+    ///     .retry_policy(RetryPolicy::none())
+    ///     .transport(mock)
+    ///     .build()?;
+    ///
+    /// let error = nft_storage.upload_file(b"hello".to_vec()).await.unwrap_err();
+    /// assert!(error.is_read_only());
+    /// assert_eq!(error.to_string(), "The service is read only, API undergoing maintenance, please try again later");
+    /// // the stored nfts can still be listed
+    /// assert_eq!(nft_storage.list_with_options(ListOptions::default()).await?.value.len(), 1);
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "test-util"))]
+    /// # fn main() {}
+    /// ```
+    ///
+    /// [`IpfsStorageProvider`]: crate::IpfsStorageProvider
+    pub fn is_read_only(&self) -> bool {
+        matches!(self, NFTStorageError::ServiceReadOnly { .. })
+    }
+
+    /// true if the api rate limited the request (429)
+    pub fn is_rate_limited(&self) -> bool {
+        self.status() == Some(429)
+    }
+
+    /// true if the error comes from an ipfs gateway and not from the api
+    pub fn is_gateway_error(&self) -> bool {
+        matches!(
+            self,
+            NFTStorageError::GatewayNotFound(_)
+                | NFTStorageError::GatewayTimeout(_)
+                | NFTStorageError::GatewayError { .. }
+                | NFTStorageError::AllGatewaysFailed(_)
+        )
+    }
+
+    /// Set the work completed by an operation in a `DeadlineExceeded` error, the other errors are returned as they are
+    pub(crate) fn with_partial_result<F>(self, partial: F) -> NFTStorageError
+    where
+        F: FnOnce() -> PartialResult,
+    {
+        match self {
+            NFTStorageError::DeadlineExceeded(_) => {
+                NFTStorageError::DeadlineExceeded(Box::new(partial()))
+            }
+            e => e,
+        }
+    }
+
+    /// true if the nft or the file doesn't exist
+    pub fn is_not_found(&self) -> bool {
+        matches!(
+            self,
+            NFTStorageError::NotFound { .. } | NFTStorageError::GatewayNotFound(_)
+        ) || self.status() == Some(404)
+    }
+}
+
+/// join the failures as `gateway: error`
+fn display_failures(failures: &[(String, NFTStorageError)]) -> String {
+    failures
+        .iter()
+        .map(|(gateway, error)| format!("{}: {}", gateway, error))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// message of an `ApiError`, like `HTTPError: Unauthorized (status 401)`, the raw body is shown if it doesn't have
+/// the shape of the api failures
+fn api_error_message(status: u16, body: &Value, error: Option<&ApiErrorBody>) -> String {
+    match error {
+        Some(error) if error.message.is_empty() => format!("{} (status {})", error.name, status),
+        Some(error) => format!("{}: {} (status {})", error.name, error.message, status),
+        None => format!("Api error {}: {}", status, body),
+    }
 }
@@ -0,0 +1,40 @@
+use crate::RateLimitInfo;
+use reqwest::{Method, Request, Response, Url};
+use std::{sync::Arc, time::Duration};
+
+/// Hook called before sending every request, see [`NftStorage::with_request_hook`](crate::NftStorage::with_request_hook)
+pub type RequestHook = Arc<dyn Fn(&mut Request) + Send + Sync>;
+
+/// Hook called after every request, see [`NftStorage::with_response_hook`](crate::NftStorage::with_response_hook)
+pub type ResponseHook = Arc<dyn Fn(&ResponseMeta) + Send + Sync>;
+
+/// Data of a completed request passed to the response hooks
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct ResponseMeta {
+    /// http method of the request
+    pub method: Method,
+    /// url of the request
+    pub url: Url,
+    /// status of the response, `None` when the request failed without a response
+    pub status: Option<u16>,
+    /// time from sending the request to receiving the response headers
+    pub elapsed: Duration,
+    /// id of the request assigned by the server (`x-request-id` or `cf-ray` header), nft storage support asks for
+    /// it when reporting a failed request
+    pub request_id: Option<String>,
+    /// rate limit state reported by the response headers
+    pub rate_limit: Option<RateLimitInfo>,
+}
+
+/// headers identifying a request, in order of preference
+const REQUEST_ID_HEADERS: [&str; 2] = ["x-request-id", "cf-ray"];
+
+/// id of the request assigned by the server, read from the first identifying header found
+pub(crate) fn request_id(response: &Response) -> Option<String> {
+    REQUEST_ID_HEADERS
+        .iter()
+        .find_map(|name| response.headers().get(*name))
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.to_string())
+}
@@ -0,0 +1,334 @@
+//! Client of a local Kubo (go-ipfs) node, available with the `kubo` feature
+//!
+//! It uses the rpc api of the node, so the uploads can be tried during development without an api token or a
+//! network connection. The client implements [`IpfsStorageProvider`] like [`NftStorage`], the content is added with
+//! CIDv1 to get the same cids returned by nft storage
+//! ```no_run
+//! use nft_storage::{kubo::KuboClient, types::UploadFile, IpfsStorageProvider};
+//! use anyhow::Result;
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<()> {
+//!     // the node started with `ipfs daemon`
+//!     let kubo = KuboClient::new()?;
+//!     let stored = kubo.upload_file(UploadFile::new("hello.txt", b"hello".to_vec())).await?;
+//!     println!("{}", stored.cid);
+//!
+//!     Ok(())
+//! }
+//! ```
+use crate::{
+    provider::{IpfsStorageProvider, ProviderFuture, StoredObject},
+    types::{Cid, Timestamp, UploadFile},
+    NFTStorageError, NftStorage,
+};
+use futures::{StreamExt, TryStreamExt};
+use reqwest::multipart::{Form, Part};
+use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashSet};
+
+/// default rpc api endpoint of a local node
+pub const KUBO_API_URL: &str = "http://127.0.0.1:5001";
+
+/// default gateway of a local node
+pub const KUBO_GATEWAY_URL: &str = "http://127.0.0.1:8080/ipfs/";
+
+/// number of `files/stat` requests sent at the same time while listing the pins
+const STAT_CONCURRENCY: usize = 8;
+
+/// content added by `add`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(default, rename_all = "PascalCase")]
+#[non_exhaustive]
+pub struct AddedContent {
+    /// file name, it is empty for the directory wrapping the files
+    pub name: String,
+    /// cid of the content
+    pub hash: Cid,
+    /// size of the whole dag
+    #[serde(deserialize_with = "deserialize_size")]
+    pub size: u64,
+}
+
+/// response of `pin/ls`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(default, rename_all = "PascalCase")]
+#[non_exhaustive]
+pub struct PinLsResponse {
+    /// pinned cids with their pin
+    pub keys: BTreeMap<String, PinInfo>,
+}
+
+/// pin returned by `pin/ls`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(default, rename_all = "PascalCase")]
+#[non_exhaustive]
+pub struct PinInfo {
+    /// `recursive`, `direct` or `indirect`
+    #[serde(rename = "Type")]
+    pub pin_type: String,
+}
+
+/// response of `pin/rm`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(default, rename_all = "PascalCase")]
+#[non_exhaustive]
+pub struct PinRmResponse {
+    /// unpinned cids
+    pub pins: Vec<Cid>,
+}
+
+/// response of `files/stat`
+/// ```
+/// use nft_storage::kubo::FilesStat;
+///
+/// let stat: FilesStat = serde_json::from_str(r#"{
+///     "Hash": "bafkreibm6jg3ux5qumhcn2b3flc3tyu6dmlb4xa7u5bf44yegnrjhc4yeq",
+///     "Size": 5,
+///     "CumulativeSize": 5,
+///     "Blocks": 0,
+///     "Type": "file"
+/// }"#).unwrap();
+///
+/// assert_eq!(stat.cumulative_size, 5);
+/// assert_eq!(stat.file_type, "file");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(default, rename_all = "PascalCase")]
+#[non_exhaustive]
+pub struct FilesStat {
+    /// cid of the content
+    pub hash: Cid,
+    /// size of the file, 0 for directories
+    pub size: u64,
+    /// size of the whole dag
+    pub cumulative_size: u64,
+    /// number of links of the root node
+    pub blocks: u64,
+    /// `file` or `directory`
+    #[serde(rename = "Type")]
+    pub file_type: String,
+}
+
+/// Kubo reports some sizes as strings
+fn deserialize_size<'de, D>(deserializer: D) -> Result<u64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match Value::deserialize(deserializer)? {
+        Value::Number(size) => size
+            .as_u64()
+            .ok_or_else(|| serde::de::Error::custom(format!("invalid size {}", size))),
+        Value::String(size) => size
+            .parse()
+            .map_err(|_| serde::de::Error::custom(format!("invalid size '{}'", size))),
+        _ => Ok(0),
+    }
+}
+
+/// Client of the rpc api of a Kubo node
+///
+/// The node doesn't record when the content was added, so the `created` date of the [`StoredObject`]s is the
+/// default timestamp. Cloning it is cheap, the clones share the connection pool
+#[derive(Debug, Clone)]
+pub struct KuboClient {
+    /// client running the requests, its url is the node rpc api
+    inner: NftStorage,
+}
+
+impl KuboClient {
+    /// Create a client of the node listening on `http://127.0.0.1:5001`
+    pub fn new() -> Result<KuboClient, NFTStorageError> {
+        KuboClient::with_url(KUBO_API_URL)
+    }
+
+    /// Create a client of the node with the rpc api listening on `url`
+    pub fn with_url<S>(url: S) -> Result<KuboClient, NFTStorageError>
+    where
+        S: Into<String>,
+    {
+        // a local node doesn't need a token, an empty one sends no authorization header
+        let inner = NftStorage::builder(url.into(), String::new())
+            .gateway(KUBO_GATEWAY_URL)
+            .build()?;
+        Ok(KuboClient::from_client(inner))
+    }
+
+    /// Create a client from an already configured client, its url must be the node rpc api
+    pub fn from_client(inner: NftStorage) -> KuboClient {
+        KuboClient { inner }
+    }
+
+    /// The client used to run the requests
+    pub fn inner(&self) -> &NftStorage {
+        &self.inner
+    }
+
+    /// Add and pin a single file
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn add(&self, file: UploadFile) -> Result<AddedContent, NFTStorageError> {
+        let form = Form::new().part("file", file.into_part()?);
+        self.add_form(form, false).await
+    }
+
+    /// Add and pin the files wrapped in a directory, the file names can contain `/` to create sub directories
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn add_directory(
+        &self,
+        mut files: Vec<UploadFile>,
+    ) -> Result<AddedContent, NFTStorageError> {
+        if files.is_empty() {
+            return Err(NFTStorageError::InvalidInput(
+                "at least one file is required".to_string(),
+            ));
+        }
+        // the node reads the parts in order, every directory must come before its files
+        files.sort_by(|a, b| a.name.split('/').cmp(b.name.split('/')));
+        let mut form = Form::new();
+        let mut directories = HashSet::new();
+        for file in files {
+            if let Some((parent, _)) = file.name.rsplit_once('/') {
+                let mut path = String::new();
+                for directory in parent.split('/') {
+                    if !path.is_empty() {
+                        path.push('/');
+                    }
+                    path.push_str(directory);
+                    if directories.insert(path.clone()) {
+                        let part = Part::bytes(Vec::new())
+                            .file_name(path.clone())
+                            .mime_str("application/x-directory")?;
+                        form = form.part("file", part);
+                    }
+                }
+            }
+            form = form.part("file", file.into_part()?);
+        }
+        self.add_form(form, true).await
+    }
+
+    /// List the recursive pins, or only `cid` when it is set
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn pin_ls(&self, cid: Option<&Cid>) -> Result<PinLsResponse, NFTStorageError> {
+        let mut query = vec![("type", "recursive".to_string())];
+        if let Some(cid) = cid {
+            query.push(("arg", cid.to_string()));
+        }
+        self.rpc("pin/ls", &query).await
+    }
+
+    /// Remove the pin of `cid`, the blocks are deleted by the next garbage collection of the node
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn pin_rm<C>(&self, cid: C) -> Result<PinRmResponse, NFTStorageError>
+    where
+        C: TryInto<Cid>,
+        NFTStorageError: From<C::Error>,
+    {
+        let cid: Cid = cid.try_into()?;
+        self.rpc("pin/rm", &[("arg", cid.to_string())]).await
+    }
+
+    /// Get the size and the type of `cid`
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn files_stat<C>(&self, cid: C) -> Result<FilesStat, NFTStorageError>
+    where
+        C: TryInto<Cid>,
+        NFTStorageError: From<C::Error>,
+    {
+        let cid: Cid = cid.try_into()?;
+        self.rpc("files/stat", &[("arg", format!("/ipfs/{}", cid))])
+            .await
+    }
+
+    /// Send an `add` request, `quieter` makes the node return only the root of the added content
+    async fn add_form(&self, form: Form, wrap: bool) -> Result<AddedContent, NFTStorageError> {
+        let query = [
+            ("cid-version", "1"),
+            ("pin", "true"),
+            ("quieter", "true"),
+            ("wrap-with-directory", if wrap { "true" } else { "false" }),
+        ];
+        let url = self.inner.endpoint("api/v0/add");
+        let request = self.inner.client.post(url).query(&query).multipart(form);
+        self.inner.request(request).await
+    }
+
+    /// Send a request to the rpc api, all the methods use `POST`
+    async fn rpc<T>(&self, path: &str, query: &[(&str, String)]) -> Result<T, NFTStorageError>
+    where
+        T: DeserializeOwned,
+    {
+        let url = self.inner.endpoint(&format!("api/v0/{}", path));
+        let request = self.inner.client.post(url).query(query);
+        self.inner.request(request).await
+    }
+
+    /// Get the stored object of a pinned `cid`
+    async fn stored_object(&self, cid: Cid) -> Result<StoredObject, NFTStorageError> {
+        let stat = self.files_stat(&cid).await?;
+        Ok(StoredObject::new(
+            cid,
+            stat.cumulative_size,
+            Timestamp::default(),
+        ))
+    }
+}
+
+impl IpfsStorageProvider for KuboClient {
+    fn upload_file(&self, file: UploadFile) -> ProviderFuture<'_, StoredObject> {
+        Box::pin(async move {
+            let added = self.add(file).await?;
+            Ok(StoredObject::new(
+                added.hash,
+                added.size,
+                Timestamp::default(),
+            ))
+        })
+    }
+
+    fn upload_directory(&self, files: Vec<UploadFile>) -> ProviderFuture<'_, StoredObject> {
+        Box::pin(async move {
+            let added = self.add_directory(files).await?;
+            Ok(StoredObject::new(
+                added.hash,
+                added.size,
+                Timestamp::default(),
+            ))
+        })
+    }
+
+    fn list(&self) -> ProviderFuture<'_, Vec<StoredObject>> {
+        Box::pin(async move {
+            let pins = self.pin_ls(None).await?;
+            futures::stream::iter(pins.keys.into_keys())
+                .map(|cid| self.stored_object(Cid(cid)))
+                .buffer_unordered(STAT_CONCURRENCY)
+                .try_collect()
+                .await
+        })
+    }
+
+    fn delete<'a>(&'a self, cid: &'a Cid) -> ProviderFuture<'a, ()> {
+        Box::pin(async move { self.pin_rm(cid).await.map(|_| ()) })
+    }
+
+    fn status<'a>(&'a self, cid: &'a Cid) -> ProviderFuture<'a, StoredObject> {
+        Box::pin(async move {
+            // the node responds with an error instead of an empty list when the cid isn't pinned
+            match self.pin_ls(Some(cid)).await {
+                Ok(_) => self.stored_object(cid.clone()).await,
+                Err(NFTStorageError::ApiError { body, .. })
+                    if body["Message"]
+                        .as_str()
+                        .is_some_and(|message| message.contains("not pinned")) =>
+                {
+                    Err(NFTStorageError::NotFound {
+                        cid: cid.to_string(),
+                    })
+                }
+                Err(e) => Err(e),
+            }
+        })
+    }
+}
@@ -1,24 +1,233 @@
+mod auth;
+#[cfg(all(feature = "blocking", not(target_arch = "wasm32")))]
+pub mod blocking;
+mod builder;
+#[cfg(feature = "cache")]
+mod cache;
+#[cfg(feature = "car")]
+pub mod car;
+pub mod cid;
+#[cfg(feature = "gzip")]
+pub mod compress;
 mod error;
+mod hooks;
+#[cfg(feature = "kubo")]
+pub mod kubo;
+#[cfg(all(any(feature = "axum", feature = "actix"), not(target_arch = "wasm32")))]
+pub mod multipart;
+#[cfg(feature = "pinata")]
+pub mod pinata;
+pub mod pins;
+mod provider;
+mod rate_limit;
+mod raw;
+mod retry;
+mod sync;
+#[cfg(all(feature = "test-util", not(target_arch = "wasm32")))]
+pub mod test_util;
+mod time;
+mod transport;
 pub mod types;
+#[cfg(feature = "web3-storage")]
+pub mod web3_storage;
+pub use crate::auth::Auth;
+pub use crate::builder::NftStorageBuilder;
+#[cfg(feature = "cache")]
+pub use crate::cache::CacheStats;
+#[cfg(feature = "cache")]
+use crate::cache::ResponseCache;
 pub use crate::error::NFTStorageError;
+pub use crate::hooks::{RequestHook, ResponseHook, ResponseMeta};
+use crate::pins::PinningStatus;
+pub use crate::provider::{IpfsStorageProvider, ProviderFuture, StoredObject};
+pub use crate::rate_limit::{
+    RateLimitInfo, RateLimiter, NFT_STORAGE_RATE_LIMIT_INTERVAL, NFT_STORAGE_RATE_LIMIT_PERMITS,
+};
+pub use crate::raw::RawBody;
+use crate::retry::retry_after;
+pub use crate::retry::RetryPolicy;
+use crate::time::{sleep, timeout, Instant};
+pub use crate::transport::{HttpTransport, ReqwestTransport, TransportFuture};
 use crate::types::*;
-use anyhow::Result;
+use bytes::Bytes;
+#[cfg(not(target_arch = "wasm32"))]
+use futures::TryStream;
+use futures::{future, stream, stream::FuturesUnordered, Stream, StreamExt, TryStreamExt};
+#[cfg(not(target_arch = "wasm32"))]
+use reqwest::multipart::Part;
 use reqwest::{
-    multipart::{Form, Part},
-    Client,
+    header::{HeaderMap, HeaderValue, CONTENT_LENGTH, CONTENT_TYPE, USER_AGENT},
+    multipart::Form,
+    Body, Client, Request, RequestBuilder, Response, StatusCode,
+};
+use serde::{
+    de::{DeserializeOwned, IgnoredAny},
+    Serialize,
 };
 use serde_json::{json, Value};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+#[cfg(not(target_arch = "wasm32"))]
+use std::{
+    io::SeekFrom,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+};
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+#[cfg(not(target_arch = "wasm32"))]
+use tokio::{
+    fs::File,
+    io::{AsyncRead, AsyncReadExt, AsyncSeekExt},
+};
+#[cfg(not(target_arch = "wasm32"))]
+use tokio_util::io::ReaderStream;
+
+/// default nft storage rest api endpoint
+pub const DEFAULT_API_URL: &str = "https://api.nft.storage";
+/// environment variable containing the nft storage rest api endpoint
+pub const API_URL_ENV: &str = "NFT_STORAGE_API_URL";
+/// environment variable containing the nft storage api token
+pub const API_TOKEN_ENV: &str = "NFT_STORAGE_API_TOKEN";
+
+/// user-agent sent with every api request, an application can append its own identifier with
+/// [`NftStorageBuilder::app_identifier`]
+pub const DEFAULT_USER_AGENT: &str = concat!("nft-storage-rs/", env!("CARGO_PKG_VERSION"));
+
+/// default ipfs gateway used to download files
+pub const DEFAULT_GATEWAY_URL: &str = "https://nftstorage.link/ipfs/";
+
+/// content type of the CAR uploads
+const CAR_CONTENT_TYPE: &str = "application/car";
+
+/// content type of the json documents
+const JSON_CONTENT_TYPE: &str = "application/json";
+
+/// content type of the text uploads
+const TEXT_CONTENT_TYPE: &str = "text/plain; charset=utf-8";
+
+/// content type of the html uploads
+const HTML_CONTENT_TYPE: &str = "text/html; charset=utf-8";
+
+#[cfg(not(target_arch = "wasm32"))]
+/// bytes read from the start of a file to detect its mime type
+const MIME_DETECTION_LEN: usize = 8192;
+
+/// max number of files accepted in a single directory upload
+pub const MAX_DIRECTORY_FILES: usize = 10_000;
+
+/// default max size of the body of a single upload request, it matches the api limit of 100 MiB
+pub const DEFAULT_MAX_REQUEST_SIZE: u64 = 100 * 1024 * 1024;
+
+/// max size of the content stored with a single upload, also when it is split in many requests
+pub const MAX_CONTENT_SIZE: u64 = 31 * 1024 * 1024 * 1024;
+
+/// code of the errors returned by the api when it is read only
+const READ_ONLY_ERROR_CODE: &str = "ERROR_MAINTENANCE";
+
+/// name of the errors returned by the api when it is read only
+const READ_ONLY_ERROR_NAME: &str = "MAINTENANCE";
+
+/// max length of a non json response body kept in the errors
+const MAX_ERROR_BODY_LEN: usize = 1024;
+
+/// max number of nfts returned by a single list request
+pub const MAX_LIST_LIMIT: u32 = 100;
+
+/// columns of the csv written by `export_listing`
+const CSV_HEADER: &str = "cid,size,created,pin_status,deals,gateway_url,files\n";
+
+/// delay between two polls of `wait_for_deal`, the deals are made in hours or days
+pub const DEAL_POLL_INTERVAL: Duration = Duration::from_secs(10 * 60);
 
 /// NftStorage struct
+///
+/// Cloning it is cheap, the clones share the connection pool, the transport, the hooks, the rate limiter and the cache.
+/// The client is `Send + Sync`, a clone can be moved into every spawned task or shared behind an `Arc`,
+/// the shared state is only locked for the short bookkeeping of the rate limiter and the cache, never across a request,
+/// see the `concurrent_upload` example
+#[derive(Clone)]
 pub struct NftStorage {
-    /// reqwest client instance
-    pub client: Client,
+    /// reqwest client instance used to build the requests
+    pub(crate) client: Client,
+    /// transport sending the requests, by default it uses `client`
+    pub(crate) transport: Arc<dyn HttpTransport>,
     /// nft storage rest api endpoint
-    pub url: String,
-    /// nft storage api token
-    pub token: String,
+    pub(crate) url: String,
+    /// headers sent with every api request, like the token of a proxy
+    pub(crate) default_headers: HeaderMap,
+    /// credentials used to authorize the requests, the api token by default
+    pub(crate) auth: Auth,
+    /// policy used to retry rate limited and failed requests
+    pub(crate) retry_policy: RetryPolicy,
+    /// optional rate limiter awaited before sending every request, it is shared between the clones
+    pub(crate) rate_limiter: Option<Arc<RateLimiter>>,
+    /// rate limit state reported by the last api response, it is shared between the clones
+    pub(crate) rate_limit_status: Arc<Mutex<Option<RateLimitInfo>>>,
+    /// wait for the reset of the rate limit window when no requests are left instead of sending them
+    pub(crate) wait_on_rate_limit: bool,
+    /// ipfs gateway used to download files
+    pub(crate) gateway: String,
+    /// additional gateways used to create the `custom` links of the nfts, the first one is used by the other links
+    pub(crate) link_gateways: Vec<Gateway>,
+    /// hooks called in order before sending every request
+    pub(crate) request_hooks: Vec<RequestHook>,
+    /// hooks called in order after every request
+    pub(crate) response_hooks: Vec<ResponseHook>,
+    /// limits checked on the metadata before storing an nft
+    pub(crate) metadata_limits: MetadataLimits,
+    /// max size of the body of a single upload request, bigger uploads are rejected before sending them
+    pub(crate) max_request_size: u64,
+    /// timeout and deadline of the api requests
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) request_options: RequestOptions,
+    /// optional cache of the `get_nft` and `check_nft` responses, it is shared between the clones
+    #[cfg(feature = "cache")]
+    pub(crate) cache: Option<Arc<ResponseCache>>,
+}
+
+/// the token is redacted so the client can be logged and recorded by `tracing::instrument`
+impl fmt::Debug for NftStorage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug = f.debug_struct("NftStorage");
+        debug
+            .field("url", &self.url)
+            // the values can contain credentials, only the names are printed
+            .field(
+                "default_headers",
+                &self.default_headers.keys().collect::<Vec<_>>(),
+            )
+            .field("auth", &self.auth)
+            .field("transport", &self.transport)
+            .field("retry_policy", &self.retry_policy)
+            .field("rate_limiter", &self.rate_limiter)
+            .field("wait_on_rate_limit", &self.wait_on_rate_limit)
+            .field("gateway", &self.gateway)
+            .field("link_gateways", &self.link_gateways)
+            .field("request_hooks", &self.request_hooks.len())
+            .field("response_hooks", &self.response_hooks.len())
+            .field("metadata_limits", &self.metadata_limits)
+            .field("max_request_size", &self.max_request_size);
+        #[cfg(not(target_arch = "wasm32"))]
+        debug.field("request_options", &self.request_options);
+        #[cfg(feature = "cache")]
+        debug.field("cache", &self.cache);
+        debug.finish()
+    }
 }
 
+// the clients and the errors must stay usable from spawned tasks and threads
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<NftStorage>();
+    assert_send_sync::<NFTStorageError>();
+    #[cfg(feature = "blocking")]
+    assert_send_sync::<blocking::NftStorage>();
+};
+
 /// Nft storage methods
 impl NftStorage {
     /// Create a new instance of NftStorage
@@ -26,16 +235,16 @@ impl NftStorage {
     /// The `url` is the url of the api which nftt storage is using for more information see https://nft.storage/api-docs/.
     ///
     /// The `token` is the jwt token generated from nft storage dashboard.
-    /// ```
+    /// ```no_run
     /// use nft_storage::NftStorage;
     /// use anyhow::Result;
     ///
     /// #[tokio::main]
     /// async fn main() -> Result<()> {
-    /// 	// provide the url and as second argument the token generated from nft storage dashboard
-    /// 	let nft_storage = NftStorage::new("https://api.nft.storage", "token generated from nft storage");
+    ///     // provide the url and as second argument the token generated from nft storage dashboard
+    ///     let nft_storage = NftStorage::new("https://api.nft.storage", "token generated from nft storage");
     ///
-    /// 	Ok(())
+    ///     Ok(())
     /// }
     /// ```
     ///
@@ -43,484 +252,5073 @@ impl NftStorage {
     where
         S: Into<String>,
     {
+        let client = Client::new();
         NftStorage {
-            client: Client::new(),
-            url: url.into(),
-            token: token.into(),
+            transport: Arc::new(ReqwestTransport::new(client.clone())),
+            client,
+            url: url.into().trim_end_matches('/').to_string(),
+            default_headers: HeaderMap::from_iter([(
+                USER_AGENT,
+                HeaderValue::from_static(DEFAULT_USER_AGENT),
+            )]),
+            auth: Auth::ApiKey(token.into()),
+            retry_policy: RetryPolicy::default(),
+            rate_limiter: None,
+            rate_limit_status: Arc::default(),
+            wait_on_rate_limit: false,
+            gateway: DEFAULT_GATEWAY_URL.to_string(),
+            link_gateways: Vec::new(),
+            request_hooks: Vec::new(),
+            response_hooks: Vec::new(),
+            metadata_limits: MetadataLimits::default(),
+            max_request_size: DEFAULT_MAX_REQUEST_SIZE,
+            #[cfg(not(target_arch = "wasm32"))]
+            request_options: RequestOptions::default(),
+            #[cfg(feature = "cache")]
+            cache: None,
         }
     }
 
-    /// List all nfts from nft storage
-    /// `before` is used to return results created before provided timestamp `2021-12-01T08:52:33` or like this `2020-07-27T17:32:28Z` which is  and `limit` are the max records to return.
+    /// Url of the api used by the client
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Url of `cid` on the first link gateway, or on the download gateway when no link gateway is configured
+    pub(crate) fn gateway_url(&self, cid: &str, path: &str) -> String {
+        match self.link_gateways.first() {
+            Some(gateway) => gateway.url(cid, path),
+            None => Gateway::path(&self.gateway).url(cid, path),
+        }
+    }
+
+    /// Gateway url of `cid`, the one to share to retrieve the content
     ///
-    /// the `only_metadata` option is used to return only the nft which contains the metadata.json file
+    /// It uses the first gateway set with [`NftStorageBuilder::gateways`], or the download gateway when none is
+    /// configured. The subdomain gateways get the cid as base32 CIDv1, a cid that can't be converted is linked in the
+    /// path of the gateway instead of returning an error
     /// ```
-    /// use nft_storage::{NftStorage, types::*}
-    /// use anyhow::Result;
+    /// use nft_storage::{types::Gateway, NftStorage};
     ///
-    /// #[tokio::main]
-    /// async fn main() -> Result<()> {
-    /// 	// provide the url and as second argument the token generated from nft storage dashboard
-    /// 	let nft_storage = NftStorage::new("https://api.nft.storage", "token generated from nft storage");
-    /// 	// list nfts only with metadata
-    /// 	let list_nfts: ListNftResponse  = nft_storage.list_all_stored_nft(None, None, true).await?;
+    /// let nft_storage = NftStorage::builder("https://api.nft.storage", "token")
+    ///     .gateways(vec![Gateway::subdomain("https://nftstorage.link")])
+    ///     .build()
+    ///     .unwrap();
+    /// let url = nft_storage
+    ///     .preferred_gateway_url("QmWATWQ7fVPP2EFGu71UkfnqhYXDYH566qy47CnJDgvs8u")
+    ///     .unwrap();
+    /// assert_eq!(
+    ///     url,
+    ///     "https://bafybeiduiecxoeiqs3gyc6r7v3lymmhserldnpw62qjnhmqsulqjxjmtzi.ipfs.nftstorage.link"
+    /// );
     ///
-    /// 	Ok(())
-    /// }
+    /// let nft_storage = NftStorage::new("https://api.nft.storage", "token");
+    /// let url = nft_storage
+    ///     .preferred_gateway_url("QmWATWQ7fVPP2EFGu71UkfnqhYXDYH566qy47CnJDgvs8u")
+    ///     .unwrap();
+    /// assert_eq!(url, "https://nftstorage.link/ipfs/QmWATWQ7fVPP2EFGu71UkfnqhYXDYH566qy47CnJDgvs8u");
+    /// assert!(nft_storage.preferred_gateway_url("not a cid").is_err());
     /// ```
-    ///
-    pub async fn list_all_stored_nft(
-        &self,
-        before: Option<&str>,
-        limit: Option<&str>,
-        only_metadata: bool,
-    ) -> Result<ListNftResponse, NFTStorageError> {
-        // get the optional value or use an empty string
-        let before = if let Some(value) = before {
-            value.to_string()
-        } else {
-            "".to_string()
-        };
-
-        let limit = if let Some(value) = limit {
-            value.to_string()
-        } else {
-            "".to_string()
-        };
-        // create the url to make the request
-        let url = format!("{}/?before={}&limit={}", self.url, before, limit);
-        // make the request to the nft storage api
-        let response = self.client.get(url).bearer_auth(&self.token).send().await?;
-        // check the response status if is in range from 200-299
-        let status = response.status().is_success();
-        let body = response.json::<Value>().await?;
-        // if status is not success then return an error
-        if !status {
-            return Err(NFTStorageError::ApiError(body));
-        }
-        let mut body: ListNftResponse = serde_json::from_value(body)?;
-        // if true get only metadata.json files and skip others
-        if only_metadata {
-            let final_filtered_list = body
-                .value
-                .into_iter()
-                // we always know that there is only one file in the files array if we store a metadata nft
-                .filter(|f| f.files.get(0).is_some() && f.files[0].name == "metadata.json")
-                // add additional convenience links
-                .map(|mut f| {
-                    let link_1 = format!("https://{}.ipfs.dweb.link/metadata.json", f.cid);
-                    let link_2 = format!("https://ipfs.io/ipfs/{}/metadata.json", f.cid);
-                    let link_3 = format!("ipfs://{}/metadata.json", f.cid);
+    pub fn preferred_gateway_url<C>(&self, cid: C) -> Result<String, NFTStorageError>
+    where
+        C: TryInto<Cid>,
+        NFTStorageError: From<C::Error>,
+    {
+        let cid: Cid = cid.try_into()?;
+        Ok(self.gateway_url(cid.as_str(), ""))
+    }
 
-                    f.link = vec![link_1, link_2, link_3];
+    /// Url of the api endpoint `path`, the path is joined to the api url keeping its base path
+    pub(crate) fn endpoint(&self, path: &str) -> String {
+        format!("{}/{}", self.url, path.trim_start_matches('/'))
+    }
 
-                    f
-                })
-                .collect::<Vec<_>>();
-            // modify the body with modified data, does include only metadata.json
-            body.value = final_filtered_list;
-        } else {
-            let final_filtered_list = body
-                .value
-                .into_iter()
-                // add additional convenience links to the filtered
-                .map(|mut f| {
-                    let link_1 = format!("https://{}.ipfs.dweb.link", f.cid);
-                    let link_2 = format!("https://ipfs.io/ipfs/{}", f.cid);
-                    let link_3 = format!("ipfs://{}", f.cid);
+    /// Hits and misses of the response cache, `None` if the cache isn't enabled with
+    /// [`NftStorageBuilder::cache_ttl`]
+    #[cfg(feature = "cache")]
+    pub fn cache_stats(&self) -> Option<CacheStats> {
+        self.cache.as_ref().map(|cache| cache.stats())
+    }
 
-                    f.link = vec![link_1, link_2, link_3];
+    /// Rate limit state reported by the headers of the last api response, `None` if no response contained them
+    ///
+    /// It is shared between the clones, see [`NftStorageBuilder::wait_on_rate_limit`] to wait for the reset of the
+    /// window instead of sending requests that would be rate limited
+    /// ```
+    /// # #[cfg(feature = "test-util")]
+    /// # #[tokio::main]
+    /// # async fn main() -> anyhow::Result<()> {
+    /// use nft_storage::{test_util::MockTransport, NftStorage, RetryPolicy};
+    /// use serde_json::json;
+    /// use std::time::Duration;
+    ///
+    /// let mock = MockTransport::new();
+    /// mock.push_response(
+    ///     200,
+    ///     vec![
+    ///         ("x-ratelimit-limit".to_string(), "30".to_string()),
+    ///         ("x-ratelimit-remaining".to_string(), "29".to_string()),
+    ///         ("x-ratelimit-reset".to_string(), "10".to_string()),
+    ///     ],
+    ///     json!({ "ok": true, "value": [] }).to_string(),
+    /// );
+    /// mock.push_response(
+    ///     429,
+    ///     vec![
+    ///         ("x-ratelimit-remaining".to_string(), "0".to_string()),
+    ///         ("retry-after".to_string(), "7".to_string()),
+    ///     ],
+    ///     json!({ "ok": false, "error": { "message": "too many requests" } }).to_string(),
+    /// );
+    /// let nft_storage = NftStorage::builder("https://api.nft.storage", "token")
+    ///     .retry_policy(RetryPolicy::none())
+    ///     .transport(mock)
+    ///     .build()?;
+    /// assert!(nft_storage.rate_limit_status().is_none());
+    ///
+    /// nft_storage.list_with_options(Default::default()).await?;
+    /// let status = nft_storage.rate_limit_status().unwrap();
+    /// assert_eq!((status.limit, status.remaining), (Some(30), Some(29)));
+    /// assert_eq!(status.reset_after, Some(Duration::from_secs(10)));
+    ///
+    /// // the rate limited errors contain the state to schedule the retry
+    /// let error = nft_storage.list_with_options(Default::default()).await.unwrap_err();
+    /// let rate_limit = error.rate_limit().unwrap();
+    /// assert!(rate_limit.is_exhausted());
+    /// assert_eq!(rate_limit.retry_after, Some(Duration::from_secs(7)));
+    /// assert!(nft_storage.rate_limit_status().unwrap().is_exhausted());
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "test-util"))]
+    /// # fn main() {}
+    /// ```
+    pub fn rate_limit_status(&self) -> Option<RateLimitInfo> {
+        sync::lock(&self.rate_limit_status).clone()
+    }
 
-                    f
-                })
-                .collect::<Vec<_>>();
-            // modify the body with modified data, does include metadata.json and also all files
-            body.value = final_filtered_list;
-        }
-        Ok(body)
+    /// Use `token` to authorize the requests of this client, used to rotate the api token
+    ///
+    /// The clones made before keep using the previous credentials
+    pub fn with_token<S>(mut self, token: S) -> NftStorage
+    where
+        S: Into<String>,
+    {
+        self.auth = Auth::ApiKey(token.into());
+        self
     }
 
-    /// Store an NFT on nft storage
+    /// Use `options` for the api requests of this client, the clones made before keep their options
     ///
-    /// `file` is the file bytes recieved from a form-data
+    /// `options.timeout` limits every attempt of a request replacing the timeout of the client, a request taking
+    /// longer returns a `RequestTimeout` error. No request is sent after `options.deadline`, so the methods making many
+    /// requests (`store_nft`, `store_nft_in_directory`, `delete_all_nft`...) share it as their budget, they return a
+    /// `DeadlineExceeded` error containing the work completed before it.
     ///
-    /// `file_name` is the filename of the file we want to create an nft
+    /// The client is cheap to clone, so a clone can be configured for a single operation
+    /// ```
+    /// # #[cfg(feature = "test-util")]
+    /// # #[tokio::main]
+    /// # async fn main() -> anyhow::Result<()> {
+    /// use nft_storage::{test_util::FakeNftStorage, types::*, NFTStorageError};
+    /// use std::time::{Duration, Instant};
     ///
-    /// `nft_name` is the nft name and `description` is the description of the nft
+    /// let fake = FakeNftStorage::start().await?;
+    /// fake.set_latency(Duration::from_millis(300));
+    /// let nft_storage = fake.client();
     ///
-    /// the main difference from `upload_file` method is that after uploading the file it creates a `metadata.json` file which
-    /// contains the uploaded file cid and also the nft name and it's description
+    /// // every request can take at most 100ms
+    /// let options = RequestOptions {
+    ///     timeout: Some(Duration::from_millis(100)),
+    ///     ..Default::default()
+    /// };
+    /// let error = nft_storage
+    ///     .clone()
+    ///     .with_request_options(options)
+    ///     .list_with_options(ListOptions::default())
+    ///     .await
+    ///     .unwrap_err();
+    /// assert!(matches!(error, NFTStorageError::RequestTimeout(_)));
     ///
+    /// // the file is uploaded, then the deadline expires while uploading the metadata
+    /// let options = RequestOptions {
+    ///     deadline: Some(Instant::now() + Duration::from_millis(450)),
+    ///     ..Default::default()
+    /// };
+    /// let error = nft_storage
+    ///     .clone()
+    ///     .with_request_options(options)
+    ///     .store_nft(b"image".to_vec(), "name", "description")
+    ///     .await
+    ///     .unwrap_err();
+    /// match error {
+    ///     NFTStorageError::DeadlineExceeded(partial) => match *partial {
+    ///         PartialResult::Stored(uploads) => assert_eq!(uploads.len(), 1),
+    ///         partial => panic!("unexpected partial result {:?}", partial),
+    ///     },
+    ///     e => panic!("unexpected error {:?}", e),
+    /// }
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "test-util"))]
+    /// # fn main() {}
     /// ```
-    /// use nft_storage::{NftStorage, types::*}
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn with_request_options(mut self, options: RequestOptions) -> NftStorage {
+        self.request_options = options;
+        self
+    }
+
+    /// Create a new instance of NftStorage from environment variables
+    ///
+    /// `NFT_STORAGE_API_URL` is the url of the api, if not set `https://api.nft.storage` is used.
+    ///
+    /// `NFT_STORAGE_API_TOKEN` is the jwt token generated from nft storage dashboard, it is required.
+    /// ```no_run
+    /// use nft_storage::NftStorage;
     /// use anyhow::Result;
     ///
     /// #[tokio::main]
     /// async fn main() -> Result<()> {
-    /// 	// provide the url and as second argument the token generated from nft storage dashboard
-    /// 	let nft_storage = NftStorage::new("https://api.nft.storage", "token generated from nft storage");
-    /// 	// read a file in order to have a Vec<u8> the same from a form-data
-    /// 	let file = std::fs::read("my_nft.jpg")?;
-    /// 	// store an nft
-    /// 	let store_nft: StoreNftResponse  = nft_storage.store_nft(file, "My NFT name", "My NFT description").await?;
+    ///     // read the url and the token from the environment
+    ///     let nft_storage = NftStorage::from_env()?;
     ///
-    /// 	Ok(())
+    ///     Ok(())
     /// }
     /// ```
     ///
-    pub async fn store_nft<S>(
-        &self,
-        file: Vec<u8>,
-        nft_name: S,
-        description: S,
-    ) -> Result<StoreNftResponse, NFTStorageError>
+    /// A missing or empty token returns a `MissingToken` error, a token that isn't a jwt an `InvalidToken` error
+    /// ```
+    /// use nft_storage::{NFTStorageError, NftStorage, API_TOKEN_ENV, API_URL_ENV, DEFAULT_API_URL};
+    ///
+    /// std::env::remove_var(API_URL_ENV);
+    /// std::env::remove_var(API_TOKEN_ENV);
+    /// assert!(matches!(NftStorage::from_env(), Err(NFTStorageError::MissingToken(_))));
+    ///
+    /// std::env::set_var(API_TOKEN_ENV, "   ");
+    /// assert!(matches!(NftStorage::from_env(), Err(NFTStorageError::MissingToken(_))));
+    ///
+    /// std::env::set_var(API_TOKEN_ENV, "not-a-jwt");
+    /// assert!(matches!(NftStorage::from_env(), Err(NFTStorageError::InvalidToken(_))));
+    ///
+    /// // the token is trimmed and the default url is used when it isn't set
+    /// std::env::set_var(API_TOKEN_ENV, " header.payload.signature\n");
+    /// assert_eq!(NftStorage::from_env().unwrap().url(), DEFAULT_API_URL);
+    ///
+    /// std::env::set_var(API_URL_ENV, "https://nft-storage.example/");
+    /// assert_eq!(NftStorage::from_env().unwrap().url(), "https://nft-storage.example");
+    ///
+    /// std::env::remove_var(API_URL_ENV);
+    /// std::env::remove_var(API_TOKEN_ENV);
+    /// ```
+    pub fn from_env() -> Result<NftStorage, NFTStorageError> {
+        // use the default api url if not provided
+        let url = std::env::var(API_URL_ENV)
+            .ok()
+            .map(|url| url.trim().to_string())
+            .filter(|url| !url.is_empty())
+            .unwrap_or_else(|| DEFAULT_API_URL.to_string());
+        let token = std::env::var(API_TOKEN_ENV).unwrap_or_default();
+        let token = token.trim();
+        if token.is_empty() {
+            return Err(NFTStorageError::MissingToken(API_TOKEN_ENV.to_string()));
+        }
+        // a jwt is made of three parts separated by dots
+        if !token.contains('.') {
+            return Err(NFTStorageError::InvalidToken(format!(
+                "{} is not a valid jwt",
+                API_TOKEN_ENV
+            )));
+        }
+        NftStorage::builder(url, token.to_string()).build()
+    }
+
+    /// Create a builder to configure the NftStorage instance
+    ///
+    /// Use it to provide a custom `reqwest::Client` or to set timeouts and the user-agent, see [`NftStorageBuilder`]
+    pub fn builder<S>(url: S, token: S) -> NftStorageBuilder
     where
-        S: AsRef<str>,
+        S: Into<String>,
     {
-        // upload the file to nft storage, which is the actual file we want to create an nft
-        let response: StoreNftResponse = self.upload_file(file).await?;
-        // get dir cid
-        let cid = response.value.cid;
-        // create the metadata form which will contain all files cid
-        let metadata = json!({
-            "name": nft_name.as_ref(),
-            "description": description.as_ref(),
-            "files": cid
-        });
-        // create the form-data instance for metadata.json
-        let metadata_json_bytes = serde_json::to_vec(&metadata)?;
-        // create the metadata.json which will contain the nft cids
-        let response: StoreNftResponse = self.upload_file(metadata_json_bytes).await?;
-        Ok(response)
+        NftStorageBuilder::new(url, token)
     }
 
-    /// Delete an NFT
+    /// Add a hook called before sending every request, including the retries and the gateway requests
     ///
-    /// `cid` is the ipfs hash, every file/nft has it's unique cid
+    /// The hooks run in the order they are added and can change the request, like adding a header.
+    /// The api token is added after the hooks, so they can't read it
+    /// ```no_run
+    /// use nft_storage::NftStorage;
+    /// use reqwest::header::HeaderValue;
     ///
+    /// let nft_storage = NftStorage::new("https://api.nft.storage", "token generated from nft storage")
+    ///     .with_request_hook(|request| {
+    ///         request.headers_mut().insert("x-client", HeaderValue::from_static("my-app"));
+    ///     })
+    ///     .with_response_hook(|meta| {
+    ///         println!("{} {} {:?} in {:?}", meta.method, meta.url.path(), meta.status, meta.elapsed);
+    ///     });
     /// ```
-    /// use nft_storage::{NftStorage, types::*}
+    ///
+    pub fn with_request_hook<F>(mut self, hook: F) -> NftStorage
+    where
+        F: Fn(&mut Request) + Send + Sync + 'static,
+    {
+        self.request_hooks.push(Arc::new(hook));
+        self
+    }
+
+    /// Add a hook called after every request with its method, url, status and latency
+    ///
+    /// The hooks run in the order they are added, every retry calls them again
+    pub fn with_response_hook<F>(mut self, hook: F) -> NftStorage
+    where
+        F: Fn(&ResponseMeta) + Send + Sync + 'static,
+    {
+        self.response_hooks.push(Arc::new(hook));
+        self
+    }
+
+    /// Check that the api token is accepted by nft storage
+    ///
+    /// It makes a cheap authenticated request listing a single nft, useful to fail fast at startup.
+    /// It returns an `Unauthorized` error if the token is invalid
+    /// ```no_run
+    /// use nft_storage::NftStorage;
     /// use anyhow::Result;
     ///
     /// #[tokio::main]
     /// async fn main() -> Result<()> {
-    /// 	// provide the url and as second argument the token generated from nft storage dashboard
-    /// 	let nft_storage = NftStorage::new("https://api.nft.storage", "token generated from nft storage");
-    /// 	// delete nft by cid
-    /// 	let delete_nft: DeleteNftResponse  = nft_storage.delete_nft("bafybeihflij24dndd6qo3aacbbysuzuygis7yurvrzxp3uk7bk5kfvfsdt").await?;
+    ///     let nft_storage = NftStorage::from_env()?;
+    ///     // stop here if the token is wrong
+    ///     nft_storage.validate_token().await?;
     ///
-    /// 	Ok(())
+    ///     Ok(())
     /// }
     /// ```
     ///
-    pub async fn delete_nft<S>(&self, cid: S) -> Result<DeleteNftResponse, NFTStorageError>
-    where
-        S: AsRef<str>,
-    {
-        // create the url
-        let url = format!("{}/{}", self.url, cid.as_ref());
-        // make the request to the nft storage api
-        let response = self
-            .client
-            .delete(url)
-            .bearer_auth(&self.token)
-            .send()
-            .await?;
-        // check if the status of the request is in range of 200-299
-        let status = response.status().is_success();
-        let body = response.json::<Value>().await?;
-        match status {
-            true => Ok(serde_json::from_value(body)?),
-            false => Err(NFTStorageError::ApiError(body)),
-        }
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn validate_token(&self) -> Result<(), NFTStorageError> {
+        self.list_page(None, Some(1)).await?;
+        Ok(())
     }
 
-    /// Delete all NFT
-    ///
-    /// ⚠ WARNING! ⚠
+    /// List all nfts from nft storage
+    /// `before` is used to return results created before provided timestamp and `limit` are the max records to return,
+    /// it must be between 1 and 100.
     ///
-    /// It will fetch and delete all nfts
+    /// the `only_metadata` option is used to return only the nft which contains the metadata.json file
+    #[deprecated(note = "use `list_with_options` with `ListOptions` instead")]
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn list_all_stored_nft(
+        &self,
+        before: Option<Timestamp>,
+        limit: Option<u32>,
+        only_metadata: bool,
+    ) -> Result<ListNftResponse, NFTStorageError> {
+        let options = ListOptions {
+            before,
+            limit,
+            only_metadata,
+            ..Default::default()
+        };
+        self.list_with_options(options).await
+    }
+
+    /// List a page of nfts from nft storage
     ///
-    /// This method is meant for developing purposes, it can be quite dangerous in production.
-    /// ```
-    /// use nft_storage::{NftStorage, types::*}
+    /// `options.before` returns the nfts created before the timestamp and `options.limit` is the max number of nfts
+    /// to return, between 1 and 100. `options.only_metadata` returns only the nfts containing the metadata.json file,
+    /// their links point to it. The links aren't built when `options.decorate_links` is false
+    /// ```no_run
+    /// use nft_storage::{NftStorage, types::*};
     /// use anyhow::Result;
     ///
     /// #[tokio::main]
     /// async fn main() -> Result<()> {
-    /// 	// provide the url and as second argument the token generated from nft storage dashboard
-    /// 	let nft_storage = NftStorage::new("https://api.nft.storage", "token generated from nft storage");
-    /// 	// delete all nfts
-    /// 	let delete_nfts: DeleteNftResponse  = nft_storage.delete_all_nft().await?;
+    ///     // provide the url and as second argument the token generated from nft storage dashboard
+    ///     let nft_storage = NftStorage::new("https://api.nft.storage", "token generated from nft storage");
+    ///     // list nfts only with metadata
+    ///     let options = ListOptions {
+    ///         only_metadata: true,
+    ///         ..Default::default()
+    ///     };
+    ///     let list_nfts: ListNftResponse  = nft_storage.list_with_options(options).await?;
+    ///     // list the cids of 10 nfts created before the 27th of July 2020
+    ///     let options = ListOptions {
+    ///         before: Some("2020-07-27T17:32:28Z".parse()?),
+    ///         limit: Some(10),
+    ///         decorate_links: false,
+    ///         ..Default::default()
+    ///     };
+    ///     let list_nfts: ListNftResponse  = nft_storage.list_with_options(options).await?;
     ///
-    /// 	Ok(())
+    ///     Ok(())
     /// }
     /// ```
     ///
-    pub async fn delete_all_nft(&self) -> Result<DeleteNftResponse, NFTStorageError> {
-        // create a loop to iterate and reqwest all nfts
-        loop {
-            // get first 100 nfts
-            let nfts: ListNftResponse = self.list_all_stored_nft(None, Some("100"), false).await?;
-            // check if ok is true this means the request was successfull and also check if the array is empty
-            // if all is true break the loop this mean no nft are stored
-            if nfts.ok && nfts.value.len() <= 0 {
-                break;
-            }
-            for e in nfts.value {
-                println!("deleting CID: {}", e.cid);
-                self.delete_nft(&e.cid).await?;
-                println!("NFT deleted: {}", e.cid);
-            }
+    /// ```
+    /// # #[cfg(feature = "test-util")]
+    /// # #[tokio::main]
+    /// # async fn main() -> anyhow::Result<()> {
+    /// use nft_storage::{test_util::{fixtures, MockTransport}, types::*};
+    ///
+    /// let mock = MockTransport::new();
+    /// mock.push_json(200, serde_json::from_str(fixtures::LIST_RESPONSE)?);
+    /// mock.push_json(200, serde_json::from_str(fixtures::LIST_RESPONSE)?);
+    /// let nft_storage = mock.client();
+    ///
+    /// let decorated = nft_storage.list_with_options(ListOptions::default()).await?;
+    /// let nft = &decorated.value[0];
+    /// assert_eq!(nft.links.ipfs_uri, format!("ipfs://{}", nft.cid));
+    ///
+    /// let options = ListOptions {
+    ///     decorate_links: false,
+    ///     ..Default::default()
+    /// };
+    /// let plain = nft_storage.list_with_options(options).await?;
+    /// assert_eq!(plain.value[0].links, GatewayLinks::default());
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "test-util"))]
+    /// # fn main() {}
+    /// ```
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn list_with_options(
+        &self,
+        options: ListOptions,
+    ) -> Result<ListNftResponse, NFTStorageError> {
+        let before = options.before.as_ref().map(to_rfc3339);
+        let mut body = self.list_page(before.as_deref(), options.limit).await?;
+        let filter = ListFilter {
+            only_metadata: options.only_metadata,
+            content_kind: options.content_kind,
+            ..Default::default()
+        };
+        body.value = filter.apply(body.value);
+        if options.decorate_links {
+            body.value = decorate_links(body.value, options.only_metadata, &self.link_gateways);
         }
-
-        Ok(DeleteNftResponse { ok: true })
+        Ok(body)
     }
 
-    /// Retrive an NFT
+    /// List a page of nfts like `list_with_options` converting every entry separately
+    ///
+    /// A malformed entry doesn't fail the whole page, it is skipped and reported in `failures` with its index in the
+    /// page and the deserialization error, the other entries are filtered and decorated following the `options`
+    /// ```
+    /// # #[cfg(feature = "test-util")]
+    /// # #[tokio::main]
+    /// # async fn main() -> anyhow::Result<()> {
+    /// use nft_storage::{test_util::MockTransport, types::*};
+    /// use serde_json::json;
     ///
-    /// It will fetch an nft from `cid`
+    /// let mock = MockTransport::new();
+    /// let page = json!({
+    ///     "ok": true,
+    ///     "value": [
+    ///         { "cid": "bafkreia", "size": 10 },
+    ///         { "cid": "bafkreib", "size": "not a number" },
+    ///         { "cid": "bafkreic", "size": 30 },
+    ///     ],
+    /// });
+    /// mock.push_json(200, page.clone());
+    /// mock.push_json(200, page);
+    /// let nft_storage = mock.client();
+    ///
+    /// // the strict listing fails on the malformed entry
+    /// assert!(nft_storage.list_with_options(ListOptions::default()).await.is_err());
+    ///
+    /// let page = nft_storage.list_lenient(ListOptions::default()).await?;
+    /// let cids = page.response.value.iter().map(|nft| nft.cid.as_str()).collect::<Vec<_>>();
+    /// assert_eq!(cids, ["bafkreia", "bafkreic"]);
+    /// assert_eq!(page.failures.len(), 1);
+    /// assert_eq!(page.failures[0].0, 1);
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "test-util"))]
+    /// # fn main() {}
     /// ```
-    /// use nft_storage::{NftStorage, types::*}
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn list_lenient(
+        &self,
+        options: ListOptions,
+    ) -> Result<LenientListResponse, NFTStorageError> {
+        let before = options.before.as_ref().map(to_rfc3339);
+        let request = self.list_page_request(before.as_deref(), options.limit)?;
+        let raw: RawListResponse = self.request(request).await?;
+        let mut page = raw.into_lenient();
+        #[cfg(feature = "tracing")]
+        for (index, e) in &page.failures {
+            tracing::warn!(index, error = %e, "skipping malformed nft of the listing");
+        }
+        let filter = ListFilter {
+            only_metadata: options.only_metadata,
+            content_kind: options.content_kind,
+            ..Default::default()
+        };
+        let nfts = filter.apply(std::mem::take(&mut page.response.value));
+        page.response.value = if options.decorate_links {
+            decorate_links(nfts, options.only_metadata, &self.link_gateways)
+        } else {
+            nfts
+        };
+        Ok(page)
+    }
+
+    /// List all nfts from nft storage matching the `filter`
+    ///
+    /// It works like `list_with_options` but the nfts of the page are filtered using all the [`ListFilter`] options,
+    /// so less than `limit` nfts can be returned
+    /// ```no_run
+    /// use nft_storage::{NftStorage, types::*};
     /// use anyhow::Result;
     ///
     /// #[tokio::main]
     /// async fn main() -> Result<()> {
-    /// 	// provide the url and as second argument the token generated from nft storage dashboard
-    /// 	let nft_storage = NftStorage::new("https://api.nft.storage", "token generated from nft storage");
-    /// 	// get nft by cid
-    /// 	let get_nft: GetNftResponse  = nft_storage.get_nft("bafybeihflij24dndd6qo3aacbbysuzuygis7yurvrzxp3uk7bk5kfvfsfg").await?;
+    ///     // provide the url and as second argument the token generated from nft storage dashboard
+    ///     let nft_storage = NftStorage::new("https://api.nft.storage", "token generated from nft storage");
+    ///     // list only pinned images with filecoin deals
+    ///     let filter = ListFilter {
+    ///         pin_status: Some("pinned".to_string()),
+    ///         mime_type: Some("image/png".to_string()),
+    ///         has_deals: Some(true),
+    ///         ..Default::default()
+    ///     };
+    ///     let list_nfts: ListNftResponse  = nft_storage.list_filtered(None, Some(100), &filter).await?;
     ///
-    /// 	Ok(())
+    ///     Ok(())
     /// }
     /// ```
     ///
-    pub async fn get_nft<S>(&self, cid: S) -> Result<GetNftResponse, NFTStorageError>
-    where
-        S: AsRef<str>,
-    {
-        let url = format!("{}/{}", self.url, cid.as_ref());
-        let response = self.client.get(url).bearer_auth(&self.token).send().await?;
-        // check if the status of the request is in range of 200-299
-        let status = response.status().is_success();
-        let body = response.json::<Value>().await?;
-        if !status {
-            return Err(NFTStorageError::ApiError(body));
-        }
-        let mut body: GetNftResponse = serde_json::from_value(body)?;
-        // add some convinient links
-        let link_1 = format!("https://{}.ipfs.dweb.link", body.value.cid);
-        let link_2 = format!("https://ipfs.io/ipfs/{}", body.value.cid);
-        let link_3 = format!("ipfs://{}", body.value.cid);
-        // modify the body with added links
-        body.value.link = vec![link_1, link_2, link_3];
-
+    /// Only the provided `before` and `limit` are sent, the timestamp is percent-encoded
+    /// ```
+    /// # #[cfg(feature = "test-util")]
+    /// # #[tokio::main]
+    /// # async fn main() -> anyhow::Result<()> {
+    /// use nft_storage::{test_util::MockTransport, types::*};
+    /// use serde_json::json;
+    ///
+    /// let page = json!({
+    ///     "ok": true,
+    ///     "value": [
+    ///         { "cid": "bafkreia", "created": "2021-12-01T10:00:00Z", "pin": { "status": "pinned" } },
+    ///         { "cid": "bafkreib", "created": "2021-12-01T09:00:00Z", "pin": { "status": "queued" } },
+    ///     ],
+    /// });
+    /// let mock = MockTransport::new();
+    /// mock.push_json(200, json!({ "ok": true, "value": [] }));
+    /// mock.push_json(200, page.clone());
+    /// mock.push_json(200, page.clone());
+    /// mock.push_json(200, page);
+    /// let nft_storage = mock.client();
+    /// let filter = ListFilter {
+    ///     pin_status: Some("pinned".to_string()),
+    ///     ..Default::default()
+    /// };
+    /// let before: Timestamp = "2021-12-02T08:52:33.000Z".parse()?;
+    ///
+    /// // an empty page returns no nfts
+    /// assert!(nft_storage.list_filtered(None, None, &filter).await?.value.is_empty());
+    /// // the filter is applied to the requested page
+    /// let pinned = nft_storage.list_filtered(None, Some(2), &filter).await?;
+    /// assert_eq!(pinned.value.iter().map(|nft| nft.cid.as_str()).collect::<Vec<_>>(), ["bafkreia"]);
+    /// let pinned = nft_storage.list_filtered(Some(before.clone()), None, &filter).await?;
+    /// assert_eq!(pinned.value.len(), 1);
+    /// let pinned = nft_storage.list_filtered(Some(before), Some(2), &filter).await?;
+    /// assert_eq!(pinned.value.len(), 1);
+    ///
+    /// let queries = mock.requests().iter().map(|request| request.url.query().map(str::to_string)).collect::<Vec<_>>();
+    /// assert_eq!(queries, [
+    ///     None,
+    ///     Some("limit=2".to_string()),
+    ///     Some("before=2021-12-02T08%3A52%3A33.000Z".to_string()),
+    ///     Some("before=2021-12-02T08%3A52%3A33.000Z&limit=2".to_string()),
+    /// ]);
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "test-util"))]
+    /// # fn main() {}
+    /// ```
+    ///
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn list_filtered(
+        &self,
+        before: Option<Timestamp>,
+        limit: Option<u32>,
+        filter: &ListFilter,
+    ) -> Result<ListNftResponse, NFTStorageError> {
+        let before = before.as_ref().map(to_rfc3339);
+        let mut body = self.list_page(before.as_deref(), limit).await?;
+        body.value = decorate_links(
+            filter.apply(body.value),
+            filter.only_metadata,
+            &self.link_gateways,
+        );
         Ok(body)
     }
 
-    /// Upload an arbitrary file on Nft storage
+    /// List all nfts from nft storage following the pages automatically
     ///
-    /// It will upload an arbitrary file on ipfs backed up by nft storage and filecoin
+    /// Every request returns at most `page_size` nfts, the `created` timestamp of the oldest nft is used as `before`
+    /// for the next request until all nfts are fetched.
     ///
-    /// the max sise is around 30GB per file
-    /// ```
-    /// use nft_storage::{NftStorage, types::*}
+    /// the `only_metadata` option works like in `list_with_options`
+    /// ```no_run
+    /// use nft_storage::{NftStorage, types::*};
     /// use anyhow::Result;
     ///
     /// #[tokio::main]
     /// async fn main() -> Result<()> {
-    /// 	// provide the url and as second argument the token generated from nft storage dashboard
-    /// 	let nft_storage = NftStorage::new("https://api.nft.storage", "token generated from nft storage");
-    /// 	// read a file in order to have a Vec<u8> the same from a form-data
-    /// 	let file = std::fs::read("my_nft.jpg")?;
-    /// 	// delete nft
-    /// 	let upload_file: StoreNftResponse  = nft_storage.upload_file(file).await?;
+    ///     // provide the url and as second argument the token generated from nft storage dashboard
+    ///     let nft_storage = NftStorage::new("https://api.nft.storage", "token generated from nft storage");
+    ///     // list all nfts requesting 100 nfts every time
+    ///     let nfts: Vec<NftValue> = nft_storage.list_all_pages(100, false).await?;
     ///
-    /// 	Ok(())
+    ///     Ok(())
     /// }
     /// ```
-    pub async fn upload_file(&self, file: Vec<u8>) -> Result<StoreNftResponse, NFTStorageError> {
-        let url = format!("{}/upload", self.url);
-        let response = self
-            .client
-            .post(url)
-            .bearer_auth(&self.token)
-            .body(file)
-            .send()
-            .await?;
-        let status = response.status().is_success();
-        // check if the status of the request is in range of 200-299
-        let body = response.json::<Value>().await?;
-        match status {
-            true => Ok(serde_json::from_value(body)?),
-            false => Err(NFTStorageError::ApiError(body)),
-        }
+    ///
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn list_all_pages(
+        &self,
+        page_size: u32,
+        only_metadata: bool,
+    ) -> Result<Vec<NftValue>, NFTStorageError> {
+        let nfts = self.list_stream(page_size).try_collect::<Vec<_>>().await?;
+        let filter = ListFilter {
+            only_metadata,
+            ..Default::default()
+        };
+        Ok(decorate_links(
+            filter.apply(nfts),
+            only_metadata,
+            &self.link_gateways,
+        ))
     }
 
-    /// Check if the provided NFT cid is stored on nft storage
+    /// Compute the storage used by the account
     ///
-    /// It will check the nft by `cid`
-    /// ```
-    /// use nft_storage::{NftStorage, types::*}
+    /// The nfts are listed a page at a time and only the summary is kept in memory, with `since` only the nfts
+    /// created after it are counted
+    /// ```no_run
+    /// use nft_storage::{NftStorage, types::*};
     /// use anyhow::Result;
     ///
     /// #[tokio::main]
     /// async fn main() -> Result<()> {
-    /// 	// provide the url and as second argument the token generated from nft storage dashboard
-    /// 	let nft_storage = NftStorage::new("https://api.nft.storage", "token generated from nft storage");
-    /// 	// get nft by cid
-    /// 	let is_stored_on_nft_storage: CheckCidNftResponse  = nft_storage.check_nft("bafybeihflij24dndd6qo3aacbbysuzuygis7yurvrzxp3uk7bk5kfvfsfg").await?;
+    ///     // provide the url and as second argument the token generated from nft storage dashboard
+    ///     let nft_storage = NftStorage::new("https://api.nft.storage", "token generated from nft storage");
+    ///     // usage of the last month
+    ///     let since = "2021-11-01T00:00:00Z".parse()?;
+    ///     let usage: UsageSummary = nft_storage.storage_usage(Some(since)).await?;
+    ///     println!("{}", usage);
     ///
-    /// 	Ok(())
+    ///     Ok(())
     /// }
     /// ```
-    pub async fn check_nft<S>(&self, cid: S) -> Result<CheckCidNftResponse, NFTStorageError>
-    where
-        S: AsRef<str>,
-    {
-        let url = format!("{}/check/{}", self.url, cid.as_ref());
-        let response = self.client.get(url).bearer_auth(&self.token).send().await?;
-        // check if the status of the request is in range of 200-299
-        let status = response.status().is_success();
-        let body = response.json::<Value>().await?;
-        match status {
-            true => Ok(serde_json::from_value(body)?),
-            false => Err(NFTStorageError::ApiError(body)),
+    ///
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn storage_usage(
+        &self,
+        since: Option<Timestamp>,
+    ) -> Result<UsageSummary, NFTStorageError> {
+        let mut usage = UsageSummary::default();
+        let mut nfts = Box::pin(self.list_stream(MAX_LIST_LIMIT));
+        while let Some(nft) = nfts.next().await {
+            let nft = nft?;
+            // the nfts are listed from the newest, so the next ones are older too
+            if matches!(&since, Some(since) if nft.created < *since) {
+                break;
+            }
+            usage.add(&nft);
         }
+        Ok(usage)
     }
 
-    /// Upload multiple files to Nft Storage
-    ///
-    /// It will upload multiple files using multipart form data and files will be stored in IPFS Directory
+    /// List all nfts from nft storage as a stream
     ///
-    /// The max sise is around 30GB per file
-    ///
-    /// The main difference between `upload_file` method is that it upload a file NOT in a direcotry so its url is unique, if using
-    /// `upload_file_in_directory` it will store one or multiple files in an ipfs direcotry preserving the original filenames and all file will be
-    /// fetched by the direcotry cid  for example `bafybeihflij24dndd6qo3aacbbysuzuygis7yurvrzxp3uk7bk5kfvfsfg/my_file.txt`
+    /// The nfts are fetched `page_size` at a time following the `before` cursor like `list_all_pages`, but they are
+    /// yielded as soon as a page arrives instead of collecting all of them in memory.
     ///
-    /// Every time using this method it will create a new directory
-    /// ```
+    /// The stream ends after the first error
+    /// ```no_run
+    /// use nft_storage::{NftStorage, types::*};
     /// use anyhow::Result;
-    /// use nft_storage::{NftStorage, types::*}
+    /// use futures::StreamExt;
     ///
     /// #[tokio::main]
     /// async fn main() -> Result<()> {
-    /// 	// provide the url and as second argument the token generated from nft storage dashboard
-    /// 	let nft_storage = NftStorage::new("https://api.nft.storage", "token generated from nft storage");
-    /// 	// read a file in order to have a Vec<u8> the same from a form-data
-    /// 	let file = std::fs::read("my_nft.jpg")?;
-    /// 	let fil2 = std::fs::read("my_nft2.jpg")?;
-    /// 	// create a vec of files bytes
-    /// 	let v = vec![file, file2];
-    /// 	// create a vec of file names
-    /// 	let f = vec!["my_nft.jpg".to_String()., "my_nft2.jpg".to_string()];
-    /// 	// delete nft
-    /// 	let upload_file: StoreNftResponse  = nft_storage.upload_file_in_directory(v, f).await?;
+    ///     // provide the url and as second argument the token generated from nft storage dashboard
+    ///     let nft_storage = NftStorage::new("https://api.nft.storage", "token generated from nft storage");
+    ///     // list all nfts requesting 100 nfts every time
+    ///     let mut nfts = Box::pin(nft_storage.list_stream(100));
+    ///     while let Some(nft) = nfts.next().await {
+    ///         let nft: NftValue = nft?;
+    ///         println!("{}", nft.cid);
+    ///     }
     ///
-    /// 	Ok(())
+    ///     Ok(())
     /// }
     /// ```
-    pub async fn upload_file_in_directory<S>(
+    ///
+    pub fn list_stream(
         &self,
-        files: Vec<Vec<u8>>,
-        file_names: Vec<S>,
-    ) -> Result<StoreNftResponse, NFTStorageError>
-    where
-        S: AsRef<str>,
-    {
-        let url = format!("{}/upload", self.url);
-        let mut form = Form::new();
-        // creating a custom part of teh form
-        for (index, _) in files.iter().enumerate() {
-            let part =
-                Part::bytes(files[index].clone()).file_name(file_names[index].as_ref().to_string());
-            form = Form::from(form.part("file", part));
-        }
-        let response = self
-            .client
-            .post(url)
-            .bearer_auth(&self.token)
-            .multipart(form)
-            .send()
-            .await?;
-        let status = response.status().is_success();
-        // check if the status of the request is in range of 200-299
-        let body = response.json::<Value>().await?;
-        match status {
-            true => Ok(serde_json::from_value(body)?),
-            false => Err(NFTStorageError::ApiError(body)),
-        }
+        page_size: u32,
+    ) -> impl Stream<Item = Result<NftValue, NFTStorageError>> + '_ {
+        stream::unfold(ListCursor::default(), move |mut cursor| {
+            async move {
+                if cursor.done {
+                    return None;
+                }
+                let page = match self
+                    .list_page(cursor.before.as_deref(), Some(page_size))
+                    .await
+                {
+                    Ok(page) => page.value,
+                    Err(e) => {
+                        // stop on the first error
+                        cursor.done = true;
+                        return Some((Err(e), cursor));
+                    }
+                };
+                let page_len = page.len();
+                // the oldest nft is the cursor of the next page
+                let next_before = page.iter().map(|f| &f.created).min().map(to_rfc3339);
+                // the nfts created at the cursor timestamp can be returned twice
+                let new_nfts = page
+                    .into_iter()
+                    .filter(|f| cursor.seen.insert(f.cid.clone()))
+                    .collect::<Vec<_>>();
+                // stop when there are no more pages or when the api keeps returning the same page
+                cursor.done = page_len < page_size as usize
+                    || new_nfts.is_empty()
+                    || next_before == cursor.before;
+                cursor.before = next_before;
+                Some((
+                    Ok(decorate_links(new_nfts, false, &self.link_gateways)),
+                    cursor,
+                ))
+            }
+        })
+        .map(|page| {
+            let nfts = match page {
+                Ok(nfts) => nfts.into_iter().map(Ok).collect::<Vec<_>>(),
+                Err(e) => vec![Err(e)],
+            };
+            stream::iter(nfts)
+        })
+        .flatten()
     }
 
-    /// Store an NFT on nft storage in a directory
-    ///
-    /// `file` is the array containing the the file bytes vecs recieved from a form-data
+    /// Export all the stored nfts to `writer`, one row or json object per upload
     ///
-    /// `file_name` is the filename of the file we want to create an nft
-    ///
-    /// `nft_name` is the nft name and `description` is the description of the nft
-    ///
-    /// The difference from `upload_file_in_directory` method is that after uploading all files it creates a `metadata.json` file
-    /// that lists all files uploaded and also assigns the nft name and it's description, this metadata.json file it is stored on a IPFS Direcotry
-    ///
-    /// ```
+    /// The nfts are streamed with `list_stream`, so they are written as soon as a page arrives. `ExportFormat::Csv`
+    /// writes a header followed by the cid, size, creation date, pin status, number of deals, gateway url and file
+    /// names (separated by `;`) of every upload, `ExportFormat::Ndjson` writes the whole nft as json on every line.
+    /// The number of exported nfts is returned
+    /// ```no_run
+    /// use nft_storage::{NftStorage, types::*};
     /// use anyhow::Result;
-    /// use nft_storage::{NftStorage, types::*}
     ///
     /// #[tokio::main]
     /// async fn main() -> Result<()> {
-    /// 	// provide the url and as second argument the token generated from nft storage dashboard
-    /// 	let nft_storage = NftStorage::new("https://api.nft.storage", "token generated from nft storage");
-    /// 	// read a file in order to have a Vec<u8> the same from a form-data
-    /// 	let file = std::fs::read("my_nft.jpg")?;
-    /// 	let fil2 = std::fs::read("my_nft2.jpg")?;
-    /// 	// create a vec of file bytes
-    /// 	let v = vec![file, file2];
-    /// 	// store an nft
-    /// 	let store_nft: StoreNftResponse  = nft_storage.store_nft_in_directory(v, "My NFT name", "My NFT description").await?;
+    ///     // provide the url and as second argument the token generated from nft storage dashboard
+    ///     let nft_storage = NftStorage::new("https://api.nft.storage", "token generated from nft storage");
+    ///     let file = tokio::fs::File::create("nfts.csv").await?;
+    ///     let exported = nft_storage.export_listing(ExportFormat::Csv, file).await?;
+    ///     println!("{} nfts exported", exported);
     ///
-    /// 	Ok(())
+    ///     Ok(())
     /// }
     /// ```
-    ///
-    pub async fn store_nft_in_directory<S>(
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(format = ?format)))]
+    pub async fn export_listing<W>(
+        &self,
+        format: ExportFormat,
+        writer: W,
+    ) -> Result<usize, NFTStorageError>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        let mut writer = tokio::io::BufWriter::new(writer);
+        if format == ExportFormat::Csv {
+            writer.write_all(CSV_HEADER.as_bytes()).await?;
+        }
+        let mut nfts = Box::pin(self.list_stream(MAX_LIST_LIMIT));
+        let mut exported = 0;
+        while let Some(nft) = nfts.next().await {
+            let nft = nft?;
+            let mut line = match format {
+                ExportFormat::Csv => self.csv_row(&nft),
+                ExportFormat::Ndjson => serde_json::to_string(&nft)?,
+            };
+            line.push('\n');
+            writer.write_all(line.as_bytes()).await?;
+            exported += 1;
+        }
+        writer.flush().await?;
+        Ok(exported)
+    }
+
+    /// Csv row of `nft` with the columns of `CSV_HEADER`, without the line break
+    fn csv_row(&self, nft: &NftValue) -> String {
+        let gateway_url = self.gateway_url(nft.cid.as_str(), "");
+        let file_names = nft
+            .files
+            .iter()
+            .map(|f| f.name.as_str())
+            .collect::<Vec<_>>()
+            .join(";");
+        [
+            nft.cid.to_string(),
+            nft.size.to_string(),
+            to_rfc3339(&nft.created),
+            nft.pin.status.clone(),
+            nft.deals.len().to_string(),
+            gateway_url,
+            file_names,
+        ]
+        .iter()
+        .map(|field| csv_field(field))
+        .collect::<Vec<_>>()
+        .join(",")
+    }
+
+    /// Get a single page of nfts without filtering and decorating them
+    ///
+    /// `before` is a rfc3339 timestamp
+    async fn list_page(
+        &self,
+        before: Option<&str>,
+        limit: Option<u32>,
+    ) -> Result<ListNftResponse, NFTStorageError> {
+        let request = self.list_page_request(before, limit)?;
+        self.request(request).await
+    }
+
+    /// Request of a single page of nfts, `before` is a rfc3339 timestamp
+    fn list_page_request(
+        &self,
+        before: Option<&str>,
+        limit: Option<u32>,
+    ) -> Result<RequestBuilder, NFTStorageError> {
+        if let Some(limit) = limit {
+            if limit == 0 || limit > MAX_LIST_LIMIT {
+                return Err(NFTStorageError::InvalidInput(format!(
+                    "limit must be between 1 and {}, got {}",
+                    MAX_LIST_LIMIT, limit
+                )));
+            }
+        }
+        // send only the provided parameters, they are percent-encoded by reqwest
+        let mut query = Vec::new();
+        if let Some(before) = before {
+            query.push(("before", before.to_string()));
+        }
+        if let Some(limit) = limit {
+            query.push(("limit", limit.to_string()));
+        }
+        // create the url to make the request
+        let url = self.endpoint("");
+        Ok(self.client.get(url).query(&query))
+    }
+
+    /// Store an NFT on nft storage
+    ///
+    /// `file` is the file bytes recieved from a form-data
+    ///
+    /// `file_name` is the filename of the file we want to create an nft
+    ///
+    /// `nft_name` is the nft name and `description` is the description of the nft
+    ///
+    /// the main difference from `upload_file` method is that after uploading the file it creates a `metadata.json` file which
+    /// contains the uploaded file cid as `image` and also the nft name and it's description, following the ERC-721 metadata schema
+    ///
+    /// the generated metadata is returned in `StoreNftResponse.metadata`
+    ///
+    /// An `InvalidInput` error is returned before uploading anything if the name is empty, the name or the description
+    /// exceed the client `metadata_limits` or contain control characters
+    ///
+    /// ```no_run
+    /// use nft_storage::{NftStorage, types::*};
+    /// use anyhow::Result;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     // provide the url and as second argument the token generated from nft storage dashboard
+    ///     let nft_storage = NftStorage::new("https://api.nft.storage", "token generated from nft storage");
+    ///     // read a file in order to have a Vec<u8> the same from a form-data
+    ///     let file = std::fs::read("my_nft.jpg")?;
+    ///     // store an nft, the name and the description can be different string types
+    ///     let nft_name = format!("My NFT #{}", 1);
+    ///     let store_nft: StoreNftResponse  = nft_storage.store_nft(file, nft_name, "My NFT description").await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn store_nft<N, D>(
+        &self,
+        file: Vec<u8>,
+        nft_name: N,
+        description: D,
+    ) -> Result<StoreNftResponse, NFTStorageError>
+    where
+        N: AsRef<str>,
+        D: AsRef<str>,
+    {
+        self.store_nft_erc721(file, nft_name, description, Erc721Options::default())
+            .await
+    }
+
+    /// Store an ERC-721 compliant NFT on nft storage
+    ///
+    /// It works like `store_nft` but it also accepts the optional ERC-721 fields `external_url`, `animation_url`
+    /// and `background_color` which are added to the `metadata.json` file
+    ///
+    /// ```no_run
+    /// use nft_storage::{NftStorage, types::*};
+    /// use anyhow::Result;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     // provide the url and as second argument the token generated from nft storage dashboard
+    ///     let nft_storage = NftStorage::new("https://api.nft.storage", "token generated from nft storage");
+    ///     // read a file in order to have a Vec<u8> the same from a form-data
+    ///     let file = std::fs::read("my_nft.jpg")?;
+    ///     // optional erc-721 fields
+    ///     let options = Erc721Options {
+    ///         external_url: Some("https://example.com/nft/1".to_string()),
+    ///         background_color: Some("FFFFFF".to_string()),
+    ///         ..Default::default()
+    ///     };
+    ///     // store an nft
+    ///     let store_nft: StoreNftResponse  = nft_storage.store_nft_erc721(file, "My NFT name", "My NFT description", options).await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn store_nft_erc721<N, D>(
+        &self,
+        file: Vec<u8>,
+        nft_name: N,
+        description: D,
+        options: Erc721Options,
+    ) -> Result<StoreNftResponse, NFTStorageError>
+    where
+        N: AsRef<str>,
+        D: AsRef<str>,
+    {
+        let metadata = NftMetadata {
+            name: nft_name.as_ref().to_string(),
+            description: description.as_ref().to_string(),
+            external_url: options.external_url,
+            animation_url: options.animation_url,
+            background_color: options.background_color,
+            ..Default::default()
+        };
+        self.store_nft_with_metadata(file, metadata).await
+    }
+
+    /// Store an NFT on nft storage using the provided metadata
+    ///
+    /// After uploading the `file` its cid is set as `image` (`ipfs://<cid>`) in `metadata`, then the metadata
+    /// is uploaded as `metadata.json`. The metadata is validated first with the client `metadata_limits`
+    ///
+    /// ```no_run
+    /// use nft_storage::{NftStorage, types::*};
+    /// use anyhow::Result;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     // provide the url and as second argument the token generated from nft storage dashboard
+    ///     let nft_storage = NftStorage::new("https://api.nft.storage", "token generated from nft storage");
+    ///     // read a file in order to have a Vec<u8> the same from a form-data
+    ///     let file = std::fs::read("my_nft.jpg")?;
+    ///     // create the nft metadata
+    ///     let metadata = NftMetadata::new("My NFT name", "My NFT description")
+    ///         .attribute("Eyes", "Blue")
+    ///         .attribute("Level", 5);
+    ///     // store an nft
+    ///     let store_nft: StoreNftResponse  = nft_storage.store_nft_with_metadata(file, metadata).await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn store_nft_with_metadata(
+        &self,
+        file: Vec<u8>,
+        metadata: NftMetadata,
+    ) -> Result<StoreNftResponse, NFTStorageError> {
+        let result = self.store_nft_detailed(file, metadata).await?;
+        Ok(result.metadata_response)
+    }
+
+    /// Store an NFT on nft storage returning both the uploads and the uploaded metadata
+    ///
+    /// It works like `store_nft_with_metadata`, the returned `metadata` is the one uploaded with the `image` set, so
+    /// it can be recorded without downloading it from a gateway
+    /// ```
+    /// # #[cfg(feature = "test-util")]
+    /// # #[tokio::main]
+    /// # async fn main() -> anyhow::Result<()> {
+    /// use nft_storage::{cid::compute_cid_v1_raw, test_util::FakeNftStorage, types::*};
+    ///
+    /// let fake = FakeNftStorage::start().await?;
+    /// let nft_storage = fake.client();
+    /// let metadata = NftMetadata::new("My NFT", "My NFT description").attribute("Level", 5);
+    /// let result = nft_storage.store_nft_detailed(b"not really a png".to_vec(), metadata).await?;
+    /// assert_eq!(result.metadata.image, format!("ipfs://{}", result.asset_response.value.cid));
+    ///
+    /// // serializing the returned metadata gives the uploaded document and its cid
+    /// let document = serde_json::to_vec(&result.metadata)?;
+    /// assert_eq!(fake.uploads()[1].body, document);
+    /// assert_eq!(compute_cid_v1_raw(&document), result.metadata_response.value.cid);
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "test-util"))]
+    /// # fn main() {}
+    /// ```
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn store_nft_detailed(
+        &self,
+        file: Vec<u8>,
+        mut metadata: NftMetadata,
+    ) -> Result<StoreNftResult, NFTStorageError> {
+        metadata.validate(&self.metadata_limits)?;
+        // upload the file to nft storage, which is the actual file we want to create an nft
+        let asset_response = self.upload_file(file).await?;
+        // point the metadata image to the uploaded file
+        metadata.image = format!("ipfs://{}", asset_response.value.cid);
+        // create the form-data instance for metadata.json
+        let metadata_json_bytes = serde_json::to_vec(&metadata)?;
+        // create the metadata.json which will contain the nft cids
+        let mut metadata_response = self.upload_file(metadata_json_bytes).await.map_err(|e| {
+            e.with_partial_result(|| PartialResult::Stored(vec![asset_response.clone()]))
+        })?;
+        // return the generated metadata so it can be verified without downloading it
+        metadata_response.metadata = Some(serde_json::to_value(&metadata)?);
+        Ok(StoreNftResult {
+            asset_response,
+            metadata_response,
+            metadata,
+        })
+    }
+
+    /// Upload a corrected metadata of an already stored asset returning its token uri, `ipfs://<cid>`
+    ///
+    /// The asset must be stored on nft storage, a `NotFound` error is returned otherwise. The `image` of the
+    /// metadata is set to `ipfs://<asset_cid>` and the metadata is uploaded like in `store_nft_with_metadata`.
+    /// When `old_metadata_cid` is provided the previous metadata is deleted after the upload, an error deleting it is
+    /// returned even if the new metadata is already stored
+    /// ```
+    /// # #[cfg(feature = "test-util")]
+    /// # #[tokio::main]
+    /// # async fn main() -> anyhow::Result<()> {
+    /// use nft_storage::{test_util::FakeNftStorage, types::*};
+    ///
+    /// let fake = FakeNftStorage::start().await?;
+    /// let nft_storage = fake.client();
+    /// let metadata = NftMetadata::new("My NFT", "My NFT descriptoin");
+    /// let stored = nft_storage.store_nft_detailed(b"image".to_vec(), metadata).await?;
+    /// let asset_cid = stored.asset_response.value.cid;
+    /// let old_metadata_cid = stored.metadata_response.value.cid;
+    ///
+    /// // fix the typo replacing the metadata
+    /// let metadata = NftMetadata::new("My NFT", "My NFT description");
+    /// let token_uri = nft_storage
+    ///     .reupload_metadata(asset_cid.as_str(), metadata, Some(old_metadata_cid.as_str()))
+    ///     .await?;
+    /// let new_metadata_cid = token_uri.trim_start_matches("ipfs://");
+    /// let stored = fake.stored();
+    /// assert!(stored.contains(&asset_cid));
+    /// assert!(!stored.contains(&old_metadata_cid));
+    /// assert!(stored.iter().any(|cid| cid.as_str() == new_metadata_cid));
+    ///
+    /// let uploaded: NftMetadata = serde_json::from_slice(&fake.uploads()[2].body)?;
+    /// assert_eq!(uploaded.image, format!("ipfs://{}", asset_cid));
+    /// assert_eq!(uploaded.description, "My NFT description");
+    ///
+    /// // the asset must exist
+    /// let missing = "bafkreifzjut3te2nhyekklss27nh3k72ysco7y32koao5eei66wof36n5e";
+    /// let error = nft_storage
+    ///     .reupload_metadata(missing, NftMetadata::new("My NFT", "description"), None)
+    ///     .await
+    ///     .unwrap_err();
+    /// assert!(error.is_not_found());
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "test-util"))]
+    /// # fn main() {}
+    /// ```
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(asset_cid = %asset_cid)))]
+    pub async fn reupload_metadata(
+        &self,
+        asset_cid: &str,
+        mut metadata: NftMetadata,
+        old_metadata_cid: Option<&str>,
+    ) -> Result<String, NFTStorageError> {
+        metadata.validate(&self.metadata_limits)?;
+        let old_metadata_cid = old_metadata_cid.map(Cid::new).transpose()?;
+        // the asset must be stored before pointing the metadata to it
+        let asset_cid = self.check_nft(asset_cid).await?.value.cid;
+        metadata.image = format!("ipfs://{}", asset_cid);
+        let metadata_response = self.upload_file(serde_json::to_vec(&metadata)?).await?;
+        if let Some(old_metadata_cid) = old_metadata_cid {
+            self.delete_nft_idempotent(old_metadata_cid).await?;
+        }
+        Ok(format!("ipfs://{}", metadata_response.value.cid))
+    }
+
+    /// Store a collection of NFTs in the layout expected by the ERC-721 contracts using a base uri
+    ///
+    /// The images are uploaded in a directory, every item `image` is set to `ipfs://<images_cid>/<file name>`, then
+    /// the metadata are uploaded in a second directory named by token id (`0.json`, `1.json`, ...).
+    /// The image names must be unique, an `InvalidInput` error is returned before uploading anything otherwise
+    ///
+    /// ```no_run
+    /// use nft_storage::{NftStorage, types::*};
+    /// use anyhow::Result;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     // provide the url and as second argument the token generated from nft storage dashboard
+    ///     let nft_storage = NftStorage::new("https://api.nft.storage", "token generated from nft storage");
+    ///     let mut items = Vec::new();
+    ///     for id in 0..10 {
+    ///         let image = UploadFile::new(format!("{}.png", id), std::fs::read(format!("images/{}.png", id))?);
+    ///         let metadata = NftMetadata::new(format!("My NFT #{}", id), "My collection");
+    ///         items.push(CollectionItem::new(image, metadata));
+    ///     }
+    ///     let collection = nft_storage.store_collection(items, CollectionOptions::default()).await?;
+    ///     println!("base uri: {}", collection.base_uri);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn store_collection(
+        &self,
+        items: Vec<CollectionItem>,
+        options: CollectionOptions,
+    ) -> Result<StoreCollectionResponse, NFTStorageError> {
+        if items.is_empty() {
+            return Err(NFTStorageError::InvalidInput(
+                "the collection has no items".to_string(),
+            ));
+        }
+        for item in &items {
+            item.metadata.validate(&self.metadata_limits)?;
+        }
+        let mut names = HashSet::new();
+        if let Some(item) = items.iter().find(|item| !names.insert(&item.file.name)) {
+            return Err(NFTStorageError::InvalidInput(format!(
+                "duplicated image name '{}'",
+                item.file.name
+            )));
+        }
+        let (images, metadata): (Vec<_>, Vec<_>) = items
+            .into_iter()
+            .map(|item| (item.file, item.metadata))
+            .unzip();
+        let image_names = images.iter().map(|f| f.name.clone()).collect::<Vec<_>>();
+        let images_cid = self.upload_directory(images).await?.value.cid;
+
+        let extension = if options.json_extension { ".json" } else { "" };
+        let metadata = metadata
+            .into_iter()
+            .zip(image_names)
+            .enumerate()
+            .map(|(index, (mut metadata, image_name))| {
+                metadata.image = format!("ipfs://{}/{}", images_cid, image_name);
+                (options.start_index + index as u64, metadata)
+            })
+            .collect::<Vec<_>>();
+        let mut metadata_files = Vec::with_capacity(metadata.len());
+        for (token_id, metadata) in &metadata {
+            let file_name = format!("{}{}", token_id, extension);
+            let content = serde_json::to_vec(metadata)?;
+            metadata_files.push(UploadFile::new(file_name, content).mime(JSON_CONTENT_TYPE));
+        }
+        let metadata_cid = self.upload_directory(metadata_files).await?.value.cid;
+
+        let base_uri = format!("ipfs://{}/", metadata_cid);
+        let reports = metadata
+            .into_iter()
+            .map(|(token_id, metadata)| CollectionItemReport {
+                token_id,
+                image_uri: metadata.image.clone(),
+                token_uri: format!("{}{}{}", base_uri, token_id, extension),
+                metadata,
+            })
+            .collect();
+        Ok(StoreCollectionResponse {
+            images_cid,
+            metadata_cid,
+            base_uri,
+            items: reports,
+        })
+    }
+
+    /// Store a Solana NFT with Metaplex metadata
+    ///
+    /// `metadata` is validated before uploading anything, then the `image` is uploaded and its `ipfs://` uri is set as
+    /// `image` and added first to `properties.files` before uploading the metadata json. Both cids are returned
+    ///
+    /// ```no_run
+    /// use nft_storage::{NftStorage, types::*};
+    /// use anyhow::Result;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     // provide the url and as second argument the token generated from nft storage dashboard
+    ///     let nft_storage = NftStorage::new("https://api.nft.storage", "token generated from nft storage");
+    ///     let image = UploadFile::new("my_nft.png", std::fs::read("my_nft.png")?);
+    ///     // create the nft metadata
+    ///     let metadata = MetaplexMetadata::new("My NFT name", "MNFT", "My NFT description")
+    ///         .seller_fee_basis_points(500)
+    ///         .creator("7fUbGpDCNT5jC3n4sQtt4zXoBH8G7VdQKQcbNzKmDhSw", 100)
+    ///         .category("image");
+    ///     // store an nft
+    ///     let store_nft: StoreMetaplexResponse = nft_storage.store_nft_metaplex(image, metadata).await?;
+    ///     println!("token uri: ipfs://{}", store_nft.metadata_cid);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn store_nft_metaplex(
+        &self,
+        image: UploadFile,
+        mut metadata: MetaplexMetadata,
+    ) -> Result<StoreMetaplexResponse, NFTStorageError> {
+        metadata.validate()?;
+        let image_type = image
+            .content_type()
+            .unwrap_or_else(|| "application/octet-stream".to_string());
+        // upload the image and point the metadata to it
+        let image_cid = self.upload_file(image.content).await?.value.cid;
+        let image_uri = format!("ipfs://{}", image_cid);
+        metadata.image = image_uri.clone();
+        metadata.properties.files.insert(
+            0,
+            MetaplexFile {
+                uri: image_uri,
+                file_type: image_type,
+            },
+        );
+        let metadata_json_bytes = serde_json::to_vec(&metadata)?;
+        let metadata_cid = self.upload_file(metadata_json_bytes).await?.value.cid;
+        Ok(StoreMetaplexResponse {
+            image_cid,
+            metadata_cid,
+            metadata,
+        })
+    }
+
+    /// Store an ERC-1155 compatible NFT using the nft storage `/store` endpoint
+    ///
+    /// `metadata` is sent as the `meta` part of the form, `image` is the nft image and `extra_files` are additional
+    /// files that will be referenced in `properties.files` of the stored metadata.
+    ///
+    /// nft storage replaces the files with their `ipfs://` urls and stores the resulting `metadata.json`, the token uri
+    /// is returned in `value.url`. The `image` must have an `image/*` mime type.
+    ///
+    /// ```no_run
+    /// use nft_storage::{NftStorage, types::*};
+    /// use anyhow::Result;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     // provide the url and as second argument the token generated from nft storage dashboard
+    ///     let nft_storage = NftStorage::new("https://api.nft.storage", "token generated from nft storage");
+    ///     // read a file in order to have a Vec<u8> the same from a form-data
+    ///     let image = UploadFile::new("my_nft.png", std::fs::read("my_nft.png")?).mime("image/png");
+    ///     // create the nft metadata
+    ///     let metadata = NftMetadata::new("My NFT name", "My NFT description");
+    ///     // store an nft
+    ///     let store_nft: StoreResponse  = nft_storage.store_erc1155(metadata, image, vec![]).await?;
+    ///     println!("token uri: {}", store_nft.value.url);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn store_erc1155(
+        &self,
+        metadata: NftMetadata,
+        image: UploadFile,
+        extra_files: Vec<UploadFile>,
+    ) -> Result<StoreResponse, NFTStorageError> {
+        metadata.validate(&self.metadata_limits)?;
+        self.check_request_size(
+            extra_files
+                .iter()
+                .map(|file| file.content.len() as u64)
+                .sum::<u64>()
+                + image.content.len() as u64,
+        )?;
+        let url = self.endpoint("store");
+        // the files are referenced by the form part name, nft storage will replace them with their ipfs url
+        let mut form = Form::new()
+            .text("meta", serde_json::to_string(&metadata)?)
+            .part("image", image.into_part()?);
+        for (index, file) in extra_files.into_iter().enumerate() {
+            form = form.part(format!("properties.files.{}", index), file.into_part()?);
+        }
+        let request = self.client.post(url).multipart(form);
+        let store: StoreResponse = self.request(request).await?;
+        #[cfg(feature = "cache")]
+        self.invalidate_cache(&store.value.ipnft);
+        Ok(store)
+    }
+
+    /// Delete an NFT
+    ///
+    /// `cid` is the ipfs hash, every file/nft has it's unique cid, an `InvalidCid` error is returned without
+    /// making any request if it is not a valid CIDv0 or CIDv1 and a `NotFound` error if it isn't stored
+    ///
+    /// ```no_run
+    /// use nft_storage::{NftStorage, types::*};
+    /// use anyhow::Result;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     // provide the url and as second argument the token generated from nft storage dashboard
+    ///     let nft_storage = NftStorage::new("https://api.nft.storage", "token generated from nft storage");
+    ///     // delete nft by cid
+    ///     let delete_nft: DeleteNftResponse  = nft_storage.delete_nft("bafybeihflij24dndd6qo3aacbbysuzuygis7yurvrzxp3uk7bk5kfvfsdt").await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// The empty, truncated and malformed cids are rejected before the request is sent
+    /// ```
+    /// use nft_storage::{NFTStorageError, NftStorage};
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let nft_storage = NftStorage::new("https://api.nft.storage", "token");
+    /// let gateway_url = "https://ipfs.io/ipfs/QmbWqxBEKC3P8tqsKc98xmWNzrzDtRLMiMPL8wBuTGsMnR";
+    /// for cid in ["", "bafy", "Qm", "not a cid", gateway_url] {
+    ///     let result = nft_storage.delete_nft(cid).await;
+    ///     assert!(matches!(result, Err(NFTStorageError::InvalidCid(_))));
+    /// }
+    /// # }
+    /// ```
+    ///
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(cid = tracing::field::Empty)))]
+    pub async fn delete_nft<C>(&self, cid: C) -> Result<DeleteNftResponse, NFTStorageError>
+    where
+        C: TryInto<Cid>,
+        NFTStorageError: From<C::Error>,
+    {
+        // check the cid before making the request
+        let cid: Cid = cid.try_into()?;
+        #[cfg(feature = "tracing")]
+        tracing::Span::current().record("cid", cid.as_str());
+        self.delete_cid(cid, false).await
+    }
+
+    /// Delete an NFT succeeding also when it isn't stored
+    ///
+    /// It works like `delete_nft` but a `NotFound` error, returned when the nft was already deleted by another
+    /// worker, is mapped to a successful response. The other errors are still returned
+    /// ```
+    /// # #[cfg(feature = "test-util")]
+    /// # #[tokio::main]
+    /// # async fn main() -> anyhow::Result<()> {
+    /// use nft_storage::test_util::FakeNftStorage;
+    ///
+    /// let fake = FakeNftStorage::start().await?;
+    /// let nft_storage = fake.client();
+    /// let cid = nft_storage.upload_file(b"hello".to_vec()).await?.value.cid;
+    ///
+    /// // 200, the nft is deleted
+    /// assert!(nft_storage.delete_nft_idempotent(&cid).await?.ok);
+    /// // 404, the nft was already deleted
+    /// assert!(nft_storage.delete_nft_idempotent(&cid).await?.ok);
+    /// assert!(nft_storage.delete_nft(&cid).await.unwrap_err().is_not_found());
+    /// // 500, the other errors are returned after the retries
+    /// fake.fail_next(500, 4);
+    /// let error = nft_storage.delete_nft_idempotent(&cid).await.unwrap_err();
+    /// assert_eq!(error.status(), Some(500));
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "test-util"))]
+    /// # fn main() {}
+    /// ```
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(cid = tracing::field::Empty)))]
+    pub async fn delete_nft_idempotent<C>(
+        &self,
+        cid: C,
+    ) -> Result<DeleteNftResponse, NFTStorageError>
+    where
+        C: TryInto<Cid>,
+        NFTStorageError: From<C::Error>,
+    {
+        let cid: Cid = cid.try_into()?;
+        #[cfg(feature = "tracing")]
+        tracing::Span::current().record("cid", cid.as_str());
+        self.delete_cid(cid, true).await
+    }
+
+    /// Delete the nft `cid`, when `idempotent` is set a missing nft is deleted successfully
+    async fn delete_cid(
+        &self,
+        cid: Cid,
+        idempotent: bool,
+    ) -> Result<DeleteNftResponse, NFTStorageError> {
+        // create the url
+        let url = self.endpoint(cid.as_str());
+        // make the request to the nft storage api
+        let result = self.request_nft(self.client.delete(url), &cid).await;
+        // the cached responses are stale even if the nft was already deleted
+        #[cfg(feature = "cache")]
+        self.invalidate_cache(&cid);
+        match result {
+            Err(NFTStorageError::NotFound { .. }) if idempotent => {
+                #[cfg(feature = "tracing")]
+                tracing::debug!(cid = %cid, "nft already deleted");
+                Ok(DeleteNftResponse { ok: true })
+            }
+            result => result,
+        }
+    }
+
+    /// Delete all NFT
+    ///
+    /// ⚠ WARNING! ⚠
+    ///
+    /// It will fetch and delete all nfts
+    ///
+    /// This method is meant for developing purposes, it can be quite dangerous in production.
+    ///
+    /// A failed deletion doesn't stop the others, the returned summary contains the deleted and the failed cids.
+    /// The nfts already deleted by another client are counted as deleted, so concurrent runs don't fail
+    /// ```no_run
+    /// use nft_storage::{NftStorage, types::*};
+    /// use anyhow::Result;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     // provide the url and as second argument the token generated from nft storage dashboard
+    ///     let nft_storage = NftStorage::new("https://api.nft.storage", "token generated from nft storage");
+    ///     // delete all nfts
+    ///     let summary: DeleteAllSummary  = nft_storage.delete_all_nft().await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// An nft that can't be deleted is reported once, a listing returning only those nfts ends with
+    /// `DeleteAllStalled` after `max_stalled_passes` listings instead of looping forever
+    /// ```
+    /// # #[cfg(feature = "test-util")]
+    /// # #[tokio::main]
+    /// # async fn main() -> anyhow::Result<()> {
+    /// use nft_storage::{test_util::{fixtures, MockTransport}, NFTStorageError};
+    /// use serde_json::json;
+    ///
+    /// let stubborn = json!({ "ok": false, "error": { "name": "HTTPError", "message": "system object" } });
+    /// // the last page contains a single nft that can't be deleted
+    /// let mock = MockTransport::new();
+    /// mock.push_response(200, vec![], fixtures::LIST_RESPONSE);
+    /// mock.push_json(500, stubborn.clone());
+    /// let summary = mock.client().delete_all_nft().await?;
+    /// assert!(summary.deleted.is_empty());
+    /// assert_eq!(summary.failed.len(), 1);
+    /// assert!(matches!(summary.failed[0].1, NFTStorageError::ApiError { status: 500, .. }));
+    ///
+    /// // a full page of nfts that can't be deleted is returned by every listing
+    /// let page = (0..100)
+    ///     .map(|i| json!({ "cid": format!("bafkreinft{}", i), "created": format!("2021-12-02T08:{:02}:00Z", i % 60) }))
+    ///     .collect::<Vec<_>>();
+    /// let mock = MockTransport::new();
+    /// mock.push_json(200, json!({ "ok": true, "value": page }));
+    /// for _ in 0..100 {
+    ///     mock.push_json(500, stubborn.clone());
+    /// }
+    /// for _ in 0..3 {
+    ///     mock.push_json(200, json!({ "ok": true, "value": page }));
+    /// }
+    /// let error = mock.client().delete_all_nft().await.unwrap_err();
+    /// assert!(matches!(error, NFTStorageError::DeleteAllStalled { passes: 3, deleted: 0, failed: 100 }));
+    /// // every nft is deleted only once
+    /// assert_eq!(mock.requests().len(), 104);
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "test-util"))]
+    /// # fn main() {}
+    /// ```
+    ///
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn delete_all_nft(&self) -> Result<DeleteAllSummary, NFTStorageError> {
+        self.delete_all_nft_with_progress(|_| {}).await
+    }
+
+    /// Delete all NFT reporting the progress
+    ///
+    /// It works like `delete_all_nft` but `on_progress` is called after every deletion
+    /// ```no_run
+    /// use nft_storage::{NftStorage, types::*};
+    /// use anyhow::Result;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     // provide the url and as second argument the token generated from nft storage dashboard
+    ///     let nft_storage = NftStorage::new("https://api.nft.storage", "token generated from nft storage");
+    ///     // delete all nfts printing the progress
+    ///     let summary: DeleteAllSummary  = nft_storage
+    ///         .delete_all_nft_with_progress(|progress| {
+    ///             println!("{}/{} {} deleted: {}", progress.index + 1, progress.total, progress.cid, progress.deleted)
+    ///         })
+    ///         .await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn delete_all_nft_with_progress<F>(
+        &self,
+        on_progress: F,
+    ) -> Result<DeleteAllSummary, NFTStorageError>
+    where
+        F: FnMut(DeleteProgress),
+    {
+        self.delete_all_nft_with_options(DeleteAllOptions::default(), on_progress)
+            .await
+    }
+
+    /// Delete all NFT using the provided options
+    ///
+    /// The nfts are listed page by page from the newest to the oldest, the nfts that can't be deleted are reported in
+    /// the summary and skipped. If the api keeps returning only nfts already processed for
+    /// `options.max_stalled_passes` listings in a row it stops with `NFTStorageError::DeleteAllStalled`.
+    ///
+    /// The nfts of a page are deleted running at most `options.concurrency` requests at the same time, configure a
+    /// rate limiter on the client to stay under the api limits.
+    ///
+    /// When `options.dry_run` is true the nfts are only listed and returned in `would_delete`, `options.confirm` can
+    /// be used to skip some nfts and `options.before` to delete only the nfts created before a timestamp.
+    ///
+    /// `on_progress` is called after every deletion
+    /// ```no_run
+    /// use nft_storage::{NftStorage, types::*};
+    /// use anyhow::Result;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     // provide the url and as second argument the token generated from nft storage dashboard
+    ///     let nft_storage = NftStorage::new("https://api.nft.storage", "token generated from nft storage");
+    ///     // check which nfts older than a date and not containing metadata would be deleted
+    ///     let options = DeleteAllOptions {
+    ///         dry_run: true,
+    ///         before: Some("2021-12-01T00:00:00Z".parse()?),
+    ///         confirm: Some(Box::new(|nft| nft.files.iter().all(|f| f.name != "metadata.json"))),
+    ///         ..Default::default()
+    ///     };
+    ///     let summary: DeleteAllSummary  = nft_storage.delete_all_nft_with_options(options, |_| {}).await?;
+    ///     println!("{:?}", summary.would_delete);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// A dry run stops with `DeleteAllStalled` when the listing cursor doesn't move, like when a whole page has the
+    /// same creation time
+    /// ```
+    /// # #[cfg(feature = "test-util")]
+    /// # #[tokio::main]
+    /// # async fn main() -> anyhow::Result<()> {
+    /// use nft_storage::{test_util::MockTransport, types::*, NFTStorageError};
+    /// use serde_json::json;
+    ///
+    /// let page = (0..100)
+    ///     .map(|i| json!({ "cid": format!("bafkreinft{}", i), "created": "2021-12-02T08:52:33.461+00:00" }))
+    ///     .collect::<Vec<_>>();
+    /// let mock = MockTransport::new();
+    /// for _ in 0..10 {
+    ///     mock.push_json(200, json!({ "ok": true, "value": page }));
+    /// }
+    /// let nft_storage = mock.client();
+    ///
+    /// let options = DeleteAllOptions { dry_run: true, max_stalled_passes: 3, ..Default::default() };
+    /// let error = nft_storage.delete_all_nft_with_options(options, |_| {}).await.unwrap_err();
+    /// assert!(matches!(error, NFTStorageError::DeleteAllStalled { passes: 3, deleted: 0, failed: 0 }));
+    /// // the first listing moves the cursor, the next 3 return the same page
+    /// assert_eq!(mock.requests().len(), 4);
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "test-util"))]
+    /// # fn main() {}
+    /// ```
+    ///
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn delete_all_nft_with_options<F>(
+        &self,
+        mut options: DeleteAllOptions,
+        mut on_progress: F,
+    ) -> Result<DeleteAllSummary, NFTStorageError>
+    where
+        F: FnMut(DeleteProgress),
+    {
+        let mut summary = DeleteAllSummary::default();
+        // cids already processed, the same nft can be returned by more listings
+        let mut attempted = HashSet::new();
+        // listings in a row that didn't return new nfts
+        let mut stalled_passes = 0;
+        // start listing from the provided timestamp so only the older nfts are deleted
+        let mut before = options.before.as_ref().map(to_rfc3339);
+        // create a loop to iterate and reqwest all nfts
+        loop {
+            if is_cancelled(&mut options.cancel) {
+                return Err(NFTStorageError::Cancelled(Box::new(
+                    PartialResult::DeleteAll(summary),
+                )));
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            if self.deadline_elapsed() {
+                return Err(NFTStorageError::DeadlineExceeded(Box::new(
+                    PartialResult::DeleteAll(summary),
+                )));
+            }
+            // get the next 100 nfts, the failed ones stay behind the cursor
+            let page = match self
+                .list_page(before.as_deref(), Some(MAX_LIST_LIMIT))
+                .await
+            {
+                Ok(page) => page.value,
+                Err(e) => return Err(e.with_partial_result(|| PartialResult::DeleteAll(summary))),
+            };
+            let is_last_page = page.len() < MAX_LIST_LIMIT as usize;
+            // the oldest nft is the cursor of the next page
+            let next_before = page.iter().map(|f| &f.created).min().map(to_rfc3339);
+            let nfts = page
+                .into_iter()
+                .filter(|f| attempted.insert(f.cid.clone()))
+                .collect::<Vec<_>>();
+            let found_new_nfts = !nfts.is_empty();
+            // skip the nfts rejected by the confirm predicate
+            let nfts = nfts
+                .into_iter()
+                .filter(|f| match &options.confirm {
+                    Some(confirm) if !confirm(f) => {
+                        summary.skipped.push(f.cid.clone());
+                        false
+                    }
+                    _ => true,
+                })
+                .collect::<Vec<_>>();
+            if options.dry_run {
+                summary
+                    .would_delete
+                    .extend(nfts.iter().map(|f| f.cid.clone()));
+                if is_last_page {
+                    break;
+                }
+                // nothing is deleted in a dry run, so a cursor that doesn't move returns the same page again
+                if next_before == before {
+                    stalled_passes += 1;
+                    if stalled_passes >= options.max_stalled_passes {
+                        return Err(NFTStorageError::DeleteAllStalled {
+                            passes: stalled_passes,
+                            deleted: 0,
+                            failed: 0,
+                        });
+                    }
+                } else {
+                    stalled_passes = 0;
+                }
+                before = next_before;
+                continue;
+            }
+            if !found_new_nfts {
+                // this mean no more nft are stored
+                if is_last_page {
+                    break;
+                }
+                stalled_passes += 1;
+                if stalled_passes >= options.max_stalled_passes {
+                    return Err(NFTStorageError::DeleteAllStalled {
+                        passes: stalled_passes,
+                        deleted: summary.deleted.len(),
+                        failed: summary.failed.len(),
+                    });
+                }
+            } else {
+                stalled_passes = 0;
+            }
+            // delete the page running at most `concurrency` deletions at the same time, no task is spawned
+            // so dropping the future stops all of them. After a cancellation no new deletion is started
+            let cancel = &mut options.cancel;
+            let mut deletions = stream::iter(nfts)
+                .take_while(|_| future::ready(!is_cancelled(cancel)))
+                .map(|e| async move {
+                    // another client deleting the same nfts isn't a failure
+                    let result = self.delete_cid(e.cid.clone(), true).await;
+                    (e.cid, result)
+                })
+                .buffer_unordered(options.concurrency.max(1));
+            while let Some((cid, result)) = deletions.next().await {
+                let deleted = match result {
+                    Ok(_) => {
+                        #[cfg(feature = "tracing")]
+                        tracing::info!(cid = %cid, "deleted nft");
+                        summary.deleted.push(cid.clone());
+                        true
+                    }
+                    Err(err) => {
+                        #[cfg(feature = "tracing")]
+                        tracing::error!(cid = %cid, error = %err, "failed to delete nft");
+                        summary.failed.push((cid.clone(), err));
+                        false
+                    }
+                };
+                on_progress(DeleteProgress {
+                    index: summary.deleted.len() + summary.failed.len() - 1,
+                    total: attempted.len() - summary.skipped.len(),
+                    skipped: summary.skipped.len(),
+                    cid,
+                    deleted,
+                });
+            }
+            // the remaining nfts of the page weren't deleted
+            drop(deletions);
+            if is_cancelled(&mut options.cancel) {
+                return Err(NFTStorageError::Cancelled(Box::new(
+                    PartialResult::DeleteAll(summary),
+                )));
+            }
+            if is_last_page {
+                break;
+            }
+            before = next_before;
+        }
+
+        Ok(summary)
+    }
+
+    /// Retrive an NFT
+    ///
+    /// It will fetch an nft from `cid`, a `NotFound` error is returned if it isn't stored, when it is a directory upload `file_links` contains the links of every file
+    /// ```no_run
+    /// use nft_storage::{NftStorage, types::*};
+    /// use anyhow::Result;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     // provide the url and as second argument the token generated from nft storage dashboard
+    ///     let nft_storage = NftStorage::new("https://api.nft.storage", "token generated from nft storage");
+    ///     // get nft by cid
+    ///     let get_nft: GetNftResponse  = nft_storage.get_nft("bafybeihflij24dndd6qo3aacbbysuzuygis7yurvrzxp3uk7bk5kfvfsfg").await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(cid = tracing::field::Empty)))]
+    pub async fn get_nft<C>(&self, cid: C) -> Result<GetNftResponse, NFTStorageError>
+    where
+        C: TryInto<Cid>,
+        NFTStorageError: From<C::Error>,
+    {
+        let cid: Cid = cid.try_into()?;
+        #[cfg(feature = "tracing")]
+        tracing::Span::current().record("cid", cid.as_str());
+        self.get_nft_cached(cid, false).await
+    }
+
+    /// Retrive an NFT and its metadata
+    ///
+    /// After getting the nft from the api its `metadata.json` file, or its content when the upload is a single json
+    /// file, is downloaded from the client gateway and parsed, the file is found like in [`NftValue::token_uri`]. The metadata is `None` when the nft has no metadata,
+    /// the file isn't on the gateway or it isn't json. The other gateway failures are returned as errors,
+    /// [`NFTStorageError::is_gateway_error`] tells them from the api failures
+    /// ```
+    /// # #[cfg(feature = "test-util")]
+    /// # #[tokio::main]
+    /// # async fn main() -> anyhow::Result<()> {
+    /// use nft_storage::test_util::MockTransport;
+    /// use serde_json::json;
+    ///
+    /// let cid = "bafybeibjt6afd4u7or3olgfhy7cc2t2zfpf436w6limuiyp3347t23andy";
+    /// let nft = json!({ "ok": true, "value": { "cid": cid, "files": [{ "name": "metadata.json", "type": "application/json" }] } });
+    /// let mock = MockTransport::new();
+    /// mock.push_json(200, nft.clone());
+    /// mock.push_json(200, json!({ "name": "My NFT", "description": "My description", "image": "ipfs://bafy/image.png" }));
+    /// mock.push_json(200, nft.clone());
+    /// mock.push_response(404, vec![], "not found");
+    /// mock.push_json(200, nft);
+    /// mock.push_response(502, vec![], "bad gateway");
+    /// let nft_storage = mock.client();
+    ///
+    /// let detailed = nft_storage.get_nft_with_metadata(cid).await?;
+    /// assert_eq!(detailed.metadata.unwrap().name, "My NFT");
+    /// assert_eq!(mock.requests()[1].url.path(), format!("/ipfs/{}/metadata.json", cid));
+    ///
+    /// // the metadata isn't on the gateway
+    /// let detailed = nft_storage.get_nft_with_metadata(cid).await?;
+    /// assert_eq!(detailed.metadata, None);
+    ///
+    /// let error = nft_storage.get_nft_with_metadata(cid).await.unwrap_err();
+    /// assert!(error.is_gateway_error());
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "test-util"))]
+    /// # fn main() {}
+    /// ```
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(cid = tracing::field::Empty)))]
+    pub async fn get_nft_with_metadata<C>(&self, cid: C) -> Result<GetNftDetailed, NFTStorageError>
+    where
+        C: TryInto<Cid>,
+        NFTStorageError: From<C::Error>,
+    {
+        let cid: Cid = cid.try_into()?;
+        #[cfg(feature = "tracing")]
+        tracing::Span::current().record("cid", cid.as_str());
+        let info = self.get_nft_cached(cid, false).await?;
+        let nft = &info.value;
+        // the metadata file is found like in the token uri, an empty path is a single json file
+        let path = match nft.token_path() {
+            Some(path) => path,
+            None => {
+                return Ok(GetNftDetailed {
+                    info,
+                    metadata: None,
+                    raw_metadata: None,
+                })
+            }
+        };
+        let path = Some(path.as_str()).filter(|path| !path.is_empty());
+        let content = match self
+            .gateway_get(&self.gateway, nft.cid.as_str(), path)
+            .await
+        {
+            Ok(response) => read_gateway_body(response).await?,
+            Err(NFTStorageError::GatewayNotFound(_)) => Bytes::new(),
+            Err(e) => return Err(e),
+        };
+        // a missing or non json file isn't an error, the nft simply has no metadata
+        let raw_metadata = serde_json::from_slice::<Value>(&content).ok();
+        let metadata = raw_metadata
+            .clone()
+            .and_then(|raw| serde_json::from_value::<NftMetadata>(raw).ok());
+        Ok(GetNftDetailed {
+            info,
+            metadata,
+            raw_metadata,
+        })
+    }
+
+    /// Retrive an NFT choosing how the response cache is used
+    ///
+    /// With `options.bypass_cache` the nft is always requested to the api, the cache is updated with the response
+    /// ```no_run
+    /// use nft_storage::{NftStorage, types::*};
+    /// use std::time::Duration;
+    /// use anyhow::Result;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     // provide the url and as second argument the token generated from nft storage dashboard
+    ///     let nft_storage = NftStorage::builder("https://api.nft.storage", "token generated from nft storage")
+    ///         .cache_ttl(Duration::from_secs(30))
+    ///         .build()?;
+    ///     let options = ReadOptions { bypass_cache: true };
+    ///     let get_nft: GetNftResponse  = nft_storage.get_nft_with_options("bafybeihflij24dndd6qo3aacbbysuzuygis7yurvrzxp3uk7bk5kfvfsfg", &options).await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[cfg(feature = "cache")]
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(cid = tracing::field::Empty)))]
+    pub async fn get_nft_with_options<C>(
+        &self,
+        cid: C,
+        options: &ReadOptions,
+    ) -> Result<GetNftResponse, NFTStorageError>
+    where
+        C: TryInto<Cid>,
+        NFTStorageError: From<C::Error>,
+    {
+        let cid: Cid = cid.try_into()?;
+        #[cfg(feature = "tracing")]
+        tracing::Span::current().record("cid", cid.as_str());
+        self.get_nft_cached(cid, options.bypass_cache).await
+    }
+
+    /// Get the nft from the cache or from the api caching the response
+    #[cfg_attr(not(feature = "cache"), allow(unused_variables))]
+    async fn get_nft_cached(
+        &self,
+        cid: Cid,
+        bypass_cache: bool,
+    ) -> Result<GetNftResponse, NFTStorageError> {
+        #[cfg(feature = "cache")]
+        if let Some(nft) = self.cache_lookup(bypass_cache, |cache| cache.nft(&cid)) {
+            return Ok(nft);
+        }
+        let url = self.endpoint(cid.as_str());
+        let mut body: GetNftResponse = self.request_nft(self.client.get(url), &cid).await?;
+        // add some convinient links
+        body.value.links =
+            GatewayLinks::with_gateways(body.value.cid.as_str(), "", &self.link_gateways);
+        // add the links of every file when it is a directory upload
+        let root = &body.value.cid;
+        body.value.file_links = body
+            .value
+            .files
+            .iter()
+            .map(|f| FileLinks {
+                name: f.name.clone(),
+                links: GatewayLinks::with_gateways(root.as_str(), &f.name, &self.link_gateways),
+            })
+            .collect();
+        #[cfg(feature = "cache")]
+        if let Some(cache) = &self.cache {
+            cache.insert_nft(&cid, body.clone());
+        }
+
+        Ok(body)
+    }
+
+    /// Download the content of `cid` through the ipfs gateway
+    ///
+    /// The gateway is `https://nftstorage.link/ipfs/` unless configured with `NftStorageBuilder::gateway`,
+    /// redirects are followed and a missing cid returns a `GatewayNotFound` error
+    /// ```no_run
+    /// use nft_storage::NftStorage;
+    /// use anyhow::Result;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     // provide the url and as second argument the token generated from nft storage dashboard
+    ///     let nft_storage = NftStorage::new("https://api.nft.storage", "token generated from nft storage");
+    ///     // download the file content
+    ///     let content = nft_storage.download("bafybeihflij24dndd6qo3aacbbysuzuygis7yurvrzxp3uk7bk5kfvfsfg").await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(cid = %cid)))]
+    pub async fn download(&self, cid: &str) -> Result<Bytes, NFTStorageError> {
+        let response = self.gateway_get(&self.gateway, cid, None).await?;
+        read_gateway_body(response).await
+    }
+
+    /// Download a single file of a directory upload through the ipfs gateway
+    ///
+    /// `path_in_dir` is the path of the file inside the directory, like `metadata.json`
+    /// ```no_run
+    /// use nft_storage::NftStorage;
+    /// use anyhow::Result;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     // provide the url and as second argument the token generated from nft storage dashboard
+    ///     let nft_storage = NftStorage::new("https://api.nft.storage", "token generated from nft storage");
+    ///     // download the metadata of an nft
+    ///     let metadata = nft_storage
+    ///         .download_file("bafybeihflij24dndd6qo3aacbbysuzuygis7yurvrzxp3uk7bk5kfvfsfg", "metadata.json")
+    ///         .await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(cid = %cid)))]
+    pub async fn download_file(
+        &self,
+        cid: &str,
+        path_in_dir: &str,
+    ) -> Result<Bytes, NFTStorageError> {
+        let response = self
+            .gateway_get(&self.gateway, cid, Some(path_in_dir))
+            .await?;
+        read_gateway_body(response).await
+    }
+
+    /// Download the content of `cid` through the ipfs gateway straight to the `output` file
+    ///
+    /// The content is streamed to disk so the memory usage stays flat even for very big files,
+    /// `path_in_dir` works like in `download_file`. It returns the number of bytes written
+    /// ```no_run
+    /// use nft_storage::NftStorage;
+    /// use anyhow::Result;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     // provide the url and as second argument the token generated from nft storage dashboard
+    ///     let nft_storage = NftStorage::new("https://api.nft.storage", "token generated from nft storage");
+    ///     // save the file on disk
+    ///     let size = nft_storage
+    ///         .download_to_path("bafybeihflij24dndd6qo3aacbbysuzuygis7yurvrzxp3uk7bk5kfvfsfg", None, "my_video.mp4")
+    ///         .await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    #[cfg(not(target_arch = "wasm32"))]
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(cid = %cid)))]
+    pub async fn download_to_path<P>(
+        &self,
+        cid: &str,
+        path_in_dir: Option<&str>,
+        output: P,
+    ) -> Result<u64, NFTStorageError>
+    where
+        P: AsRef<Path>,
+    {
+        let response = self.gateway_get(&self.gateway, cid, path_in_dir).await?;
+        let url = response.url().to_string();
+        let mut file = File::create(output).await?;
+        let mut stream = response.bytes_stream();
+        let mut written = 0;
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk.map_err(|e| gateway_error(e, &url))?;
+            file.write_all(&chunk).await?;
+            written += chunk.len() as u64;
+        }
+        file.flush().await?;
+
+        Ok(written)
+    }
+
+    /// Download the content of `cid` from many ipfs gateways at the same time
+    ///
+    /// The content of the first gateway that responds successfully within `timeout` is returned and the other
+    /// requests are dropped. If every gateway fails an `AllGatewaysFailed` error lists the failure of each one
+    /// ```no_run
+    /// use nft_storage::NftStorage;
+    /// use anyhow::Result;
+    /// use std::time::Duration;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     // provide the url and as second argument the token generated from nft storage dashboard
+    ///     let nft_storage = NftStorage::new("https://api.nft.storage", "token generated from nft storage");
+    ///     let gateways = vec![
+    ///         "https://nftstorage.link/ipfs/".to_string(),
+    ///         "https://dweb.link/ipfs/".to_string(),
+    ///         "https://ipfs.io/ipfs/".to_string(),
+    ///     ];
+    ///     // download from the fastest gateway
+    ///     let content = nft_storage
+    ///         .download_race("bafybeihflij24dndd6qo3aacbbysuzuygis7yurvrzxp3uk7bk5kfvfsfg", &gateways, Duration::from_secs(30))
+    ///         .await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(cid = %cid)))]
+    pub async fn download_race(
+        &self,
+        cid: &str,
+        gateways: &[String],
+        timeout_after: Duration,
+    ) -> Result<Bytes, NFTStorageError> {
+        if gateways.is_empty() {
+            return Err(NFTStorageError::InvalidInput(
+                "at least one gateway is required".to_string(),
+            ));
+        }
+        let (_, content) = self.race_gateways(cid, gateways, timeout_after).await?;
+        Ok(content)
+    }
+
+    /// Download `cid` from the `gateways` at the same time returning the first successful gateway and its content
+    async fn race_gateways<'a>(
+        &self,
+        cid: &str,
+        gateways: &'a [String],
+        timeout_after: Duration,
+    ) -> Result<(&'a String, Bytes), NFTStorageError> {
+        validate_cid(cid)?;
+        let mut downloads = gateways
+            .iter()
+            .map(|gateway| async move {
+                let download = async {
+                    let response = self.gateway_get(gateway, cid, None).await?;
+                    read_gateway_body(response).await
+                };
+                let result = match timeout(timeout_after, download).await {
+                    Ok(result) => result,
+                    Err(_) => Err(NFTStorageError::GatewayTimeout(gateway.clone())),
+                };
+                (gateway, result)
+            })
+            .collect::<FuturesUnordered<_>>();
+
+        let mut failures = Vec::with_capacity(gateways.len());
+        while let Some((gateway, result)) = downloads.next().await {
+            match result {
+                // returning drops the downloads still in flight
+                Ok(content) => return Ok((gateway, content)),
+                Err(e) => failures.push((gateway.clone(), e)),
+            }
+        }
+
+        Err(NFTStorageError::AllGatewaysFailed(failures))
+    }
+
+    /// Upload an arbitrary file on Nft storage
+    ///
+    /// It will upload an arbitrary file on ipfs backed up by nft storage and filecoin
+    ///
+    /// the max sise is around 30GB per file
+    /// ```no_run
+    /// use nft_storage::{NftStorage, types::*};
+    /// use anyhow::Result;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     // provide the url and as second argument the token generated from nft storage dashboard
+    ///     let nft_storage = NftStorage::new("https://api.nft.storage", "token generated from nft storage");
+    ///     // read a file in order to have a Vec<u8> the same from a form-data
+    ///     let file = std::fs::read("my_nft.jpg")?;
+    ///     // delete nft
+    ///     let upload_file: StoreNftResponse  = nft_storage.upload_file(file).await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn upload_file(&self, file: Vec<u8>) -> Result<StoreNftResponse, NFTStorageError> {
+        self.upload_request(self.upload_file_request(file)?).await
+    }
+
+    /// Upload an arbitrary file on Nft storage returning also the metadata of the response
+    ///
+    /// It works like `upload_file`, the `request_id` of the metadata identifies the request for nft storage support.
+    /// The `request_id` of a failed upload is in the `ApiError`, see [`NFTStorageError::request_id`]
+    /// ```no_run
+    /// use nft_storage::{NftStorage, types::*};
+    /// use anyhow::Result;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let nft_storage = NftStorage::new("https://api.nft.storage", "token generated from nft storage");
+    ///     let file = std::fs::read("my_nft.jpg")?;
+    ///     match nft_storage.upload_file_with_meta(file).await {
+    ///         Ok((upload, meta)) => println!("uploaded {} in request {:?}", upload.value.cid, meta.request_id),
+    ///         Err(e) => eprintln!("upload failed in request {:?}: {}", e.request_id(), e),
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn upload_file_with_meta(
+        &self,
+        file: Vec<u8>,
+    ) -> Result<(StoreNftResponse, ResponseMeta), NFTStorageError> {
+        self.upload_request_with_meta(self.upload_file_request(file)?)
+            .await
+    }
+
+    /// Upload a json document serializing `value`
+    ///
+    /// The document is serialized compactly and uploaded with the `application/json` content type. When `file_name`
+    /// is set it is uploaded in a directory so the name is preserved, use `upload_json_with_options` for a pretty
+    /// printed document
+    /// ```no_run
+    /// use nft_storage::{NftStorage, types::*};
+    /// use anyhow::Result;
+    /// use serde_json::json;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let nft_storage = NftStorage::new("https://api.nft.storage", "token generated from nft storage");
+    ///     let manifest = json!({ "version": 1, "items": ["a", "b"] });
+    ///     let uploaded = nft_storage.upload_json(&manifest, Some("manifest.json")).await?;
+    ///     // ipfs://<cid>/manifest.json
+    ///     println!("{}", uploaded.ipfs_uri);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn upload_json<T>(
+        &self,
+        value: &T,
+        file_name: Option<&str>,
+    ) -> Result<UploadJsonResponse, NFTStorageError>
+    where
+        T: Serialize + ?Sized,
+    {
+        let options = JsonUploadOptions {
+            file_name: file_name.map(|name| name.to_string()),
+            ..Default::default()
+        };
+        self.upload_json_with_options(value, options).await
+    }
+
+    /// Upload a json document serializing `value` with the provided options
+    ///
+    /// The same value serialized compactly or pretty printed has different cids
+    /// ```
+    /// # #[cfg(feature = "test-util")]
+    /// # #[tokio::main]
+    /// # async fn main() -> anyhow::Result<()> {
+    /// use nft_storage::{test_util::FakeNftStorage, types::*};
+    /// use serde_json::json;
+    ///
+    /// let fake = FakeNftStorage::start().await?;
+    /// let nft_storage = fake.client();
+    /// let record = json!({ "artist": "me", "year": 2022 });
+    ///
+    /// let compact = nft_storage.upload_json(&record, None).await?;
+    /// assert_eq!(compact.ipfs_uri, format!("ipfs://{}", compact.upload.value.cid));
+    ///
+    /// let options = JsonUploadOptions {
+    ///     file_name: Some("record.json".to_string()),
+    ///     pretty: true,
+    /// };
+    /// let pretty = nft_storage.upload_json_with_options(&record, options).await?;
+    /// assert_eq!(pretty.ipfs_uri, format!("ipfs://{}/record.json", pretty.upload.value.cid));
+    /// assert_ne!(pretty.upload.value.cid, compact.upload.value.cid);
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "test-util"))]
+    /// # fn main() {}
+    /// ```
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn upload_json_with_options<T>(
+        &self,
+        value: &T,
+        options: JsonUploadOptions,
+    ) -> Result<UploadJsonResponse, NFTStorageError>
+    where
+        T: Serialize + ?Sized,
+    {
+        let document = if options.pretty {
+            serde_json::to_vec_pretty(value)?
+        } else {
+            serde_json::to_vec(value)?
+        };
+        let file_name = options.file_name.as_deref();
+        let upload = self
+            .upload_typed(document, JSON_CONTENT_TYPE, file_name)
+            .await?;
+        // the name is trimmed by upload_directory, the one returned by the api is used
+        let path = match file_name {
+            Some(_) => upload
+                .value
+                .files
+                .first()
+                .map(|file| format!("/{}", file.name))
+                .unwrap_or_default(),
+            None => String::new(),
+        };
+        let ipfs_uri = format!("ipfs://{}{}", upload.value.cid, path);
+        Ok(UploadJsonResponse { upload, ipfs_uri })
+    }
+
+    /// Upload a utf-8 text, like a poem or a license
+    ///
+    /// The text is uploaded with the `text/plain; charset=utf-8` content type. When `file_name` is set it is uploaded
+    /// in a directory so the name is preserved and the text is at `ipfs://<cid>/<file_name>`, see
+    /// [`StoreNftResponse::token_uri`]
+    /// ```
+    /// # #[cfg(feature = "test-util")]
+    /// # #[tokio::main]
+    /// # async fn main() -> anyhow::Result<()> {
+    /// use nft_storage::{test_util::FakeNftStorage, types::*};
+    ///
+    /// let fake = FakeNftStorage::start().await?;
+    /// let nft_storage = fake.client();
+    ///
+    /// let poem = nft_storage.upload_text("roses are red", None).await?;
+    /// assert_eq!(fake.uploads()[0].content_type.as_deref(), Some("text/plain; charset=utf-8"));
+    /// assert_eq!(nft_storage.download(poem.value.cid.as_str()).await?, "roses are red");
+    ///
+    /// nft_storage.upload_text("MIT License", Some("LICENSE")).await?;
+    /// let part = &fake.uploads()[1].parts[0];
+    /// assert_eq!(part.file_name.as_deref(), Some("LICENSE"));
+    /// assert_eq!(part.content_type.as_deref(), Some("text/plain; charset=utf-8"));
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "test-util"))]
+    /// # fn main() {}
+    /// ```
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn upload_text(
+        &self,
+        text: &str,
+        file_name: Option<&str>,
+    ) -> Result<StoreNftResponse, NFTStorageError> {
+        self.upload_string(text.to_string(), file_name).await
+    }
+
+    /// Upload a utf-8 text taking its ownership
+    ///
+    /// It works like `upload_text` without copying the text
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn upload_string(
+        &self,
+        text: String,
+        file_name: Option<&str>,
+    ) -> Result<StoreNftResponse, NFTStorageError> {
+        self.upload_typed(text.into_bytes(), TEXT_CONTENT_TYPE, file_name)
+            .await
+    }
+
+    /// Upload an html document, like an on-chain html nft
+    ///
+    /// The document is uploaded with the `text/html; charset=utf-8` content type, the gateways render it only with
+    /// it. When `file_name` is set it is uploaded in a directory so the name is preserved
+    /// ```no_run
+    /// use nft_storage::{NftStorage, types::*};
+    /// use anyhow::Result;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let nft_storage = NftStorage::new("https://api.nft.storage", "token generated from nft storage");
+    ///     let html = "<!DOCTYPE html><html><body><canvas id=\"art\"></canvas></body></html>";
+    ///     let uploaded = nft_storage.upload_html(html, Some("index.html")).await?;
+    ///     // ipfs://<cid>/index.html
+    ///     println!("{:?}", uploaded.token_uri());
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn upload_html(
+        &self,
+        html: &str,
+        file_name: Option<&str>,
+    ) -> Result<StoreNftResponse, NFTStorageError> {
+        self.upload_typed(html.as_bytes().to_vec(), HTML_CONTENT_TYPE, file_name)
+            .await
+    }
+
+    /// Upload `content` with `content_type`, in a directory keeping `file_name` when it is set
+    async fn upload_typed(
+        &self,
+        content: Vec<u8>,
+        content_type: &str,
+        file_name: Option<&str>,
+    ) -> Result<StoreNftResponse, NFTStorageError> {
+        match file_name {
+            Some(file_name) => {
+                let file = UploadFile::new(file_name, content).mime(content_type);
+                self.upload_directory(vec![file]).await
+            }
+            None => {
+                self.check_request_size(content.len() as u64)?;
+                let url = self.endpoint("upload");
+                let request = self
+                    .client
+                    .post(url)
+                    .header(CONTENT_TYPE, content_type)
+                    .body(content);
+                self.upload_request(request).await
+            }
+        }
+    }
+
+    /// Build the request uploading a single file
+    fn upload_file_request(&self, file: Vec<u8>) -> Result<RequestBuilder, NFTStorageError> {
+        self.check_request_size(file.len() as u64)?;
+        let url = self.endpoint("upload");
+        // the file has no name so the type can be detected only from the content
+        let content_type = detect_mime("", &file);
+        let request = self.client.post(url).body(file);
+        Ok(match content_type {
+            Some(content_type) => request.header(CONTENT_TYPE, content_type),
+            None => request,
+        })
+    }
+
+    /// Upload an arbitrary file on Nft storage and check the cid returned by the api
+    ///
+    /// The cid is computed locally before uploading, a `CidMismatch` error is returned if the api assigned a
+    /// different one. Only files up to `cid::MAX_RAW_BLOCK_SIZE` (1 MiB) can be verified, bigger files are chunked by
+    /// nft storage and return an `InvalidInput` error without being uploaded
+    /// ```no_run
+    /// use nft_storage::{NftStorage, types::*};
+    /// use anyhow::Result;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     // provide the url and as second argument the token generated from nft storage dashboard
+    ///     let nft_storage = NftStorage::new("https://api.nft.storage", "token generated from nft storage");
+    ///     let file = std::fs::read("my_nft.jpg")?;
+    ///     // upload the file and check its cid
+    ///     let upload_file: StoreNftResponse  = nft_storage.upload_file_verified(file).await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn upload_file_verified(
+        &self,
+        file: Vec<u8>,
+    ) -> Result<StoreNftResponse, NFTStorageError> {
+        if file.len() > cid::MAX_RAW_BLOCK_SIZE {
+            return Err(NFTStorageError::InvalidInput(format!(
+                "the cid of files bigger than {} bytes can't be verified, got {} bytes",
+                cid::MAX_RAW_BLOCK_SIZE,
+                file.len()
+            )));
+        }
+        let expected = cid::compute_cid_v1_raw(&file);
+        let response = self.upload_file(file).await?;
+        ensure_cid_matches(&expected, &response.value.cid)?;
+        Ok(response)
+    }
+
+    /// Upload an arbitrary file on Nft storage and verify that the stored content is the uploaded one
+    ///
+    /// The cid of files up to `cid::MAX_RAW_BLOCK_SIZE` (1 MiB) is computed locally and compared to the returned one,
+    /// a `CidMismatch` error is returned if it differs. Bigger files, or every file with `options.force_readback`,
+    /// are downloaded back from the `options.gateways` at the same time like `download_race` and their sha2-256
+    /// digest is compared, a `DigestMismatch` error carrying both digests is returned if it differs.
+    ///
+    /// A new upload can take a while to be available on the gateways, when none of them returns the content the
+    /// upload is returned with `verified` set to false and the error in `readback_error`
+    /// ```no_run
+    /// use nft_storage::{NftStorage, types::*};
+    /// use anyhow::Result;
+    /// use std::time::Duration;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let nft_storage = NftStorage::new("https://api.nft.storage", "token generated from nft storage");
+    ///     let file = std::fs::read("contract.pdf")?;
+    ///     let options = VerifyOptions {
+    ///         gateways: vec!["https://nftstorage.link/ipfs".to_string(), "https://ipfs.io/ipfs".to_string()],
+    ///         timeout: Duration::from_secs(120),
+    ///         force_readback: true,
+    ///     };
+    ///     let upload = nft_storage.upload_and_verify(file, options).await?;
+    ///     if !upload.verified {
+    ///         println!("{} not verified yet: {:?}", upload.response.value.cid, upload.readback_error);
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn upload_and_verify(
+        &self,
+        file: Vec<u8>,
+        options: VerifyOptions,
+    ) -> Result<VerifiedUpload, NFTStorageError> {
+        let local_cid =
+            (file.len() <= cid::MAX_RAW_BLOCK_SIZE).then(|| cid::compute_cid_v1_raw(&file));
+        let readback = local_cid.is_none() || options.force_readback;
+        // the digest is needed only when the content is read back
+        let expected = readback.then(|| cid::sha256_hex(&file));
+        let response = self.upload_file(file).await?;
+        let actual_cid = &response.value.cid;
+        if let Some(expected) = &local_cid {
+            ensure_cid_matches(expected, actual_cid)?;
+        }
+        let expected = match expected {
+            Some(expected) => expected,
+            None => {
+                return Ok(VerifiedUpload {
+                    response,
+                    verified: true,
+                    method: VerificationMethod::LocalCid,
+                    gateway: None,
+                    readback_error: None,
+                })
+            }
+        };
+
+        let gateways = if options.gateways.is_empty() {
+            vec![self.gateway.clone()]
+        } else {
+            options.gateways
+        };
+        let (gateway, content) = match self
+            .race_gateways(actual_cid.as_str(), &gateways, options.timeout)
+            .await
+        {
+            Ok(readback) => readback,
+            Err(e) => {
+                #[cfg(feature = "tracing")]
+                tracing::warn!(cid = %actual_cid, error = %e, "content not available on the gateways");
+                return Ok(VerifiedUpload {
+                    response,
+                    verified: false,
+                    method: VerificationMethod::GatewayReadback,
+                    gateway: None,
+                    readback_error: Some(e),
+                });
+            }
+        };
+        let actual = cid::sha256_hex(&content);
+        if actual != expected {
+            return Err(NFTStorageError::DigestMismatch {
+                cid: actual_cid.clone(),
+                gateway: gateway.clone(),
+                expected,
+                actual,
+            });
+        }
+        let gateway = Some(gateway.clone());
+        Ok(VerifiedUpload {
+            response,
+            verified: true,
+            method: VerificationMethod::GatewayReadback,
+            gateway,
+            readback_error: None,
+        })
+    }
+
+    /// Upload an arbitrary file on Nft storage only if its content isn't already stored
+    ///
+    /// The cid is computed locally and checked with `check_nft` before uploading, so processing again the same file
+    /// costs a single check request. Files bigger than `cid::MAX_RAW_BLOCK_SIZE` (1 MiB) are chunked by nft storage
+    /// and their cid can't be computed, they are always uploaded and `UploadedUnchecked` is returned
+    /// ```no_run
+    /// use nft_storage::{NftStorage, types::*};
+    /// use anyhow::Result;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     // provide the url and as second argument the token generated from nft storage dashboard
+    ///     let nft_storage = NftStorage::new("https://api.nft.storage", "token generated from nft storage");
+    ///     let file = std::fs::read("my_nft.jpg")?;
+    ///     // upload the file only the first time
+    ///     match nft_storage.upload_file_if_absent(file).await? {
+    ///         UploadIfAbsentResponse::AlreadyStored(check) => println!("already stored {}", check.value.cid),
+    ///         upload => println!("uploaded {:?}", upload),
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn upload_file_if_absent(
+        &self,
+        file: Vec<u8>,
+    ) -> Result<UploadIfAbsentResponse, NFTStorageError> {
+        if file.len() > cid::MAX_RAW_BLOCK_SIZE {
+            let response = self.upload_file(file).await?;
+            return Ok(UploadIfAbsentResponse::UploadedUnchecked(response));
+        }
+        let expected = cid::compute_cid_v1_raw(&file);
+        match self.check_nft(expected).await {
+            Ok(check) => Ok(UploadIfAbsentResponse::AlreadyStored(check)),
+            Err(NFTStorageError::NotFound { .. }) => {
+                let response = self.upload_file(file).await?;
+                Ok(UploadIfAbsentResponse::Uploaded(response))
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Upload an arbitrary file on Nft storage reporting the progress
+    ///
+    /// It works like `upload_file`, when `options.on_progress` is set it is called while the body is sent so the
+    /// request isn't retried
+    /// ```no_run
+    /// use nft_storage::{NftStorage, types::*};
+    /// use anyhow::Result;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     // provide the url and as second argument the token generated from nft storage dashboard
+    ///     let nft_storage = NftStorage::new("https://api.nft.storage", "token generated from nft storage");
+    ///     let file = std::fs::read("my_video.mp4")?;
+    ///     let options = UploadOptions {
+    ///         on_progress: Some(Box::new(|progress| {
+    ///             println!("{}/{} bytes", progress.bytes_sent, progress.total_bytes)
+    ///         })),
+    ///         ..Default::default()
+    ///     };
+    ///     let upload_file: StoreNftResponse  = nft_storage.upload_file_with_options(file, options).await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// With the `gzip` feature `options.compress` compresses the text and json files before uploading them and sends
+    /// `Content-Encoding: gzip`, the other types are uploaded as they are. The stored content is the compressed one,
+    /// so the returned cid is its cid, the response is flagged as `compressed` and the downloads must be decompressed
+    /// ```
+    /// # #[cfg(all(feature = "gzip", feature = "test-util"))]
+    /// # #[tokio::main]
+    /// # async fn main() -> anyhow::Result<()> {
+    /// use nft_storage::{compress::gunzip, test_util::FakeNftStorage, types::*};
+    ///
+    /// let fake = FakeNftStorage::start().await?;
+    /// let nft_storage = fake.client();
+    /// let bundle = br#"{"provenance": "minted by the artist"}"#.repeat(1000);
+    /// let options = UploadOptions {
+    ///     compress: true,
+    ///     ..Default::default()
+    /// };
+    /// let upload = nft_storage.upload_file_with_options(bundle.clone(), options).await?;
+    /// assert!(upload.compressed);
+    /// assert!(fake.uploads()[0].body.len() < bundle.len());
+    ///
+    /// // the gateway serves the compressed bytes
+    /// let downloaded = nft_storage.download(upload.value.cid.as_str()).await?;
+    /// assert_eq!(gunzip(&downloaded)?, bundle);
+    ///
+    /// // the binary files are not compressed
+    /// let png = [0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0x00];
+    /// let options = UploadOptions {
+    ///     compress: true,
+    ///     ..Default::default()
+    /// };
+    /// let upload = nft_storage.upload_file_with_options(png.to_vec(), options).await?;
+    /// assert!(!upload.compressed);
+    /// assert_eq!(nft_storage.download(upload.value.cid.as_str()).await?, png[..]);
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(all(feature = "gzip", feature = "test-util")))]
+    /// # fn main() {}
+    /// ```
+    ///
+    /// The pin and the deals of a new upload are still empty, `options.wait_for_status` checks the upload until it is
+    /// pinned or the wait elapses and merges the last observed status in the response, `status_fresh` tells if the
+    /// pin reached a final status
+    /// ```
+    /// # #[cfg(feature = "test-util")]
+    /// # #[tokio::main]
+    /// # async fn main() -> anyhow::Result<()> {
+    /// use nft_storage::{test_util::{fixtures, MockTransport}, types::*};
+    /// use serde_json::json;
+    /// use std::time::Duration;
+    ///
+    /// let mock = MockTransport::new();
+    /// mock.push_response(200, vec![], fixtures::UPLOAD_RESPONSE);
+    /// mock.push_response(200, vec![], fixtures::CHECK_RESPONSE);
+    /// mock.push_response(200, vec![], fixtures::UPLOAD_RESPONSE);
+    /// mock.push_json(200, json!({ "ok": true, "value": { "pin": { "status": "pinning" } } }));
+    /// let nft_storage = mock.client();
+    ///
+    /// let options = UploadOptions {
+    ///     wait_for_status: Some(Duration::from_secs(1)),
+    ///     ..Default::default()
+    /// };
+    /// let upload = nft_storage.upload_file_with_options(b"image".to_vec(), options).await?;
+    /// assert!(upload.status_fresh);
+    /// assert_eq!(upload.value.pin.status, "pinned");
+    /// assert_eq!(upload.value.deals.len(), 2);
+    ///
+    /// // the wait elapses while the content is still being pinned
+    /// let options = UploadOptions {
+    ///     wait_for_status: Some(Duration::from_millis(100)),
+    ///     ..Default::default()
+    /// };
+    /// let upload = nft_storage.upload_file_with_options(b"image".to_vec(), options).await?;
+    /// assert!(!upload.status_fresh);
+    /// assert_eq!(upload.value.pin.status, "pinning");
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "test-util"))]
+    /// # fn main() {}
+    /// ```
+    #[cfg(not(target_arch = "wasm32"))]
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn upload_file_with_options(
+        &self,
+        file: Vec<u8>,
+        options: UploadOptions,
+    ) -> Result<StoreNftResponse, NFTStorageError> {
+        let wait_for_status = options.wait_for_status;
+        let mut response = self.upload_file_with_progress(file, options).await?;
+        if let Some(wait) = wait_for_status {
+            self.refresh_status(&mut response, wait).await;
+        }
+        Ok(response)
+    }
+
+    /// Upload a file following the progress and compression `options`
+    #[cfg(not(target_arch = "wasm32"))]
+    async fn upload_file_with_progress(
+        &self,
+        file: Vec<u8>,
+        options: UploadOptions,
+    ) -> Result<StoreNftResponse, NFTStorageError> {
+        #[cfg(feature = "gzip")]
+        if options.compress {
+            let content_type = detect_mime("", &file);
+            if compress::is_compressible(content_type.as_deref(), &file) {
+                return self
+                    .upload_compressed(&file, content_type, options.on_progress)
+                    .await;
+            }
+        }
+        let on_progress = match options.on_progress {
+            Some(on_progress) => on_progress,
+            None => return self.upload_file(file).await,
+        };
+        let tracker = ProgressTracker::new(file.len() as u64, on_progress);
+        let content_length = file.len() as u64;
+        let content_type = detect_mime("", &file);
+        let response = self
+            .upload_body(
+                tracker.body(file),
+                Some(content_length),
+                content_type.as_deref(),
+            )
+            .await?;
+        tracker.finish();
+        Ok(response)
+    }
+
+    /// Upload a file from disk on Nft storage
+    ///
+    /// It works like `upload_file` but the file is streamed from disk while uploading it, so the memory usage
+    /// stays flat even for very big files
+    /// ```no_run
+    /// use nft_storage::{NftStorage, types::*};
+    /// use anyhow::Result;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     // provide the url and as second argument the token generated from nft storage dashboard
+    ///     let nft_storage = NftStorage::new("https://api.nft.storage", "token generated from nft storage");
+    ///     // upload the file without loading it in memory
+    ///     let upload_file: StoreNftResponse  = nft_storage.upload_file_from_path("my_video.mp4").await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// The body is a stream of the file chunks sent with the `Content-Length` of the file
+    /// ```
+    /// # #[cfg(feature = "test-util")]
+    /// # #[tokio::main]
+    /// # async fn main() -> anyhow::Result<()> {
+    /// use nft_storage::test_util::{fixtures, FakeNftStorage, MockTransport};
+    /// use reqwest::header::CONTENT_LENGTH;
+    ///
+    /// let content = (0..100_000).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+    /// let path = std::env::temp_dir().join(format!("nft-storage-upload-from-path-{}.bin", std::process::id()));
+    /// std::fs::write(&path, &content)?;
+    ///
+    /// let mock = MockTransport::new();
+    /// mock.push_response(200, vec![], fixtures::UPLOAD_RESPONSE);
+    /// mock.client().upload_file_from_path(&path).await?;
+    /// let request = &mock.requests()[0];
+    /// // a streamed body isn't available as a single buffer
+    /// assert!(request.body.is_none());
+    /// assert_eq!(request.headers[CONTENT_LENGTH], "100000");
+    ///
+    /// // the chunks are received as the content of the file
+    /// let fake = FakeNftStorage::start().await?;
+    /// let upload = fake.client().upload_file_from_path(&path).await?;
+    /// assert_eq!(fake.uploads()[0].body, content);
+    /// assert_eq!(fake.uploads()[0].cid, upload.value.cid);
+    ///
+    /// // a missing file is an io error
+    /// std::fs::remove_file(&path)?;
+    /// let error = fake.client().upload_file_from_path(&path).await.unwrap_err();
+    /// assert!(matches!(error, nft_storage::NFTStorageError::Io(_)));
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "test-util"))]
+    /// # fn main() {}
+    /// ```
+    #[cfg(not(target_arch = "wasm32"))]
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn upload_file_from_path<P>(
+        &self,
+        path: P,
+    ) -> Result<StoreNftResponse, NFTStorageError>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let mut file = File::open(path).await?;
+        // the file size is needed so the body isn't sent with chunked transfer encoding
+        let size = file.metadata().await?.len();
+        let content_type = detect_file_mime(&mut file, path).await?;
+        let body = Body::wrap_stream(ReaderStream::new(file));
+        self.upload_body(body, Some(size), content_type.as_deref())
+            .await
+    }
+
+    /// Upload a file from an async reader on Nft storage
+    ///
+    /// The reader is streamed while uploading, see `upload_stream` for the meaning of `content_length`
+    /// ```no_run
+    /// use nft_storage::{NftStorage, types::*};
+    /// use anyhow::Result;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     // provide the url and as second argument the token generated from nft storage dashboard
+    ///     let nft_storage = NftStorage::new("https://api.nft.storage", "token generated from nft storage");
+    ///     // any type implementing AsyncRead can be uploaded
+    ///     let reader = tokio::fs::File::open("my_video.mp4").await?;
+    ///     let upload_file: StoreNftResponse  = nft_storage.upload_reader(reader, None).await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[cfg(not(target_arch = "wasm32"))]
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn upload_reader<R>(
+        &self,
+        reader: R,
+        content_length: Option<u64>,
+    ) -> Result<StoreNftResponse, NFTStorageError>
+    where
+        R: AsyncRead + Send + Sync + 'static,
+    {
+        self.upload_stream(ReaderStream::new(reader), content_length)
+            .await
+    }
+
+    /// Upload a stream of bytes on Nft storage
+    ///
+    /// It is useful to forward the content of a download or of a form-data body without buffering it.
+    ///
+    /// When `content_length` is provided it is sent as `Content-Length` header, otherwise the body is sent using
+    /// chunked transfer encoding.
+    ///
+    /// The bytes are counted while streaming them, the upload is aborted with a `PayloadTooLarge` error as soon as
+    /// they exceed the client `max_request_size`
+    /// ```no_run
+    /// use nft_storage::{NftStorage, types::*};
+    /// use anyhow::Result;
+    /// use bytes::Bytes;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     // provide the url and as second argument the token generated from nft storage dashboard
+    ///     let nft_storage = NftStorage::new("https://api.nft.storage", "token generated from nft storage");
+    ///     // create a stream of bytes
+    ///     let chunks: Vec<Result<Bytes, std::io::Error>> = vec![Ok(Bytes::from("hello ")), Ok(Bytes::from("world"))];
+    ///     let stream = futures::stream::iter(chunks);
+    ///     let upload_file: StoreNftResponse  = nft_storage.upload_stream(stream, None).await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// Without a `content_length` the request has no `Content-Length` header and the chunks are sent as they come
+    /// ```
+    /// # #[cfg(feature = "test-util")]
+    /// # #[tokio::main]
+    /// # async fn main() -> anyhow::Result<()> {
+    /// use bytes::Bytes;
+    /// use nft_storage::test_util::{fixtures, FakeNftStorage, MockTransport};
+    /// use reqwest::header::CONTENT_LENGTH;
+    ///
+    /// let chunks = || {
+    ///     let chunks = ["hello ", "stream", "ed world"].map(|chunk| Ok::<_, std::io::Error>(Bytes::from(chunk)));
+    ///     futures::stream::iter(chunks)
+    /// };
+    ///
+    /// let mock = MockTransport::new();
+    /// mock.push_response(200, vec![], fixtures::UPLOAD_RESPONSE);
+    /// mock.push_response(200, vec![], fixtures::UPLOAD_RESPONSE);
+    /// mock.client().upload_stream(chunks(), None).await?;
+    /// mock.client().upload_stream(chunks(), Some(20)).await?;
+    /// let requests = mock.requests();
+    /// assert!(requests.iter().all(|request| request.body.is_none()));
+    /// assert!(requests[0].headers.get(CONTENT_LENGTH).is_none());
+    /// assert_eq!(requests[1].headers[CONTENT_LENGTH], "20");
+    ///
+    /// // the chunked body is received whole, also from an async reader
+    /// let fake = FakeNftStorage::start().await?;
+    /// let nft_storage = fake.client();
+    /// let streamed = nft_storage.upload_stream(chunks(), None).await?;
+    /// let read = nft_storage.upload_reader(&b"hello streamed world"[..], None).await?;
+    /// assert_eq!(fake.uploads()[0].body, b"hello streamed world");
+    /// assert_eq!(streamed.value.cid, read.value.cid);
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "test-util"))]
+    /// # fn main() {}
+    /// ```
+    #[cfg(not(target_arch = "wasm32"))]
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn upload_stream<S>(
+        &self,
+        stream: S,
+        content_length: Option<u64>,
+    ) -> Result<StoreNftResponse, NFTStorageError>
+    where
+        S: TryStream + Send + Sync + 'static,
+        S::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+        Bytes: From<S::Ok>,
+    {
+        let limit = self.request_size_limit();
+        let exceeded = Arc::new(AtomicU64::new(0));
+        let body = limited_body(stream.map_ok(Bytes::from), limit, exceeded.clone());
+        let result = self.upload_body(body, content_length, None).await;
+        match exceeded.load(Ordering::SeqCst) {
+            0 => result,
+            size => Err(NFTStorageError::PayloadTooLarge { size, limit }),
+        }
+    }
+
+    /// Upload a CAR file on Nft storage
+    ///
+    /// The CAR contains an already encoded dag, so the returned cid is its root and it is known before uploading,
+    /// see `car::pack_files_to_car` with the `car` feature
+    /// ```no_run
+    /// use nft_storage::{NftStorage, types::*};
+    /// use anyhow::Result;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     // provide the url and as second argument the token generated from nft storage dashboard
+    ///     let nft_storage = NftStorage::new("https://api.nft.storage", "token generated from nft storage");
+    ///     let car = std::fs::read("my_nft.car")?;
+    ///     let upload_car: StoreNftResponse  = nft_storage.upload_car(car).await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn upload_car(&self, car: Vec<u8>) -> Result<StoreNftResponse, NFTStorageError> {
+        let content_length = car.len() as u64;
+        self.upload_body(
+            Body::from(car),
+            Some(content_length),
+            Some(CAR_CONTENT_TYPE),
+        )
+        .await
+    }
+
+    /// Upload a CAR file from disk on Nft storage
+    ///
+    /// It works like `upload_car` but the file is streamed from disk while uploading it
+    /// ```no_run
+    /// use nft_storage::{NftStorage, types::*};
+    /// use anyhow::Result;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     // provide the url and as second argument the token generated from nft storage dashboard
+    ///     let nft_storage = NftStorage::new("https://api.nft.storage", "token generated from nft storage");
+    ///     let upload_car: StoreNftResponse  = nft_storage.upload_car_from_path("my_nft.car").await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[cfg(not(target_arch = "wasm32"))]
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn upload_car_from_path<P>(
+        &self,
+        path: P,
+    ) -> Result<StoreNftResponse, NFTStorageError>
+    where
+        P: AsRef<Path>,
+    {
+        let file = File::open(path).await?;
+        let size = file.metadata().await?.len();
+        let body = Body::wrap_stream(ReaderStream::new(file));
+        self.upload_body(body, Some(size), Some(CAR_CONTENT_TYPE))
+            .await
+    }
+
+    /// Upload a big file from disk splitting it in many CAR requests
+    ///
+    /// The api rejects requests bigger than 100 MiB, so the file is encoded locally as a UnixFS dag and sent in CAR
+    /// shards of at most `chunk_size` bytes sharing the same root, cut at the block boundaries. The file is read twice,
+    /// the first time to compute the root cid and the second time to upload the shards one after the other.
+    ///
+    /// `on_progress` is called after every uploaded shard, the root cid is returned once the last one is stored
+    /// ```no_run
+    /// use nft_storage::{car::MAX_CAR_SHARD_SIZE, NftStorage};
+    /// use anyhow::Result;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     // provide the url and as second argument the token generated from nft storage dashboard
+    ///     let nft_storage = NftStorage::new("https://api.nft.storage", "token generated from nft storage");
+    ///     let cid = nft_storage
+    ///         .upload_large("my_video.mp4", MAX_CAR_SHARD_SIZE, |progress| {
+    ///             println!("{}/{} bytes", progress.bytes_sent, progress.total_bytes)
+    ///         })
+    ///         .await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[cfg(all(feature = "car", not(target_arch = "wasm32")))]
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn upload_large<P, F>(
+        &self,
+        source: P,
+        chunk_size: usize,
+        mut on_progress: F,
+    ) -> Result<Cid, NFTStorageError>
+    where
+        P: AsRef<Path>,
+        F: FnMut(CarUploadProgress),
+    {
+        use crate::car::{car_block, car_block_len, car_header, read_chunk, FileBuilder};
+
+        // a shard must contain at least the header and one full chunk
+        let min_chunk_size = cid::MAX_RAW_BLOCK_SIZE + 1024;
+        if !(min_chunk_size..=car::MAX_CAR_SHARD_SIZE).contains(&chunk_size) {
+            return Err(NFTStorageError::InvalidInput(format!(
+                "chunk_size must be between {} and {}, got {}",
+                min_chunk_size,
+                car::MAX_CAR_SHARD_SIZE,
+                chunk_size
+            )));
+        }
+        self.check_request_size(chunk_size as u64)?;
+        let path = source.as_ref();
+
+        // first read, compute the dag without keeping the chunks
+        let mut file = File::open(path).await?;
+        let total_bytes = file.metadata().await?.len();
+        // the shards are stored as a single content
+        if total_bytes > MAX_CONTENT_SIZE {
+            return Err(NFTStorageError::PayloadTooLarge {
+                size: total_bytes,
+                limit: MAX_CONTENT_SIZE,
+            });
+        }
+        let mut builder = FileBuilder::default();
+        loop {
+            let chunk = read_chunk(&mut file).await?;
+            if chunk.is_empty() {
+                break;
+            }
+            builder.push_chunk(chunk);
+        }
+        let (root, _, nodes) = builder.finish();
+        let expected = Cid(root.to_string());
+
+        // second read, upload the chunks followed by the nodes linking them
+        let mut file = File::open(path).await?;
+        let mut progress = CarUploadProgress {
+            bytes_sent: 0,
+            total_bytes,
+            shards_completed: 0,
+        };
+        let mut nodes = nodes.into_iter();
+        let mut shard = car_header(&root);
+        let mut shard_bytes = 0;
+        let mut leaves = FileBuilder::default();
+        loop {
+            let chunk = read_chunk(&mut file).await?;
+            // an empty file is made only by the empty block created by `finish`
+            let block = if chunk.is_empty() {
+                match nodes.next() {
+                    Some(node) => node,
+                    None => break,
+                }
+            } else {
+                leaves.push_chunk(chunk)
+            };
+            // send the shard before it becomes too big
+            if shard.len() + car_block_len(&block) > chunk_size {
+                self.upload_car_shard(std::mem::replace(&mut shard, car_header(&root)), &expected)
+                    .await?;
+                progress.bytes_sent += shard_bytes;
+                progress.shards_completed += 1;
+                shard_bytes = 0;
+                on_progress(progress.clone());
+            }
+            if block.cid.codec() == cid::RAW_CODEC {
+                shard_bytes += block.data.len() as u64;
+            }
+            car_block(&block, &mut shard);
+        }
+        self.upload_car_shard(shard, &expected).await?;
+        progress.bytes_sent += shard_bytes;
+        progress.shards_completed += 1;
+        on_progress(progress);
+
+        Ok(expected)
+    }
+
+    /// Upload many files concurrently on Nft storage
+    ///
+    /// Every file is uploaded like `upload_file`, with its name in the `X-Name` header and its mime as the content
+    /// type, at most `concurrency` uploads are in flight at the same time.
+    ///
+    /// When `rate_limiter` is provided every upload waits for a permit before starting, use `RateLimiter::default()`
+    /// to respect the nft storage limit of 30 requests every 10 seconds.
+    ///
+    /// A failed upload doesn't stop the others, the results are returned in the same order of `files`
+    /// ```no_run
+    /// use nft_storage::{NftStorage, RateLimiter, types::*};
+    /// use anyhow::Result;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     // provide the url and as second argument the token generated from nft storage dashboard
+    ///     let nft_storage = NftStorage::new("https://api.nft.storage", "token generated from nft storage");
+    ///     let files = vec![
+    ///         UploadFile::new("1.png", std::fs::read("1.png")?),
+    ///         UploadFile::new("2.png", std::fs::read("2.png")?),
+    ///     ];
+    ///     // upload at most 10 files at the same time respecting the api rate limit
+    ///     let rate_limiter = RateLimiter::default();
+    ///     let results = nft_storage.upload_files_concurrent(files, 10, Some(&rate_limiter)).await;
+    ///     for result in results {
+    ///         let upload_file: StoreNftResponse = result?;
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// Every file keeps its name and mime type, the results are in the order of `files` whatever upload ends first
+    /// ```
+    /// # #[cfg(feature = "test-util")]
+    /// # #[tokio::main]
+    /// # async fn main() -> anyhow::Result<()> {
+    /// use nft_storage::{test_util::FakeNftStorage, types::*};
+    /// use std::time::Duration;
+    ///
+    /// let fake = FakeNftStorage::start().await?;
+    /// fake.set_latency(Duration::from_millis(50));
+    /// let files = (0..8)
+    ///     .map(|i| UploadFile::new(format!("{}.json", i), format!("[{}]", i).into_bytes()).mime("application/json"))
+    ///     .collect::<Vec<_>>();
+    /// let results = fake.client().upload_files_concurrent(files, 3, None).await;
+    /// // the uploads overlap without exceeding the concurrency
+    /// assert!((2..=3).contains(&fake.max_in_flight()));
+    ///
+    /// let uploads = fake.uploads();
+    /// for (i, result) in results.into_iter().enumerate() {
+    ///     let cid = result?.value.cid;
+    ///     let upload = uploads.iter().find(|upload| upload.cid == cid).unwrap();
+    ///     assert_eq!(upload.name, Some(format!("{}.json", i)));
+    ///     assert_eq!(upload.content_type.as_deref(), Some("application/json"));
+    ///     assert_eq!(upload.body, format!("[{}]", i).into_bytes());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "test-util"))]
+    /// # fn main() {}
+    /// ```
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn upload_files_concurrent(
+        &self,
+        files: Vec<UploadFile>,
+        concurrency: usize,
+        rate_limiter: Option<&RateLimiter>,
+    ) -> Vec<Result<StoreNftResponse, NFTStorageError>> {
+        self.upload_files_with_limits(files, concurrency, rate_limiter, &mut None)
+            .await
+            .into_iter()
+            .flatten()
+            .collect()
+    }
+
+    /// Upload multiple files concurrently as separate uploads, they can be cancelled with `options.cancel`
+    ///
+    /// It works like `upload_files_concurrent`, after the cancellation no new upload is started and a `Cancelled`
+    /// error is returned once the running ones are completed, with the results of the uploads done so far
+    /// ```no_run
+    /// use nft_storage::{NftStorage, NFTStorageError, types::*};
+    /// use anyhow::Result;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     // provide the url and as second argument the token generated from nft storage dashboard
+    ///     let nft_storage = NftStorage::new("https://api.nft.storage", "token generated from nft storage");
+    ///     let files = vec![
+    ///         UploadFile::new("1.png", std::fs::read("1.png")?),
+    ///         UploadFile::new("2.png", std::fs::read("2.png")?),
+    ///     ];
+    ///     // stop uploading when the service shuts down
+    ///     let (shutdown, shutdown_received) = tokio::sync::oneshot::channel::<()>();
+    ///     let options = ConcurrentUploadOptions {
+    ///         concurrency: 10,
+    ///         cancel: Some(CancelSignal::new(async move {
+    ///             let _ = shutdown_received.await;
+    ///         })),
+    ///         ..Default::default()
+    ///     };
+    ///     match nft_storage.upload_files_concurrent_with_options(files, options).await {
+    ///         Ok(results) => println!("{} files uploaded", results.len()),
+    ///         Err(NFTStorageError::Cancelled(partial)) => println!("cancelled, {}", partial),
+    ///         Err(e) => return Err(e.into()),
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn upload_files_concurrent_with_options(
+        &self,
+        files: Vec<UploadFile>,
+        mut options: ConcurrentUploadOptions,
+    ) -> Result<Vec<Result<StoreNftResponse, NFTStorageError>>, NFTStorageError> {
+        let results = self
+            .upload_files_with_limits(
+                files,
+                options.concurrency,
+                options.rate_limiter.as_deref(),
+                &mut options.cancel,
+            )
+            .await;
+        if results.iter().any(Option::is_none) {
+            return Err(NFTStorageError::Cancelled(Box::new(
+                PartialResult::Uploads(results),
+            )));
+        }
+        Ok(results.into_iter().flatten().collect())
+    }
+
+    /// Upload the files as separate uploads, the results are in the order of the files and they are `None` for
+    /// the uploads not started because of the cancellation
+    async fn upload_files_with_limits(
+        &self,
+        files: Vec<UploadFile>,
+        concurrency: usize,
+        rate_limiter: Option<&RateLimiter>,
+        cancel: &mut Option<CancelSignal>,
+    ) -> Vec<Option<Result<StoreNftResponse, NFTStorageError>>> {
+        let total = files.len();
+        let mut uploads = stream::iter(files.into_iter().enumerate())
+            .take_while(|_| future::ready(!is_cancelled(cancel)))
+            .map(|(index, file)| async move {
+                if let Some(rate_limiter) = rate_limiter {
+                    rate_limiter.acquire().await;
+                }
+                (index, self.upload_named_file(file).await)
+            })
+            .buffer_unordered(concurrency.max(1));
+        // put every result back in the position of its file
+        let mut results = (0..total).map(|_| None).collect::<Vec<_>>();
+        while let Some((index, result)) = uploads.next().await {
+            results[index] = Some(result);
+        }
+        results
+    }
+
+    /// Upload a single file keeping its name and mime type
+    async fn upload_named_file(
+        &self,
+        file: UploadFile,
+    ) -> Result<StoreNftResponse, NFTStorageError> {
+        let url = self.endpoint("upload");
+        let mut request = with_name(self.client.post(url), &file.name);
+        if let Some(content_type) = file.content_type() {
+            request = request.header(CONTENT_TYPE, content_type);
+        }
+        let response = self.execute_with_retry(request.body(file.content)).await?;
+        // check if the status of the request is in range of 200-299
+        parse_response(response).await
+    }
+
+    /// Check if the provided NFT cid is stored on nft storage
+    ///
+    /// It will check the nft by `cid`, a `NotFound` error is returned if it isn't stored
+    /// ```no_run
+    /// use nft_storage::{NftStorage, types::*};
+    /// use anyhow::Result;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     // provide the url and as second argument the token generated from nft storage dashboard
+    ///     let nft_storage = NftStorage::new("https://api.nft.storage", "token generated from nft storage");
+    ///     // get nft by cid
+    ///     let is_stored_on_nft_storage: CheckCidNftResponse  = nft_storage.check_nft("bafybeihflij24dndd6qo3aacbbysuzuygis7yurvrzxp3uk7bk5kfvfsfg").await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// A 404 response is returned as `NotFound` also by `get_nft` and `delete_nft`, the other failures keep the
+    /// api error
+    /// ```
+    /// # #[cfg(feature = "test-util")]
+    /// # #[tokio::main]
+    /// # async fn main() -> anyhow::Result<()> {
+    /// use nft_storage::{test_util::{fixtures, MockTransport}, NFTStorageError};
+    ///
+    /// let cid = "bafkreidivzimqfqtoqxkrpge6bjyhlvxqs3rhe73owtmdulaxr5do5in7u";
+    /// let not_found = r#"{"ok": false, "error": {"name": "HTTPError", "message": "NFT not found"}}"#;
+    /// let mock = MockTransport::new();
+    /// mock.push_response(200, vec![], fixtures::CHECK_RESPONSE);
+    /// mock.push_response(404, vec![], not_found);
+    /// mock.push_response(500, vec![], r#"{"ok": false}"#);
+    /// mock.push_response(404, vec![], not_found);
+    /// mock.push_response(404, vec![], not_found);
+    /// let nft_storage = mock.client();
+    ///
+    /// assert!(nft_storage.check_nft(cid).await.is_ok());
+    /// let error = nft_storage.check_nft(cid).await.unwrap_err();
+    /// assert!(error.is_not_found());
+    /// assert!(matches!(error, NFTStorageError::NotFound { cid: ref missing } if missing == cid));
+    /// let error = nft_storage.check_nft(cid).await.unwrap_err();
+    /// assert!(matches!(error, NFTStorageError::ApiError { status: 500, .. }));
+    /// assert!(!error.is_not_found());
+    /// assert!(nft_storage.get_nft(cid).await.unwrap_err().is_not_found());
+    /// assert!(matches!(nft_storage.delete_nft(cid).await, Err(NFTStorageError::NotFound { .. })));
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "test-util"))]
+    /// # fn main() {}
+    /// ```
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(cid = tracing::field::Empty)))]
+    pub async fn check_nft<C>(&self, cid: C) -> Result<CheckCidNftResponse, NFTStorageError>
+    where
+        C: TryInto<Cid>,
+        NFTStorageError: From<C::Error>,
+    {
+        let cid: Cid = cid.try_into()?;
+        #[cfg(feature = "tracing")]
+        tracing::Span::current().record("cid", cid.as_str());
+        self.check_nft_cached(cid, false).await
+    }
+
+    /// Check if an nft is stored on Nft Storage choosing how the response cache is used
+    ///
+    /// With `options.bypass_cache` the nft is always checked with the api, the cache is updated with the response
+    /// ```no_run
+    /// use nft_storage::{NftStorage, types::*};
+    /// use std::time::Duration;
+    /// use anyhow::Result;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     // provide the url and as second argument the token generated from nft storage dashboard
+    ///     let nft_storage = NftStorage::builder("https://api.nft.storage", "token generated from nft storage")
+    ///         .cache_ttl(Duration::from_secs(30))
+    ///         .build()?;
+    ///     let options = ReadOptions { bypass_cache: true };
+    ///     let is_stored_on_nft_storage: CheckCidNftResponse  = nft_storage.check_nft_with_options("bafybeihflij24dndd6qo3aacbbysuzuygis7yurvrzxp3uk7bk5kfvfsfg", &options).await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[cfg(feature = "cache")]
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(cid = tracing::field::Empty)))]
+    pub async fn check_nft_with_options<C>(
+        &self,
+        cid: C,
+        options: &ReadOptions,
+    ) -> Result<CheckCidNftResponse, NFTStorageError>
+    where
+        C: TryInto<Cid>,
+        NFTStorageError: From<C::Error>,
+    {
+        let cid: Cid = cid.try_into()?;
+        #[cfg(feature = "tracing")]
+        tracing::Span::current().record("cid", cid.as_str());
+        self.check_nft_cached(cid, options.bypass_cache).await
+    }
+
+    /// Wait until the content of `cid` is pinned polling its status with `check_nft`
+    ///
+    /// The final response is returned once the status is `pinned`, a `PinFailed` error is returned when the status is
+    /// one of `options.fail_on` and a `Timeout` error when it isn't pinned within `options.timeout`.
+    /// Every poll waits for the rate limiter of the client and bypasses the cache, so polling can't starve the uploads
+    /// ```no_run
+    /// use nft_storage::{NftStorage, types::*};
+    /// use std::time::Duration;
+    /// use anyhow::Result;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     // provide the url and as second argument the token generated from nft storage dashboard
+    ///     let nft_storage = NftStorage::new("https://api.nft.storage", "token generated from nft storage");
+    ///     let upload = nft_storage.upload_file(std::fs::read("my_nft.jpg")?).await?;
+    ///     let options = WaitOptions {
+    ///         timeout: Duration::from_secs(5 * 60),
+    ///         ..Default::default()
+    ///     };
+    ///     let pinned: CheckCidNftResponse = nft_storage.wait_for_pin(&upload.value.cid, options).await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(cid = tracing::field::Empty)))]
+    pub async fn wait_for_pin<C>(
+        &self,
+        cid: C,
+        options: WaitOptions,
+    ) -> Result<CheckCidNftResponse, NFTStorageError>
+    where
+        C: TryInto<Cid>,
+        NFTStorageError: From<C::Error>,
+    {
+        let cid: Cid = cid.try_into()?;
+        #[cfg(feature = "tracing")]
+        tracing::Span::current().record("cid", cid.as_str());
+        let start = Instant::now();
+        // the last status is kept to report it when the time is elapsed
+        let mut status = String::new();
+        let poll = async {
+            let mut attempt = 0;
+            loop {
+                let check = self.check_nft_cached(cid.clone(), true).await?;
+                status.clone_from(&check.value.pin.status);
+                match check.value.pin.pinning_status() {
+                    Some(PinningStatus::Pinned) => return Ok(check),
+                    Some(pinning_status) if options.fail_on.contains(&pinning_status) => {
+                        return Err(NFTStorageError::PinFailed {
+                            cid: cid.to_string(),
+                            status: pinning_status,
+                        });
+                    }
+                    _ => {}
+                }
+                #[cfg(feature = "tracing")]
+                tracing::debug!(status = %check.value.pin.status, attempt, "waiting for the pin");
+                sleep(options.delay(attempt)).await;
+                attempt += 1;
+            }
+        };
+        match timeout(options.timeout, poll).await {
+            Ok(result) => result,
+            Err(_) => Err(NFTStorageError::Timeout {
+                cid: cid.to_string(),
+                status,
+                elapsed: start.elapsed(),
+            }),
+        }
+    }
+
+    /// Check if the content of `cid` has at least `min_active_deals` active filecoin deals
+    ///
+    /// The status is checked once with `check_nft` bypassing the cache, use it to check the deals from a periodic job
+    /// instead of waiting with `wait_for_deal`
+    /// ```no_run
+    /// use nft_storage::{NftStorage, types::*};
+    /// use anyhow::Result;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     // provide the url and as second argument the token generated from nft storage dashboard
+    ///     let nft_storage = NftStorage::new("https://api.nft.storage", "token generated from nft storage");
+    ///     let deals: DealStatus = nft_storage.check_deals("bafybeihflij24dndd6qo3aacbbysuzuygis7yurvrzxp3uk7bk5kfvfsfg", 2).await?;
+    ///     for deal in &deals.active_deals {
+    ///         println!("{} {} {:?}", deal.miner, deal.chain_deal_id, deal.deal_expiration);
+    ///     }
+    ///     println!("ready: {}", deals.is_ready());
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(cid = tracing::field::Empty)))]
+    pub async fn check_deals<C>(
+        &self,
+        cid: C,
+        min_active_deals: usize,
+    ) -> Result<DealStatus, NFTStorageError>
+    where
+        C: TryInto<Cid>,
+        NFTStorageError: From<C::Error>,
+    {
+        let cid: Cid = cid.try_into()?;
+        #[cfg(feature = "tracing")]
+        tracing::Span::current().record("cid", cid.as_str());
+        self.deal_status(cid, min_active_deals).await
+    }
+
+    /// Wait until the content of `cid` has at least `min_active_deals` active filecoin deals
+    ///
+    /// The deals are checked every `DEAL_POLL_INTERVAL` like `check_deals`, a `DealTimeout` error is returned if they
+    /// aren't active within `timeout`
+    /// ```no_run
+    /// use nft_storage::{NftStorage, types::*};
+    /// use std::time::Duration;
+    /// use anyhow::Result;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     // provide the url and as second argument the token generated from nft storage dashboard
+    ///     let nft_storage = NftStorage::new("https://api.nft.storage", "token generated from nft storage");
+    ///     let timeout = Duration::from_secs(3 * 24 * 60 * 60);
+    ///     let deals: DealStatus = nft_storage.wait_for_deal("bafybeihflij24dndd6qo3aacbbysuzuygis7yurvrzxp3uk7bk5kfvfsfg", 1, timeout).await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(cid = tracing::field::Empty)))]
+    pub async fn wait_for_deal<C>(
+        &self,
+        cid: C,
+        min_active_deals: usize,
+        timeout_after: Duration,
+    ) -> Result<DealStatus, NFTStorageError>
+    where
+        C: TryInto<Cid>,
+        NFTStorageError: From<C::Error>,
+    {
+        let cid: Cid = cid.try_into()?;
+        #[cfg(feature = "tracing")]
+        tracing::Span::current().record("cid", cid.as_str());
+        let start = Instant::now();
+        // the last count is kept to report it when the time is elapsed
+        let mut active_deals = 0;
+        let poll = async {
+            loop {
+                let deals = self.deal_status(cid.clone(), min_active_deals).await?;
+                if deals.is_ready() {
+                    return Ok(deals);
+                }
+                active_deals = deals.active_deals.len();
+                #[cfg(feature = "tracing")]
+                tracing::debug!(active_deals, min_active_deals, "waiting for the deals");
+                sleep(DEAL_POLL_INTERVAL).await;
+            }
+        };
+        match timeout(timeout_after, poll).await {
+            Ok(result) => result,
+            Err(_) => Err(NFTStorageError::DealTimeout {
+                cid: cid.to_string(),
+                active_deals,
+                min_active_deals,
+                elapsed: start.elapsed(),
+            }),
+        }
+    }
+
+    /// Check the active deals of `cid` bypassing the cache
+    async fn deal_status(
+        &self,
+        cid: Cid,
+        min_active_deals: usize,
+    ) -> Result<DealStatus, NFTStorageError> {
+        let check = self.check_nft_cached(cid.clone(), true).await?;
+        Ok(DealStatus {
+            cid,
+            active_deals: check
+                .value
+                .deals
+                .into_iter()
+                .filter(|deal| deal.is_active())
+                .collect(),
+            min_active_deals,
+        })
+    }
+
+    /// Check the nft with the cache or with the api caching the response
+    #[cfg_attr(not(feature = "cache"), allow(unused_variables))]
+    async fn check_nft_cached(
+        &self,
+        cid: Cid,
+        bypass_cache: bool,
+    ) -> Result<CheckCidNftResponse, NFTStorageError> {
+        #[cfg(feature = "cache")]
+        if let Some(check) = self.cache_lookup(bypass_cache, |cache| cache.check(&cid)) {
+            return Ok(check);
+        }
+        let url = self.endpoint(&format!("check/{}", cid));
+        let check: CheckCidNftResponse = self.request_nft(self.client.get(url), &cid).await?;
+        #[cfg(feature = "cache")]
+        if let Some(cache) = &self.cache {
+            cache.insert_check(&cid, check.clone());
+        }
+        Ok(check)
+    }
+
+    /// Upload multiple files to Nft Storage
+    ///
+    /// It will upload multiple files using multipart form data and files will be stored in IPFS Directory
+    ///
+    /// The max sise is around 30GB per file
+    ///
+    /// The main difference between `upload_file` method is that it upload a file NOT in a direcotry so its url is unique, if using
+    /// `upload_directory` it will store one or multiple files in an ipfs direcotry preserving the original filenames and all file will be
+    /// fetched by the direcotry cid  for example `bafybeihflij24dndd6qo3aacbbysuzuygis7yurvrzxp3uk7bk5kfvfsfg/my_file.txt`
+    ///
+    /// When the file `mime` is set it is used as the content type of the file
+    ///
+    /// The names are trimmed, a `DuplicateFileName` error is returned if two files have the same name and an
+    /// `InvalidInput` error if a name contains `/` or `\`, use `upload_directory_with_options` to change it
+    ///
+    /// Every time using this method it will create a new directory
+    /// ```no_run
+    /// use anyhow::Result;
+    /// use nft_storage::{NftStorage, types::*};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     // provide the url and as second argument the token generated from nft storage dashboard
+    ///     let nft_storage = NftStorage::new("https://api.nft.storage", "token generated from nft storage");
+    ///     // read the files in order to have a Vec<u8> the same from a form-data
+    ///     let files = vec![
+    ///         UploadFile::new("my_nft.jpg", std::fs::read("my_nft.jpg")?).mime("image/jpeg"),
+    ///         UploadFile::new("my_nft2.jpg", std::fs::read("my_nft2.jpg")?),
+    ///     ];
+    ///     // upload the files in a directory
+    ///     let upload_file: StoreNftResponse  = nft_storage.upload_directory(files).await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn upload_directory(
+        &self,
+        files: Vec<UploadFile>,
+    ) -> Result<StoreNftResponse, NFTStorageError> {
+        let files = check_file_names(files, DedupeStrategy::Error, false)?;
+        self.upload_files(files).await
+    }
+
+    /// Upload multiple files to Nft Storage returning the details of every stored file
+    ///
+    /// It works like `upload_directory`, then the nft is requested to get the files stored in the directory with
+    /// their ipfs and gateway urls. The size of a file is `None` when the api doesn't report it
+    ///
+    /// ```
+    /// # #[cfg(feature = "test-util")]
+    /// # #[tokio::main]
+    /// # async fn main() -> anyhow::Result<()> {
+    /// use nft_storage::{test_util::FakeNftStorage, types::*};
+    ///
+    /// let fake = FakeNftStorage::start().await?;
+    /// let nft_storage = fake.client();
+    /// let files = vec![
+    ///     UploadFile::new("image.png", b"image".to_vec()).mime("image/png"),
+    ///     UploadFile::new("metadata.json", b"{}".to_vec()),
+    /// ];
+    /// let result = nft_storage.upload_directory_detailed(files).await?;
+    ///
+    /// assert_eq!(result.files.len(), 2);
+    /// assert_eq!(result.files[0].mime, "image/png");
+    /// assert_eq!(result.files[0].ipfs_uri, format!("ipfs://{}/image.png", result.dir_cid));
+    /// // the api doesn't report the size of the files
+    /// assert_eq!(result.files[0].size, None);
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "test-util"))]
+    /// # fn main() {}
+    /// ```
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn upload_directory_detailed(
+        &self,
+        files: Vec<UploadFile>,
+    ) -> Result<DirectoryUploadResult, NFTStorageError> {
+        let uploaded = self.upload_directory(files).await?;
+        let nft = self.get_nft_cached(uploaded.value.cid, true).await?.value;
+        let files = nft
+            .files
+            .into_iter()
+            .map(|file| StoredFile {
+                ipfs_uri: format!("ipfs://{}/{}", nft.cid, file.name),
+                gateway_url: self.gateway_url(nft.cid.as_str(), &file.name),
+                name: file.name,
+                mime: file.file_type,
+                size: file.size,
+            })
+            .collect();
+        Ok(DirectoryUploadResult {
+            dir_cid: nft.cid,
+            files,
+        })
+    }
+
+    /// Upload multiple files to Nft Storage reporting the progress
+    ///
+    /// It works like `upload_directory`, when `options.on_progress` is set it is called while the files are sent and
+    /// `total_bytes` is the size of all the files.
+    /// The duplicated names are handled following `options.dedupe_strategy`, `options.wait_for_status` works like in
+    /// `upload_file_with_options`
+    /// ```no_run
+    /// use nft_storage::{NftStorage, types::*};
+    /// use anyhow::Result;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     // provide the url and as second argument the token generated from nft storage dashboard
+    ///     let nft_storage = NftStorage::new("https://api.nft.storage", "token generated from nft storage");
+    ///     let files = vec![
+    ///         UploadFile::new("image.png", std::fs::read("image.png")?),
+    ///         UploadFile::new("video.mp4", std::fs::read("video.mp4")?),
+    ///     ];
+    ///     let options = UploadOptions {
+    ///         on_progress: Some(Box::new(|progress| {
+    ///             println!("{}/{} bytes", progress.bytes_sent, progress.total_bytes)
+    ///         })),
+    ///         ..Default::default()
+    ///     };
+    ///     let upload_dir: StoreNftResponse  = nft_storage.upload_directory_with_options(files, options).await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[cfg(not(target_arch = "wasm32"))]
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn upload_directory_with_options(
+        &self,
+        files: Vec<UploadFile>,
+        options: UploadOptions,
+    ) -> Result<StoreNftResponse, NFTStorageError> {
+        // a multipart part can't declare its own content encoding
+        #[cfg(feature = "gzip")]
+        if options.compress {
+            return Err(NFTStorageError::InvalidInput(
+                "compress is supported only by upload_file_with_options".to_string(),
+            ));
+        }
+        let files = check_file_names(files, options.dedupe_strategy, options.allow_nested_paths)?;
+        let mut response = match options.on_progress {
+            Some(on_progress) => self.upload_files_with_progress(files, on_progress).await?,
+            None => self.upload_files(files).await?,
+        };
+        if let Some(wait) = options.wait_for_status {
+            self.refresh_status(&mut response, wait).await;
+        }
+        Ok(response)
+    }
+
+    /// Upload the files in a directory calling `on_progress` while they are sent, their names are already checked
+    #[cfg(not(target_arch = "wasm32"))]
+    async fn upload_files_with_progress(
+        &self,
+        files: Vec<UploadFile>,
+        on_progress: UploadProgressCallback,
+    ) -> Result<StoreNftResponse, NFTStorageError> {
+        let total_bytes = files.iter().map(|f| f.content.len() as u64).sum();
+        self.check_request_size(total_bytes)?;
+        let tracker = ProgressTracker::new(total_bytes, on_progress);
+        let mut form = Form::new();
+        for file in files {
+            let part = file.into_part_with(|content| {
+                let length = content.len() as u64;
+                Part::stream_with_length(tracker.body(content), length)
+            })?;
+            form = form.part("file", part);
+        }
+        let response = self.upload_form(form).await?;
+        tracker.finish();
+        Ok(response)
+    }
+
+    /// Poll the status of the `upload` for at most `wait` merging the last observed `pin` and `deals` in it
+    ///
+    /// `status_fresh` is set when the pin reaches a final status before the wait elapses. The upload is already
+    /// stored, so a failed check only leaves the previous status
+    #[cfg(not(target_arch = "wasm32"))]
+    async fn refresh_status(&self, upload: &mut StoreNftResponse, wait: Duration) {
+        let options = WaitOptions::default();
+        let cid = upload.value.cid.clone();
+        let value = &mut upload.value;
+        let poll = async {
+            let mut attempt = 0;
+            loop {
+                // the nft can be missing from the check endpoint right after the upload
+                if let Ok(check) = self.check_nft_cached(cid.clone(), true).await {
+                    value.pin = check.value.pin;
+                    value.deals = check.value.deals;
+                    if matches!(
+                        value.pin.pinning_status(),
+                        Some(PinningStatus::Pinned | PinningStatus::Failed)
+                    ) {
+                        return;
+                    }
+                }
+                sleep(options.delay(attempt)).await;
+                attempt += 1;
+            }
+        };
+        upload.status_fresh = timeout(wait, poll).await.is_ok();
+    }
+
+    /// Upload multiple files to Nft Storage sending every distinct content only once
+    ///
+    /// The files are hashed locally and packed in a CAR as a UnixFS directory, the files with the same content are
+    /// separate entries of the directory linking the same blocks, so the directory contains every requested name
+    /// while the duplicated bytes aren't sent. The returned report lists the files sharing the same content and
+    /// `paths` maps every requested name to its path in the directory.
+    ///
+    /// The names are checked like in `upload_directory`, a `CidMismatch` error is returned if the api stores the
+    /// CAR with another root
+    /// ```
+    /// # #[cfg(feature = "test-util")]
+    /// # #[tokio::main]
+    /// # async fn main() -> anyhow::Result<()> {
+    /// use nft_storage::{car::pack_files_to_car, test_util::MockTransport, types::*};
+    /// use serde_json::json;
+    ///
+    /// let layer = vec![7; 1000];
+    /// let files = vec![
+    ///     UploadFile::new("0.png", layer.clone()),
+    ///     UploadFile::new("1.png", b"other layer".to_vec()),
+    ///     UploadFile::new("2.png", layer.clone()),
+    /// ];
+    /// let (_, root) = pack_files_to_car(files.clone())?;
+    /// let mock = MockTransport::new();
+    /// mock.push_json(200, json!({ "ok": true, "value": { "cid": root } }));
+    /// let nft_storage = mock.client();
+    ///
+    /// let result = nft_storage.upload_directory_deduplicated(files).await?;
+    /// assert_eq!(result.upload.value.cid, root);
+    /// assert_eq!(result.report.unique_files, 2);
+    /// assert_eq!(result.report.duplicate_groups, [["0.png", "2.png"]]);
+    /// assert_eq!(result.report.bytes_saved, 1000);
+    /// assert_eq!(result.paths["2.png"], format!("{}/2.png", root));
+    /// // the layer is sent once
+    /// assert!(mock.requests()[0].body.as_ref().unwrap().len() < 2000);
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "test-util"))]
+    /// # fn main() {}
+    /// ```
+    #[cfg(feature = "car")]
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn upload_directory_deduplicated(
+        &self,
+        files: Vec<UploadFile>,
+    ) -> Result<DeduplicatedUpload, NFTStorageError> {
+        let requested = files.iter().map(|f| f.name.clone()).collect::<Vec<_>>();
+        let files = check_file_names(files, DedupeStrategy::Error, false)?;
+        let report = dedup_report(&files);
+        // the duplicated names are rejected, so the checked files are in the same order as the requested ones
+        let names = files.iter().map(|f| f.name.clone()).collect::<Vec<_>>();
+        let (car, root) = car::pack_files_to_car(files)?;
+        let upload = self.upload_car(car).await?;
+        ensure_cid_matches(&root, &upload.value.cid)?;
+        let paths = requested
+            .into_iter()
+            .zip(names)
+            .map(|(requested, name)| (requested, format!("{}/{}", upload.value.cid, name)))
+            .collect();
+        Ok(DeduplicatedUpload {
+            upload,
+            report,
+            paths,
+        })
+    }
+
+    /// Upload a local directory to Nft Storage
+    ///
+    /// Every file of `dir` is streamed from disk while uploading, the paths relative to `dir` are used as file names
+    /// so when `recursive` is true the nested directories are preserved in the IPFS Directory.
+    ///
+    /// Hidden files and directories (starting with `.`) are skipped, use `upload_directory_from_path_with_options`
+    /// to include them.
+    ///
+    /// It will return an error if the directory contains no files or more than [`MAX_DIRECTORY_FILES`]
+    /// ```no_run
+    /// use anyhow::Result;
+    /// use nft_storage::{NftStorage, types::*};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     // provide the url and as second argument the token generated from nft storage dashboard
+    ///     let nft_storage = NftStorage::new("https://api.nft.storage", "token generated from nft storage");
+    ///     // upload the directory and all its sub directories
+    ///     let upload_dir: StoreNftResponse  = nft_storage.upload_directory_from_path("./collection", true).await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[cfg(not(target_arch = "wasm32"))]
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn upload_directory_from_path<P>(
+        &self,
+        dir: P,
+        recursive: bool,
+    ) -> Result<StoreNftResponse, NFTStorageError>
+    where
+        P: AsRef<Path>,
+    {
+        let options = DirectoryOptions {
+            recursive,
+            ..Default::default()
+        };
+        self.upload_directory_from_path_with_options(dir, options)
+            .await
+    }
+
+    /// Upload a local directory to Nft Storage using the provided options
+    ///
+    /// See `upload_directory_from_path` and [`DirectoryOptions`]
+    #[cfg(not(target_arch = "wasm32"))]
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn upload_directory_from_path_with_options<P>(
+        &self,
+        dir: P,
+        options: DirectoryOptions,
+    ) -> Result<StoreNftResponse, NFTStorageError>
+    where
+        P: AsRef<Path>,
+    {
+        let dir = dir.as_ref();
+        let paths = read_directory_files(dir, &options).await?;
+        if paths.is_empty() {
+            return Err(NFTStorageError::InvalidInput(format!(
+                "directory {} contains no files",
+                dir.display()
+            )));
+        }
+        if paths.len() > MAX_DIRECTORY_FILES {
+            return Err(NFTStorageError::InvalidInput(format!(
+                "directory {} contains {} files, max allowed is {}",
+                dir.display(),
+                paths.len(),
+                MAX_DIRECTORY_FILES
+            )));
+        }
+        let mut form = Form::new();
+        let mut total_bytes = 0;
+        for (path, file_name) in paths {
+            let size = tokio::fs::metadata(&path).await?.len();
+            total_bytes += size;
+            self.check_request_size(total_bytes)?;
+            // the file is closed after reading its first bytes and opened again only when its part is sent, so a
+            // single file descriptor is open at a time
+            let content_type = detect_file_mime(&mut File::open(&path).await?, &path).await?;
+            // stream the file so it is never fully loaded in memory
+            let file = stream::once(File::open(path)).map_ok(ReaderStream::new);
+            let body = Body::wrap_stream(file.try_flatten());
+            let mut part = Part::stream_with_length(body, size).file_name(file_name);
+            if let Some(content_type) = content_type {
+                part = part.mime_str(&content_type)?;
+            }
+            form = form.part("file", part);
+        }
+        self.upload_form(form).await
+    }
+
+    /// Send an upload request, the cached responses of the uploaded cid are removed
+    async fn upload_request(
+        &self,
+        request: RequestBuilder,
+    ) -> Result<StoreNftResponse, NFTStorageError> {
+        let (upload, _) = self.upload_request_with_meta(request).await?;
+        Ok(upload)
+    }
+
+    /// Send an upload request like `upload_request` returning also the metadata of the last attempt
+    async fn upload_request_with_meta(
+        &self,
+        request: RequestBuilder,
+    ) -> Result<(StoreNftResponse, ResponseMeta), NFTStorageError> {
+        let (upload, meta): (StoreNftResponse, _) = self.request_with_meta(request).await?;
+        #[cfg(feature = "cache")]
+        self.invalidate_cache(&upload.value.cid);
+        Ok((upload, meta))
+    }
+
+    /// Get a response from the cache, `None` if the cache is disabled or bypassed
+    #[cfg(feature = "cache")]
+    fn cache_lookup<T, F>(&self, bypass_cache: bool, lookup: F) -> Option<T>
+    where
+        F: FnOnce(&ResponseCache) -> Option<T>,
+    {
+        self.cache
+            .as_deref()
+            .filter(|_| !bypass_cache)
+            .and_then(lookup)
+    }
+
+    /// Remove the cached responses of `cid`
+    #[cfg(feature = "cache")]
+    fn invalidate_cache(&self, cid: &Cid) {
+        if let Some(cache) = &self.cache {
+            cache.invalidate(cid);
+        }
+    }
+
+    /// Send the request and deserialize the successful response, an `ApiError` is returned otherwise
+    pub(crate) async fn request<T>(&self, request: RequestBuilder) -> Result<T, NFTStorageError>
+    where
+        T: DeserializeOwned,
+    {
+        let response = self.execute_with_retry(request).await?;
+        parse_response(response).await
+    }
+
+    /// Send the request and deserialize the successful response returning also the metadata of the last attempt
+    pub(crate) async fn request_with_meta<T>(
+        &self,
+        request: RequestBuilder,
+    ) -> Result<(T, ResponseMeta), NFTStorageError>
+    where
+        T: DeserializeOwned,
+    {
+        let (response, meta) = self.execute_with_meta(request).await?;
+        Ok((parse_response(response).await?, meta))
+    }
+
+    /// Send a request about the nft `cid`, a 404 response returns a `NotFound` error
+    async fn request_nft<T>(&self, request: RequestBuilder, cid: &Cid) -> Result<T, NFTStorageError>
+    where
+        T: DeserializeOwned,
+    {
+        let response = self.execute_with_retry(request).await?;
+        // a missing nft isn't an api failure
+        if response.status() == StatusCode::NOT_FOUND {
+            return Err(NFTStorageError::NotFound {
+                cid: cid.to_string(),
+            });
+        }
+        parse_response(response).await
+    }
+
+    /// Send the request retrying it following the retry policy
+    ///
+    /// When a rate limiter is configured every attempt waits for a permit
+    ///
+    /// Requests with a body that can't be cloned (streams and multipart forms) are sent only once
+    async fn execute_with_retry(
+        &self,
+        request: RequestBuilder,
+    ) -> Result<Response, NFTStorageError> {
+        let (response, _) = self.execute_with_meta(request).await?;
+        Ok(response)
+    }
+
+    /// Send the request like `execute_with_retry` returning also the metadata of the last attempt
+    async fn execute_with_meta(
+        &self,
+        request: RequestBuilder,
+    ) -> Result<(Response, ResponseMeta), NFTStorageError> {
+        let mut request = self.prepare(request, true)?;
+        let mut attempt = 0;
+        loop {
+            // wait for the rate limiter before every attempt
+            if let Some(rate_limiter) = &self.rate_limiter {
+                rate_limiter.acquire().await;
+            }
+            if let Some(wait) = self.rate_limit_wait() {
+                #[cfg(feature = "tracing")]
+                tracing::warn!(
+                    wait_ms = wait.as_millis() as u64,
+                    "rate limit exhausted, waiting for the reset"
+                );
+                sleep(wait).await;
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            let attempt_timeout = self.attempt_timeout()?;
+            #[cfg(not(target_arch = "wasm32"))]
+            if attempt_timeout.is_some() {
+                // the reqwest timeout covers also reading the response body
+                *request.timeout_mut() = attempt_timeout;
+            }
+            let next_request = request.try_clone();
+            #[cfg(not(target_arch = "wasm32"))]
+            let (response, meta) = self.send_within(request, attempt_timeout).await?;
+            #[cfg(target_arch = "wasm32")]
+            let (response, meta) = self.send(request).await?;
+            if let Some(rate_limit) = &meta.rate_limit {
+                *sync::lock(&self.rate_limit_status) = Some(rate_limit.clone());
+            }
+            match next_request {
+                Some(next_request)
+                    if attempt < self.retry_policy.max_retries
+                        && self.retry_policy.should_retry(response.status()) =>
+                {
+                    // wait the time requested by the server or use the exponential backoff
+                    let delay = self
+                        .retry_policy
+                        .retry_delay(attempt, retry_after(&response));
+                    sleep(delay).await;
+                    attempt += 1;
+                    request = next_request;
+                }
+                _ => return Ok((response, meta)),
+            }
+        }
+    }
+
+    /// Timeout of the next attempt of a request, the shortest between the request timeout and the time left before
+    /// the deadline, a `DeadlineExceeded` error is returned when the deadline is elapsed
+    #[cfg(not(target_arch = "wasm32"))]
+    fn attempt_timeout(&self) -> Result<Option<Duration>, NFTStorageError> {
+        let left = match self.request_options.deadline {
+            Some(deadline) => {
+                let left = deadline.saturating_duration_since(std::time::Instant::now());
+                if left.is_zero() {
+                    return Err(NFTStorageError::DeadlineExceeded(Box::new(
+                        PartialResult::Empty,
+                    )));
+                }
+                Some(left)
+            }
+            None => None,
+        };
+        Ok(match (self.request_options.timeout, left) {
+            (Some(request_timeout), Some(left)) => Some(request_timeout.min(left)),
+            (request_timeout, left) => request_timeout.or(left),
+        })
+    }
+
+    /// Whether the deadline of the request options is elapsed
+    #[cfg(not(target_arch = "wasm32"))]
+    fn deadline_elapsed(&self) -> bool {
+        self.request_options
+            .deadline
+            .is_some_and(|deadline| std::time::Instant::now() >= deadline)
+    }
+
+    /// Send the request like `send` failing when it takes more than `attempt_timeout`
+    ///
+    /// The custom transports don't apply the reqwest timeout, so the request is also stopped here
+    #[cfg(not(target_arch = "wasm32"))]
+    async fn send_within(
+        &self,
+        request: Request,
+        attempt_timeout: Option<Duration>,
+    ) -> Result<(Response, ResponseMeta), NFTStorageError> {
+        let attempt_timeout = match attempt_timeout {
+            Some(attempt_timeout) => attempt_timeout,
+            None => return self.send(request).await,
+        };
+        match timeout(attempt_timeout, self.send(request)).await {
+            Ok(Err(NFTStorageError::InvalidRequest(e))) if e.is_timeout() => {
+                Err(self.timeout_error(attempt_timeout))
+            }
+            Ok(result) => result,
+            Err(_) => Err(self.timeout_error(attempt_timeout)),
+        }
+    }
+
+    /// Error of a request stopped after `elapsed`, `DeadlineExceeded` if it was stopped by the deadline
+    #[cfg(not(target_arch = "wasm32"))]
+    fn timeout_error(&self, elapsed: Duration) -> NFTStorageError {
+        if self.deadline_elapsed() {
+            NFTStorageError::DeadlineExceeded(Box::new(PartialResult::Empty))
+        } else {
+            NFTStorageError::RequestTimeout(elapsed)
+        }
+    }
+
+    /// Time to wait before sending a request when `wait_on_rate_limit` is enabled and no requests are left
+    fn rate_limit_wait(&self) -> Option<Duration> {
+        if !self.wait_on_rate_limit {
+            return None;
+        }
+        let status = sync::lock(&self.rate_limit_status);
+        let status = status.as_ref().filter(|status| status.is_exhausted())?;
+        status.reset_in().filter(|wait| !wait.is_zero())
+    }
+
+    /// Build the request adding the default headers and running the request hooks, the api token is added after them
+    /// so they can't read it
+    fn prepare(
+        &self,
+        request: RequestBuilder,
+        authenticated: bool,
+    ) -> Result<Request, NFTStorageError> {
+        let mut request = request.build()?;
+        // the default headers are sent only to the api, the headers of the request take precedence
+        if authenticated {
+            for (name, value) in &self.default_headers {
+                request
+                    .headers_mut()
+                    .entry(name)
+                    .or_insert_with(|| value.clone());
+            }
+        }
+        for hook in &self.request_hooks {
+            hook(&mut request);
+        }
+        if authenticated {
+            self.auth.apply(request.headers_mut())?;
+        }
+        Ok(request)
+    }
+
+    /// Send a single request through the transport and call the response hooks, the response is returned with the
+    /// metadata passed to the hooks
+    ///
+    /// With the `tracing` feature every request is recorded in a span with its method, path, payload size, status and
+    /// elapsed time, the headers and so the api token are never recorded
+    async fn send(&self, request: Request) -> Result<(Response, ResponseMeta), NFTStorageError> {
+        let method = request.method().clone();
+        let url = request.url().clone();
+        #[cfg(feature = "tracing")]
+        let span = {
+            let payload_size = request
+                .headers()
+                .get(CONTENT_LENGTH)
+                .and_then(|value| value.to_str().ok()?.parse::<u64>().ok())
+                .or_else(|| Some(request.body()?.as_bytes()?.len() as u64));
+            tracing::info_span!(
+                "http_request",
+                method = %method,
+                path = %url.path(),
+                payload_size,
+                status = tracing::field::Empty,
+                elapsed_ms = tracing::field::Empty,
+                rate_limit_remaining = tracing::field::Empty,
+            )
+        };
+
+        let start = Instant::now();
+        let execute = self.transport.execute(request);
+        #[cfg(feature = "tracing")]
+        let execute = tracing::Instrument::instrument(execute, span.clone());
+        let result = execute.await;
+        let elapsed = start.elapsed();
+
+        #[cfg(feature = "tracing")]
+        {
+            span.record("elapsed_ms", elapsed.as_millis() as u64);
+            match &result {
+                Ok(response) => {
+                    span.record("status", response.status().as_u16());
+                }
+                Err(e) => span.in_scope(|| tracing::error!(error = %e, "request failed")),
+            }
+        }
+        let response = result.as_ref().ok();
+        let meta = ResponseMeta {
+            method,
+            url,
+            status: response.map(|response| response.status().as_u16()),
+            elapsed,
+            request_id: response.and_then(hooks::request_id),
+            rate_limit: response.and_then(RateLimitInfo::from_response),
+        };
+        #[cfg(feature = "tracing")]
+        if let Some(remaining) = meta.rate_limit.as_ref().and_then(|info| info.remaining) {
+            span.record("rate_limit_remaining", remaining);
+        }
+        for hook in &self.response_hooks {
+            hook(&meta);
+        }
+        Ok((result?, meta))
+    }
+
+    /// Request `cid` from the ipfs `gateway`, the response is returned only if it is successful
+    async fn gateway_get(
+        &self,
+        gateway: &str,
+        cid: &str,
+        path_in_dir: Option<&str>,
+    ) -> Result<Response, NFTStorageError> {
+        validate_cid(cid)?;
+        let mut url = format!("{}/{}", gateway.trim_end_matches('/'), cid);
+        if let Some(path) = path_in_dir {
+            url = format!("{}/{}", url, path.trim_start_matches('/'));
+        }
+        // the gateway is public so the api token and the rate limiter aren't used
+        let request = self.prepare(self.client.get(&url), false)?;
+        let (response, _) = self.send(request).await.map_err(|e| match e {
+            NFTStorageError::InvalidRequest(e) => gateway_error(e, &url),
+            e => e,
+        })?;
+        match response.status() {
+            status if status.is_success() => Ok(response),
+            StatusCode::NOT_FOUND => Err(NFTStorageError::GatewayNotFound(url)),
+            StatusCode::GATEWAY_TIMEOUT | StatusCode::REQUEST_TIMEOUT => {
+                Err(NFTStorageError::GatewayTimeout(url))
+            }
+            status => Err(NFTStorageError::GatewayError {
+                status: status.as_u16(),
+                url,
+            }),
+        }
+    }
+
+    /// Upload a shard of a CAR checking that the api stored it with the `expected` root
+    #[cfg(all(feature = "car", not(target_arch = "wasm32")))]
+    async fn upload_car_shard(
+        &self,
+        shard: Vec<u8>,
+        expected: &Cid,
+    ) -> Result<(), NFTStorageError> {
+        let response = self.upload_car(shard).await?;
+        ensure_cid_matches(expected, &response.value.cid)
+    }
+
+    /// Upload `file` compressed with gzip, the response is flagged as `compressed`
+    #[cfg(all(feature = "gzip", not(target_arch = "wasm32")))]
+    async fn upload_compressed(
+        &self,
+        file: &[u8],
+        content_type: Option<String>,
+        on_progress: Option<UploadProgressCallback>,
+    ) -> Result<StoreNftResponse, NFTStorageError> {
+        let compressed = compress::gzip(file)?;
+        let content_length = compressed.len() as u64;
+        self.check_request_size(content_length)?;
+        let tracker =
+            on_progress.map(|on_progress| ProgressTracker::new(content_length, on_progress));
+        let body = match &tracker {
+            Some(tracker) => tracker.body(compressed),
+            None => Body::from(compressed),
+        };
+        let mut request = self
+            .client
+            .post(self.endpoint("upload"))
+            .header(reqwest::header::CONTENT_ENCODING, compress::GZIP_ENCODING)
+            .header(CONTENT_LENGTH, content_length)
+            .body(body);
+        if let Some(content_type) = content_type {
+            request = request.header(CONTENT_TYPE, content_type);
+        }
+        let mut upload = self.upload_request(request).await?;
+        if let Some(tracker) = tracker {
+            tracker.finish();
+        }
+        upload.compressed = true;
+        Ok(upload)
+    }
+
+    /// Upload a raw body on the upload endpoint
+    async fn upload_body(
+        &self,
+        body: Body,
+        content_length: Option<u64>,
+        content_type: Option<&str>,
+    ) -> Result<StoreNftResponse, NFTStorageError> {
+        let url = self.endpoint("upload");
+        let mut request = self.client.post(url).body(body);
+        if let Some(content_length) = content_length {
+            self.check_request_size(content_length)?;
+            request = request.header(CONTENT_LENGTH, content_length);
+        }
+        if let Some(content_type) = content_type {
+            request = request.header(CONTENT_TYPE, content_type);
+        }
+        self.upload_request(request).await
+    }
+
+    /// Upload the files in a directory, their names are already checked
+    async fn upload_files(
+        &self,
+        files: Vec<UploadFile>,
+    ) -> Result<StoreNftResponse, NFTStorageError> {
+        self.check_request_size(files.iter().map(|f| f.content.len() as u64).sum())?;
+        let mut form = Form::new();
+        // creating a custom part of teh form, the files are moved into the form so no copy is made
+        for file in files {
+            form = form.part("file", file.into_part()?);
+        }
+        self.upload_form(form).await
+    }
+
+    /// max size of the body of a single upload request, the content is never allowed above `MAX_CONTENT_SIZE`
+    fn request_size_limit(&self) -> u64 {
+        self.max_request_size.min(MAX_CONTENT_SIZE)
+    }
+
+    /// Return a `PayloadTooLarge` error if an upload request of `size` bytes exceeds the limit
+    fn check_request_size(&self, size: u64) -> Result<(), NFTStorageError> {
+        let limit = self.request_size_limit();
+        if size > limit {
+            return Err(NFTStorageError::PayloadTooLarge { size, limit });
+        }
+        Ok(())
+    }
+
+    /// Upload a multipart form containing the directory files
+    async fn upload_form(&self, form: Form) -> Result<StoreNftResponse, NFTStorageError> {
+        let url = self.endpoint("upload");
+        let request = self.client.post(url).multipart(form);
+        self.upload_request(request).await
+    }
+
+    /// Upload multiple files to Nft Storage
+    ///
+    /// `files` and `file_names` are paired by their position so they must have the same length.
+    ///
+    /// An `InvalidInput` error is returned before sending anything if the lengths differ, if there are no files or
+    /// if a file is empty or a name is blank, the message contains the index of the offending file.
+    /// `upload_directory` accepts the empty files
+    ///
+    /// The buffers are moved into the multipart parts instead of being copied, every part keeps its name and content
+    /// in the order of `files`
+    /// ```
+    /// # #![allow(deprecated)]
+    /// # #[cfg(feature = "test-util")]
+    /// # #[tokio::main]
+    /// # async fn main() -> anyhow::Result<()> {
+    /// use nft_storage::{
+    ///     test_util::{FakeNftStorage, MockTransport},
+    ///     NFTStorageError,
+    /// };
+    ///
+    /// let fake = FakeNftStorage::start().await?;
+    /// let files = vec![b"first".to_vec(), b"second".to_vec(), b"third".to_vec()];
+    /// fake.client().upload_file_in_directory(files.clone(), vec!["1.txt", "2.txt", "3.txt"]).await?;
+    ///
+    /// let parts = fake.uploads().remove(0).parts;
+    /// let names = parts.iter().map(|part| part.file_name.as_deref().unwrap()).collect::<Vec<_>>();
+    /// assert_eq!(names, ["1.txt", "2.txt", "3.txt"]);
+    /// assert_eq!(parts.into_iter().map(|part| part.content).collect::<Vec<_>>(), files);
+    ///
+    /// let mock = MockTransport::new();
+    /// let nft_storage = mock.client();
+    /// let invalid_input = |result: Result<_, NFTStorageError>| match result {
+    ///     Err(NFTStorageError::InvalidInput(message)) => message,
+    ///     other => panic!("unexpected result {:?}", other),
+    /// };
+    ///
+    /// let files = vec![b"a".to_vec(), b"b".to_vec()];
+    /// let message = invalid_input(nft_storage.upload_file_in_directory(files, vec!["a.txt"]).await);
+    /// assert_eq!(message, "got 2 files but 1 file names");
+    ///
+    /// let message = invalid_input(nft_storage.upload_file_in_directory(Vec::new(), Vec::<&str>::new()).await);
+    /// assert_eq!(message, "at least one file is required");
+    ///
+    /// let files = vec![b"a".to_vec(), Vec::new()];
+    /// let message = invalid_input(nft_storage.upload_file_in_directory(files, vec!["a.txt", "b.txt"]).await);
+    /// assert!(message.contains("index 1"));
+    ///
+    /// let files = vec![b"a".to_vec(), b"b".to_vec()];
+    /// let message = invalid_input(nft_storage.upload_file_in_directory(files, vec![" ", "b.txt"]).await);
+    /// assert_eq!(message, "file name at index 0 is empty");
+    ///
+    /// // the nft is checked before uploading the files
+    /// let files = vec![b"a".to_vec()];
+    /// let result = nft_storage.store_nft_in_directory(files, vec![""], "name", "description").await;
+    /// assert_eq!(invalid_input(result), "file name at index 0 is empty");
+    /// assert!(mock.requests().is_empty());
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "test-util"))]
+    /// # fn main() {}
+    /// ```
+    #[deprecated(note = "use `upload_directory` with `UploadFile` instead")]
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn upload_file_in_directory<S>(
         &self,
         files: Vec<Vec<u8>>,
         file_names: Vec<S>,
-        nft_name: S,
-        description: S,
     ) -> Result<StoreNftResponse, NFTStorageError>
     where
         S: AsRef<str>,
     {
+        let files = into_upload_files(files, file_names)?;
+        self.upload_directory(files).await
+    }
+
+    /// Store an NFT on nft storage in a directory
+    ///
+    /// `files` are the files recieved from a form-data we want to create an nft
+    ///
+    /// `nft_name` is the nft name and `description` is the description of the nft
+    ///
+    /// The difference from `upload_directory` method is that after uploading all files it creates a `metadata.json` file
+    /// that lists all files uploaded and also assigns the nft name and it's description, this metadata.json file it is stored on a IPFS Direcotry
+    ///
+    /// The response is the upload of `metadata.json` with the generated metadata in `metadata`, use
+    /// `store_nft_directory_with_options` to get also the upload of the files
+    ///
+    /// ```no_run
+    /// use anyhow::Result;
+    /// use nft_storage::{NftStorage, types::*};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     // provide the url and as second argument the token generated from nft storage dashboard
+    ///     let nft_storage = NftStorage::new("https://api.nft.storage", "token generated from nft storage");
+    ///     // read the files in order to have a Vec<u8> the same from a form-data
+    ///     let files = vec![
+    ///         UploadFile::new("my_nft.jpg", std::fs::read("my_nft.jpg")?),
+    ///         UploadFile::new("my_nft2.jpg", std::fs::read("my_nft2.jpg")?),
+    ///     ];
+    ///     // store an nft, the name and the description can be different string types
+    ///     let description = String::from("My NFT description");
+    ///     let store_nft: StoreNftResponse  = nft_storage.store_nft_directory(files, "My NFT name", &description).await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn store_nft_directory<N, D>(
+        &self,
+        files: Vec<UploadFile>,
+        nft_name: N,
+        description: D,
+    ) -> Result<StoreNftResponse, NFTStorageError>
+    where
+        N: AsRef<str>,
+        D: AsRef<str>,
+    {
+        let response = self
+            .store_nft_directory_with_options(
+                files,
+                nft_name,
+                description,
+                NftDirectoryOptions::default(),
+            )
+            .await?;
+        let mut metadata = response.metadata;
+        metadata.metadata = Some(response.generated_metadata);
+        Ok(metadata)
+    }
+
+    /// Store an NFT on nft storage in a directory returning both the uploads
+    ///
+    /// The files are uploaded in a directory and the generated `metadata.json` lists them as
+    /// `ipfs://<files cid>/<file name>`. By default `metadata.json` is uploaded alone in a second directory, when
+    /// `options.single_directory` is set it is uploaded together with the files so one cid contains the whole nft,
+    /// the files are sent twice in this case.
+    ///
+    /// `options.metadata_file_name` replaces `metadata.json`, like `0.json` for a base uri layout, and
+    /// `options.extra_fields` are added to the generated metadata
+    ///
+    /// ```
+    /// # #[cfg(feature = "test-util")]
+    /// # #[tokio::main]
+    /// # async fn main() -> anyhow::Result<()> {
+    /// use nft_storage::{test_util::FakeNftStorage, types::*};
+    ///
+    /// let fake = FakeNftStorage::start().await?;
+    /// let nft_storage = fake.client();
+    /// let files = vec![UploadFile::new("image.png", b"image".to_vec())];
+    /// let options = NftDirectoryOptions {
+    ///     single_directory: true,
+    ///     ..Default::default()
+    /// };
+    /// let response = nft_storage
+    ///     .store_nft_directory_with_options(files, "My NFT name", "My NFT description", options)
+    ///     .await?;
+    ///
+    /// let image = format!("ipfs://{}/image.png", response.files.value.cid);
+    /// assert_eq!(response.generated_metadata["files"][0], image);
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "test-util"))]
+    /// # fn main() {}
+    /// ```
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn store_nft_directory_with_options<N, D>(
+        &self,
+        files: Vec<UploadFile>,
+        nft_name: N,
+        description: D,
+        options: NftDirectoryOptions,
+    ) -> Result<StoreNftDirectoryResponse, NFTStorageError>
+    where
+        N: AsRef<str>,
+        D: AsRef<str>,
+    {
+        let metadata = NftMetadata::new(nft_name.as_ref(), description.as_ref());
+        metadata.validate(&self.metadata_limits)?;
+        // keep a copy of the files only when they are sent again with the metadata
+        let copies = options.single_directory.then(|| files.clone());
         // upload the file to nft storage, which is the actual file we want to create an nft
-        let response = self.upload_file_in_directory(files, file_names).await?;
-        // get value array
-        let value = response.value.files;
+        let files_response = self.upload_directory(files).await?;
         // get cid of the folder that contains uploaded files
-        let cid = response.value.cid;
+        let cid = &files_response.value.cid;
 
-        // get all filenames uploaded
-        let mut file_names = value.iter().map(|f| f.name.clone()).collect::<Vec<_>>();
         // concatenate to create the ipfs link to paste in metadata.json
-        let file_cids = file_names
-            .iter_mut()
-            .map(|f| format!("ipfs://{}/{}", cid, f))
+        let file_cids = files_response
+            .value
+            .files
+            .iter()
+            .map(|f| format!("ipfs://{}/{}", cid, f.name))
             .collect::<Vec<_>>();
         // create athe metadata form which will contain all files cid
-        let metadata = json!({
-            "name": nft_name.as_ref(),
-            "description": description.as_ref(),
-            "files": file_cids
-        });
+        let mut generated_metadata = options.extra_fields;
+        generated_metadata.insert("name".to_string(), json!(metadata.name));
+        generated_metadata.insert("description".to_string(), json!(metadata.description));
+        generated_metadata.insert("files".to_string(), json!(file_cids));
+        let generated_metadata = Value::Object(generated_metadata);
+        let metadata_file_name = options
+            .metadata_file_name
+            .unwrap_or_else(|| "metadata.json".to_string());
+        // some tools use names without extension, so it isn't an error
+        #[cfg(feature = "tracing")]
+        if !metadata_file_name.ends_with(".json") {
+            tracing::warn!(file_name = %metadata_file_name, "metadata file name without .json extension");
+        }
         // create the form-data instance for metadata.json
-        let metadata_json_bytes = serde_json::to_vec(&metadata)?;
+        let metadata_json_bytes = serde_json::to_vec(&generated_metadata)?;
+        let mut metadata_files = copies.unwrap_or_default();
+        metadata_files.push(UploadFile::new(metadata_file_name, metadata_json_bytes));
         // create the metadata.json which will contain the nft cids
-        let response = self
-            .upload_file_in_directory(vec![metadata_json_bytes], vec!["metadata.json".to_string()])
-            .await?;
+        let metadata_response = self.upload_directory(metadata_files).await.map_err(|e| {
+            e.with_partial_result(|| PartialResult::Stored(vec![files_response.clone()]))
+        })?;
 
-        Ok(response)
+        Ok(StoreNftDirectoryResponse {
+            files: files_response,
+            metadata: metadata_response,
+            generated_metadata,
+        })
+    }
+
+    /// Store an NFT on nft storage in a directory
+    ///
+    /// `files` and `file_names` are paired by their position so they must have the same length, they are checked
+    /// like in `upload_file_in_directory` before uploading anything
+    ///
+    /// ```no_run
+    /// # #![allow(deprecated)]
+    /// use nft_storage::{NftStorage, types::*};
+    /// use anyhow::Result;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let nft_storage = NftStorage::new("https://api.nft.storage", "token generated from nft storage");
+    ///     let files = vec![std::fs::read("my_nft.jpg")?, std::fs::read("my_nft2.jpg")?];
+    ///     // the file names don't need to have the same string type of the nft name and description
+    ///     let file_names: Vec<String> = (1..=2).map(|i| format!("my_nft{}.jpg", i)).collect();
+    ///     let store_nft: StoreNftResponse = nft_storage
+    ///         .store_nft_in_directory(files, file_names, "My NFT name", String::from("My NFT description"))
+    ///         .await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[deprecated(note = "use `store_nft_directory` with `UploadFile` instead")]
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn store_nft_in_directory<F, N, D>(
+        &self,
+        files: Vec<Vec<u8>>,
+        file_names: Vec<F>,
+        nft_name: N,
+        description: D,
+    ) -> Result<StoreNftResponse, NFTStorageError>
+    where
+        F: AsRef<str>,
+        N: AsRef<str>,
+        D: AsRef<str>,
+    {
+        let files = into_upload_files(files, file_names)?;
+        self.store_nft_directory(files, nft_name, description).await
+    }
+}
+
+/// name the upload with the `X-Name` header, it must be percent-encoded
+fn with_name(request: RequestBuilder, name: &str) -> RequestBuilder {
+    let encoded = name
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect::<String>();
+    request.header("X-Name", encoded)
+}
+
+/// pair the file bytes with their names, used by the deprecated methods taking parallel vecs
+///
+/// At least one file is required, the files can't be empty and the names can't be blank, the errors contain the
+/// index of the offending file
+fn into_upload_files<S>(
+    files: Vec<Vec<u8>>,
+    file_names: Vec<S>,
+) -> Result<Vec<UploadFile>, NFTStorageError>
+where
+    S: AsRef<str>,
+{
+    if files.len() != file_names.len() {
+        return Err(NFTStorageError::InvalidInput(format!(
+            "got {} files but {} file names",
+            files.len(),
+            file_names.len()
+        )));
+    }
+    if files.is_empty() {
+        return Err(NFTStorageError::InvalidInput(
+            "at least one file is required".to_string(),
+        ));
+    }
+    if let Some(index) = files.iter().position(|file| file.is_empty()) {
+        return Err(NFTStorageError::InvalidInput(format!(
+            "file at index {} is empty, use `upload_directory` to upload empty files",
+            index
+        )));
+    }
+    if let Some(index) = file_names
+        .iter()
+        .position(|name| name.as_ref().trim().is_empty())
+    {
+        return Err(NFTStorageError::InvalidInput(format!(
+            "file name at index {} is empty",
+            index
+        )));
+    }
+    Ok(files
+        .into_iter()
+        .zip(file_names)
+        .map(|(file, file_name)| UploadFile::new(file_name.as_ref(), file))
+        .collect())
+}
+
+/// trim the file names of a directory upload and handle the duplicated ones following `strategy`
+///
+/// The names containing a path separator are rejected unless `allow_nested_paths` is true
+fn check_file_names(
+    files: Vec<UploadFile>,
+    strategy: DedupeStrategy,
+    allow_nested_paths: bool,
+) -> Result<Vec<UploadFile>, NFTStorageError> {
+    // every original name is taken, so a renamed file can't clash with a later one
+    let mut taken = files
+        .iter()
+        .map(|file| file.name.trim().to_string())
+        .collect::<HashSet<_>>();
+    let mut positions = HashMap::new();
+    let mut checked: Vec<UploadFile> = Vec::with_capacity(files.len());
+    for mut file in files {
+        file.name = file.name.trim().to_string();
+        if !allow_nested_paths && file.name.contains(['/', '\\']) {
+            return Err(NFTStorageError::InvalidInput(format!(
+                "file name '{}' contains a path separator, allow nested paths to create sub directories",
+                file.name
+            )));
+        }
+        match (positions.get(&file.name), strategy) {
+            (None, _) => {
+                positions.insert(file.name.clone(), checked.len());
+                checked.push(file);
+            }
+            (Some(_), DedupeStrategy::Error) => {
+                return Err(NFTStorageError::DuplicateFileName(file.name))
+            }
+            (Some(&position), DedupeStrategy::Overwrite) => checked[position] = file,
+            (Some(_), DedupeStrategy::AutoRename) => {
+                let (stem, extension) = match file.name.rfind('.') {
+                    Some(dot) if dot > 0 => file.name.split_at(dot),
+                    _ => (file.name.as_str(), ""),
+                };
+                let name = (1..)
+                    .map(|counter| format!("{} ({}){}", stem, counter, extension))
+                    .find(|name| !taken.contains(name))
+                    .expect("the counter is unbounded");
+                taken.insert(name.clone());
+                positions.insert(name.clone(), checked.len());
+                file.name = name;
+                checked.push(file);
+            }
+        }
+    }
+    Ok(checked)
+}
+
+/// group the files by the sha2-256 digest of their content, in the order of their first file
+#[cfg(feature = "car")]
+fn dedup_report(files: &[UploadFile]) -> DedupReport {
+    let mut groups: Vec<(u64, Vec<String>)> = Vec::new();
+    let mut positions: HashMap<String, usize> = HashMap::new();
+    for file in files {
+        let digest = cid::sha256_hex(&file.content);
+        match positions.get(&digest) {
+            Some(&position) => groups[position].1.push(file.name.clone()),
+            None => {
+                positions.insert(digest, groups.len());
+                groups.push((file.content.len() as u64, vec![file.name.clone()]));
+            }
+        }
+    }
+    DedupReport {
+        unique_files: groups.len(),
+        bytes_saved: groups
+            .iter()
+            .map(|(size, names)| size * (names.len() as u64 - 1))
+            .sum(),
+        duplicate_groups: groups
+            .into_iter()
+            .filter(|(_, names)| names.len() > 1)
+            .map(|(_, names)| names)
+            .collect(),
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+/// collect the files of `dir` paired with their path relative to `dir` using `/` as separator
+async fn read_directory_files(
+    dir: &Path,
+    options: &DirectoryOptions,
+) -> Result<Vec<(PathBuf, String)>, NFTStorageError> {
+    let mut files = Vec::new();
+    // directories still to visit
+    let mut dirs = vec![dir.to_path_buf()];
+    while let Some(current) = dirs.pop() {
+        let mut entries = tokio::fs::read_dir(&current).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let is_hidden = entry.file_name().to_string_lossy().starts_with('.');
+            if is_hidden && !options.include_hidden {
+                continue;
+            }
+            let path = entry.path();
+            let file_type = entry.file_type().await?;
+            if file_type.is_dir() {
+                if options.recursive {
+                    dirs.push(path);
+                }
+            } else if file_type.is_file() {
+                let relative = path
+                    .strip_prefix(dir)
+                    .unwrap_or(&path)
+                    .components()
+                    .map(|c| c.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/");
+                files.push((path, relative));
+            }
+        }
+    }
+    // keep the upload order stable
+    files.sort_by(|a, b| a.1.cmp(&b.1));
+    Ok(files)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+/// detect the mime type of a file from its first bytes or its extension, the file is read from the start again
+async fn detect_file_mime(file: &mut File, path: &Path) -> Result<Option<String>, NFTStorageError> {
+    let mut head = vec![0; MIME_DETECTION_LEN];
+    let mut read = 0;
+    while read < head.len() {
+        let n = file.read(&mut head[read..]).await?;
+        if n == 0 {
+            break;
+        }
+        read += n;
+    }
+    head.truncate(read);
+    file.seek(SeekFrom::Start(0)).await?;
+    Ok(detect_mime(&path.to_string_lossy(), &head))
+}
+
+/// add the convenience links to the listed nfts, pointing to the metadata.json file when listing only metadata
+fn decorate_links(
+    nfts: Vec<NftValue>,
+    only_metadata: bool,
+    link_gateways: &[Gateway],
+) -> Vec<NftValue> {
+    let path = if only_metadata { "/metadata.json" } else { "" };
+    nfts.into_iter()
+        .map(|mut f| {
+            f.links = GatewayLinks::with_gateways(f.cid.as_str(), path, link_gateways);
+            f
+        })
+        .collect()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+/// size of the chunks of the bodies whose progress is reported
+const PROGRESS_CHUNK_SIZE: usize = 64 * 1024;
+
+/// count the bytes sent by the bodies of an upload
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Clone)]
+struct ProgressTracker {
+    /// bytes sent by all the bodies
+    sent: Arc<AtomicU64>,
+    total_bytes: u64,
+    on_progress: Arc<dyn Fn(UploadProgress) + Send + Sync>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl ProgressTracker {
+    /// create the tracker reporting the start of the upload
+    fn new(total_bytes: u64, on_progress: UploadProgressCallback) -> ProgressTracker {
+        let tracker = ProgressTracker {
+            sent: Arc::new(AtomicU64::new(0)),
+            total_bytes,
+            on_progress: Arc::from(on_progress),
+        };
+        tracker.report(0);
+        tracker
+    }
+
+    /// add the `bytes` just sent and report the progress
+    fn report(&self, bytes: u64) {
+        let bytes_sent = self.sent.fetch_add(bytes, Ordering::SeqCst) + bytes;
+        (self.on_progress)(UploadProgress {
+            bytes_sent,
+            total_bytes: self.total_bytes,
+        });
+    }
+
+    /// report the end of an empty upload, the others reach 100% when the last chunk is sent
+    fn finish(&self) {
+        if self.total_bytes == 0 {
+            self.report(0);
+        }
+    }
+
+    /// stream `content` in chunks reporting them when they are sent
+    fn body(&self, content: Vec<u8>) -> Body {
+        let content = Bytes::from(content);
+        let length = content.len();
+        let tracker = self.clone();
+        let chunks = (0..length)
+            .step_by(PROGRESS_CHUNK_SIZE)
+            .map(move |start| content.slice(start..length.min(start + PROGRESS_CHUNK_SIZE)));
+        Body::wrap_stream(stream::iter(chunks).map(move |chunk| {
+            tracker.report(chunk.len() as u64);
+            Ok::<_, std::io::Error>(chunk)
+        }))
+    }
+}
+
+/// stream the chunks aborting the body as soon as they exceed `limit` bytes, the size sent is stored in `exceeded`
+///
+/// The stream can be longer than the `Content-Length` declared by the caller, so the chunks are counted while they
+/// are sent
+#[cfg(not(target_arch = "wasm32"))]
+fn limited_body<S, E>(stream: S, limit: u64, exceeded: Arc<AtomicU64>) -> Body
+where
+    S: Stream<Item = Result<Bytes, E>> + Send + Sync + 'static,
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    let mut sent = 0;
+    Body::wrap_stream(stream.map(
+        move |chunk| -> Result<Bytes, Box<dyn std::error::Error + Send + Sync>> {
+            let chunk = chunk.map_err(Into::into)?;
+            sent += chunk.len() as u64;
+            if sent > limit {
+                exceeded.store(sent, Ordering::SeqCst);
+                return Err(NFTStorageError::PayloadTooLarge { size: sent, limit }.into());
+            }
+            Ok(chunk)
+        },
+    ))
+}
+
+/// state used to follow the pages when listing all nfts
+#[derive(Debug, Default)]
+struct ListCursor {
+    /// `created` timestamp of the oldest nft of the last page
+    before: Option<String>,
+    /// cids already returned
+    seen: HashSet<Cid>,
+    /// true when there are no more pages
+    done: bool,
+}
+
+/// deserialize a successful api response or return an `ApiError` keeping the status code and the headers
+///
+/// The body is read as text first, so a non json body (like an html error page of a proxy) returns an
+/// `UnexpectedResponse` error with the status code and the truncated body
+async fn parse_response<T>(response: Response) -> Result<T, NFTStorageError>
+where
+    T: DeserializeOwned,
+{
+    let status = response.status();
+    // the headers must be read before consuming the body
+    let retry_after = retry_after(&response);
+    let request_id = hooks::request_id(&response);
+    let rate_limit = match status {
+        StatusCode::TOO_MANY_REQUESTS => RateLimitInfo::from_response(&response).map(Box::new),
+        _ => None,
+    };
+    // authentication errors are detected from the status whatever the body is
+    #[cfg(feature = "tracing")]
+    if !status.is_success() {
+        tracing::error!(status = status.as_u16(), request_id = ?request_id, "nft storage api error");
+    }
+    match status {
+        StatusCode::UNAUTHORIZED => return Err(NFTStorageError::Unauthorized),
+        StatusCode::FORBIDDEN => return Err(NFTStorageError::Forbidden),
+        _ => {}
+    }
+    let text = response.text().await?;
+    // a successful body is deserialized directly, it is parsed again only to tell a non json body from a json one
+    // with another shape
+    let body = if status.is_success() {
+        match serde_json::from_str::<T>(&text) {
+            Ok(value) => return Ok(value),
+            Err(e) if serde_json::from_str::<IgnoredAny>(&text).is_ok() => return Err(e.into()),
+            Err(_) => None,
+        }
+    } else {
+        serde_json::from_str::<Value>(&text).ok()
+    };
+    let body = match body {
+        Some(body) => body,
+        None => {
+            #[cfg(feature = "tracing")]
+            tracing::debug!(status = status.as_u16(), body = %text, "unexpected response body");
+            return Err(NFTStorageError::UnexpectedResponse {
+                status: status.as_u16(),
+                body: truncate(text, MAX_ERROR_BODY_LEN),
+            });
+        }
+    };
+    #[cfg(feature = "tracing")]
+    tracing::debug!(status = status.as_u16(), body = %body, "nft storage api error body");
+    if let Some(message) = read_only_message(&body) {
+        return Err(NFTStorageError::ServiceReadOnly { message });
+    }
+    let error = serde_json::from_value::<ApiErrorBody>(body.clone())
+        .ok()
+        .map(Box::new);
+    Err(NFTStorageError::ApiError {
+        status: status.as_u16(),
+        body,
+        error,
+        retry_after,
+        request_id,
+        rate_limit,
+    })
+}
+
+/// message of an error body telling that the api is read only, the writes are rejected with it during a maintenance
+/// or the decommission of the service
+fn read_only_message(body: &Value) -> Option<String> {
+    let error = body.get("error")?;
+    let field = |name: &str| error.get(name).and_then(Value::as_str);
+    let read_only =
+        field("code") == Some(READ_ONLY_ERROR_CODE) || field("name") == Some(READ_ONLY_ERROR_NAME);
+    read_only.then(|| field("message").unwrap_or(READ_ONLY_ERROR_NAME).to_string())
+}
+
+/// shorten `text` to at most `max_len` bytes without splitting a character
+fn truncate(mut text: String, max_len: usize) -> String {
+    if text.len() > max_len {
+        let mut end = max_len;
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        text.truncate(end);
+        text.push_str("...");
+    }
+    text
+}
+
+/// read the whole body of a gateway response
+async fn read_gateway_body(response: Response) -> Result<Bytes, NFTStorageError> {
+    let url = response.url().to_string();
+    response.bytes().await.map_err(|e| gateway_error(e, &url))
+}
+
+/// check the optional signal of a long running operation
+fn is_cancelled(cancel: &mut Option<CancelSignal>) -> bool {
+    cancel.as_mut().is_some_and(CancelSignal::is_cancelled)
+}
+
+/// quote a csv field containing a separator, a quote or a line break, the quotes are doubled
+fn csv_field(field: &str) -> std::borrow::Cow<'_, str> {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\"")).into()
+    } else {
+        field.into()
+    }
+}
+
+/// map the timeouts of a gateway request to `GatewayTimeout`
+fn gateway_error(error: reqwest::Error, url: &str) -> NFTStorageError {
+    if error.is_timeout() {
+        NFTStorageError::GatewayTimeout(url.to_string())
+    } else {
+        NFTStorageError::InvalidRequest(error)
+    }
+}
+
+/// check that `cid` is a valid CIDv0 (`Qm...`) or CIDv1 (`baf...`)
+fn validate_cid(cid: &str) -> Result<(), NFTStorageError> {
+    Cid::new(cid).map(|_| ())
+}
+
+/// check that the `actual` cid returned by the api is the `expected` one, the api can return the same cid
+/// with another version or encoding
+fn ensure_cid_matches(expected: &Cid, actual: &Cid) -> Result<(), NFTStorageError> {
+    if actual.to_v1().ok().as_ref() != Some(expected) {
+        return Err(NFTStorageError::CidMismatch {
+            expected: expected.clone(),
+            actual: actual.clone(),
+        });
     }
+    Ok(())
 }
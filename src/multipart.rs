@@ -0,0 +1,100 @@
+//! Read the files of a multipart form received by a web service, available with the `axum` and `actix` features
+//!
+//! The fields are drained in order and converted to [`UploadFile`]s keeping their file names and content types, so
+//! they can be forwarded with `upload_directory`. The fields without a file name use the field name.
+//! The fields are buffered in memory, limit the size of the request body with the framework to bound it
+//! ```no_run
+//! # #[cfg(feature = "axum")]
+//! # mod example {
+//! use axum::{extract::{Multipart, State}, http::StatusCode};
+//! use nft_storage::{multipart::from_axum_multipart, NftStorage};
+//!
+//! async fn upload(
+//!     State(nft_storage): State<NftStorage>,
+//!     multipart: Multipart,
+//! ) -> Result<String, (StatusCode, String)> {
+//!     let files = from_axum_multipart(multipart)
+//!         .await
+//!         .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+//!     let response = nft_storage
+//!         .upload_directory(files)
+//!         .await
+//!         .map_err(|e| (StatusCode::BAD_GATEWAY, e.to_string()))?;
+//!     Ok(response.value.cid.to_string())
+//! }
+//! # }
+//! ```
+use crate::{types::UploadFile, NFTStorageError};
+
+/// Drain the fields of an axum multipart form
+#[cfg(feature = "axum")]
+pub async fn from_axum_multipart(
+    mut multipart: axum::extract::Multipart,
+) -> Result<Vec<UploadFile>, NFTStorageError> {
+    let mut files = Vec::new();
+    while let Some(field) = multipart
+        .next_field()
+        .await
+        .map_err(|e| NFTStorageError::Multipart(e.to_string()))?
+    {
+        let name = field_name(field.file_name(), field.name(), files.len());
+        let mime = field.content_type().map(|mime| mime.to_string());
+        let content = field
+            .bytes()
+            .await
+            .map_err(|e| NFTStorageError::Multipart(e.to_string()))?;
+        files.push(upload_file(name, mime, content.to_vec()));
+    }
+    Ok(files)
+}
+
+/// Drain the fields of an actix multipart form
+#[cfg(feature = "actix")]
+pub async fn from_actix_multipart(
+    mut multipart: actix_multipart::Multipart,
+) -> Result<Vec<UploadFile>, NFTStorageError> {
+    use futures::TryStreamExt;
+
+    let mut files = Vec::new();
+    while let Some(mut field) = multipart
+        .try_next()
+        .await
+        .map_err(|e| NFTStorageError::Multipart(e.to_string()))?
+    {
+        let disposition = field.content_disposition();
+        let name = field_name(
+            disposition.get_filename(),
+            disposition.get_name(),
+            files.len(),
+        );
+        let mime = field.content_type().map(|mime| mime.to_string());
+        let mut content = Vec::new();
+        while let Some(chunk) = field
+            .try_next()
+            .await
+            .map_err(|e| NFTStorageError::Multipart(e.to_string()))?
+        {
+            content.extend_from_slice(&chunk);
+        }
+        files.push(upload_file(name, mime, content));
+    }
+    Ok(files)
+}
+
+/// name of the uploaded file, the file name of the field or its name, a generated one if both are missing
+fn field_name(file_name: Option<&str>, name: Option<&str>, index: usize) -> String {
+    file_name
+        .or(name)
+        .filter(|name| !name.is_empty())
+        .map(|name| name.to_string())
+        .unwrap_or_else(|| format!("file-{}", index))
+}
+
+/// the generic `application/octet-stream` sent by the browsers is dropped so the type is detected from the content
+fn upload_file(name: String, mime: Option<String>, content: Vec<u8>) -> UploadFile {
+    let file = UploadFile::new(name, content);
+    match mime {
+        Some(mime) if mime != "application/octet-stream" => file.mime(mime),
+        _ => file,
+    }
+}
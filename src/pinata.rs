@@ -0,0 +1,294 @@
+//! Pinata client, available with the `pinata` feature
+//!
+//! It shares the transport, retries, rate limiter and hooks of [`NftStorage`], so a client configured with
+//! [`NftStorage::builder`] can be used for Pinata too, see [`Pinata::from_client`].
+//! Both clients implement [`IpfsStorageProvider`] to switch provider without changing the application code
+//! ```no_run
+//! use nft_storage::{pinata::Pinata, types::UploadFile, IpfsStorageProvider};
+//! use anyhow::Result;
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<()> {
+//!     // provide the jwt generated from the pinata dashboard
+//!     let pinata = Pinata::new("pinata jwt")?;
+//!     let stored = pinata.upload_file(UploadFile::new("hello.txt", b"hello".to_vec())).await?;
+//!     println!("{}", stored.cid);
+//!
+//!     Ok(())
+//! }
+//! ```
+use crate::{
+    parse_response,
+    provider::{IpfsStorageProvider, ProviderFuture, StoredObject},
+    types::{Cid, Timestamp, UploadFile},
+    NFTStorageError, NftStorage,
+};
+use reqwest::multipart::{Form, Part};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+/// default pinata rest api endpoint
+pub const PINATA_API_URL: &str = "https://api.pinata.cloud";
+
+/// pinata ipfs gateway
+pub const PINATA_GATEWAY_URL: &str = "https://gateway.pinata.cloud/ipfs/";
+
+/// max number of pins returned by a single list request
+pub const MAX_PINATA_PAGE_LIMIT: u32 = 1000;
+
+/// response of `pinFileToIPFS`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(default)]
+#[non_exhaustive]
+pub struct PinFileResponse {
+    /// cid of the pinned content
+    #[serde(rename = "IpfsHash")]
+    pub ipfs_hash: Cid,
+    /// size of the pinned content
+    #[serde(rename = "PinSize")]
+    pub pin_size: u64,
+    /// date the content was pinned
+    #[serde(rename = "Timestamp")]
+    #[cfg_attr(
+        feature = "chrono",
+        serde(deserialize_with = "crate::types::deserialize_timestamp")
+    )]
+    pub timestamp: Timestamp,
+}
+
+/// response of `pinList`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(default)]
+#[non_exhaustive]
+pub struct PinListResponse {
+    /// number of pins matching the query, it can be bigger than `rows`
+    pub count: u64,
+    /// pins of the page
+    pub rows: Vec<PinListRow>,
+}
+
+/// pin returned by `pinList`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(default)]
+#[non_exhaustive]
+pub struct PinListRow {
+    /// id of the pin
+    pub id: String,
+    /// cid of the pinned content
+    pub ipfs_pin_hash: Cid,
+    /// size of the pinned content
+    pub size: u64,
+    /// date the content was pinned
+    #[cfg_attr(
+        feature = "chrono",
+        serde(deserialize_with = "crate::types::deserialize_timestamp")
+    )]
+    pub date_pinned: Timestamp,
+    /// name and key values of the pin
+    pub metadata: Value,
+}
+
+/// filters used to list the pins
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PinListQuery {
+    /// return only the pins with a cid containing this string
+    pub hash_contains: Option<String>,
+    /// `all`, `pinned` or `unpinned`, the api uses `all` if not set
+    pub status: Option<String>,
+    /// max number of pins returned, between 1 and 1000
+    pub page_limit: Option<u32>,
+    /// number of pins to skip
+    pub page_offset: Option<u32>,
+}
+
+impl From<PinFileResponse> for StoredObject {
+    fn from(pin: PinFileResponse) -> StoredObject {
+        StoredObject::new(pin.ipfs_hash, pin.pin_size, pin.timestamp)
+    }
+}
+
+impl From<PinListRow> for StoredObject {
+    fn from(pin: PinListRow) -> StoredObject {
+        StoredObject::new(pin.ipfs_pin_hash, pin.size, pin.date_pinned)
+    }
+}
+
+/// Pinata client
+///
+/// Cloning it is cheap, the clones share the connection pool and the rate limiter
+#[derive(Debug, Clone)]
+pub struct Pinata {
+    /// client running the requests, its url is the pinata api
+    inner: NftStorage,
+}
+
+impl Pinata {
+    /// Create a new pinata client from the `jwt` generated from the pinata dashboard
+    pub fn new<S>(jwt: S) -> Result<Pinata, NFTStorageError>
+    where
+        S: Into<String>,
+    {
+        let inner = NftStorage::builder(PINATA_API_URL.to_string(), jwt.into())
+            .gateway(PINATA_GATEWAY_URL)
+            .build()?;
+        Ok(Pinata::from_client(inner))
+    }
+
+    /// Create a pinata client from an already configured client, its url must be the pinata api
+    /// ```no_run
+    /// use nft_storage::{pinata::{Pinata, PINATA_API_URL}, NftStorage};
+    /// use std::time::Duration;
+    ///
+    /// let client = NftStorage::builder(PINATA_API_URL, "pinata jwt")
+    ///     .timeout(Duration::from_secs(60 * 60))
+    ///     .build()
+    ///     .unwrap();
+    /// let pinata = Pinata::from_client(client);
+    /// ```
+    pub fn from_client(inner: NftStorage) -> Pinata {
+        Pinata { inner }
+    }
+
+    /// The client used to run the requests
+    pub fn inner(&self) -> &NftStorage {
+        &self.inner
+    }
+
+    /// Pin a single file
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn pin_file(&self, file: UploadFile) -> Result<PinFileResponse, NFTStorageError> {
+        let name = file.name.clone();
+        let form = Form::new().part("file", file.into_part()?);
+        self.pin_form(form, &name).await
+    }
+
+    /// Pin the files in a directory called `name`, the file names can contain `/` to create sub directories
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn pin_directory(
+        &self,
+        name: &str,
+        files: Vec<UploadFile>,
+    ) -> Result<PinFileResponse, NFTStorageError> {
+        if files.is_empty() {
+            return Err(NFTStorageError::InvalidInput(
+                "at least one file is required".to_string(),
+            ));
+        }
+        let mut form = Form::new();
+        // pinata creates the directory from the common prefix of the file paths
+        for mut file in files {
+            file.name = format!("{}/{}", name, file.name);
+            form = form.part("file", file.into_part()?);
+        }
+        self.pin_form(form, name).await
+    }
+
+    /// List a page of pins
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn pin_list(&self, query: &PinListQuery) -> Result<PinListResponse, NFTStorageError> {
+        let mut params = Vec::new();
+        if let Some(hash_contains) = &query.hash_contains {
+            params.push(("hashContains", hash_contains.clone()));
+        }
+        if let Some(status) = &query.status {
+            params.push(("status", status.clone()));
+        }
+        if let Some(page_limit) = query.page_limit {
+            if page_limit == 0 || page_limit > MAX_PINATA_PAGE_LIMIT {
+                return Err(NFTStorageError::InvalidInput(format!(
+                    "page limit must be between 1 and {}, got {}",
+                    MAX_PINATA_PAGE_LIMIT, page_limit
+                )));
+            }
+            params.push(("pageLimit", page_limit.to_string()));
+        }
+        if let Some(page_offset) = query.page_offset {
+            params.push(("pageOffset", page_offset.to_string()));
+        }
+        let url = self.inner.endpoint("data/pinList");
+        let request = self.inner.client.get(url).query(&params);
+        self.inner.request(request).await
+    }
+
+    /// Unpin the content of `cid`
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn unpin<C>(&self, cid: C) -> Result<(), NFTStorageError>
+    where
+        C: TryInto<Cid>,
+        NFTStorageError: From<C::Error>,
+    {
+        let cid: Cid = cid.try_into()?;
+        let url = self.inner.endpoint(&format!("pinning/unpin/{}", cid));
+        let response = self
+            .inner
+            .execute_with_retry(self.inner.client.delete(url))
+            .await?;
+        // the api responds with a plain `OK`
+        if response.status().is_success() {
+            return Ok(());
+        }
+        parse_response::<Value>(response).await.map(|_| ())
+    }
+
+    /// Send a `pinFileToIPFS` request naming the pin `name`
+    async fn pin_form(&self, form: Form, name: &str) -> Result<PinFileResponse, NFTStorageError> {
+        let metadata = json!({ "name": name }).to_string();
+        let form = form.part("pinataMetadata", Part::text(metadata));
+        let url = self.inner.endpoint("pinning/pinFileToIPFS");
+        let request = self.inner.client.post(url).multipart(form);
+        self.inner.request(request).await
+    }
+}
+
+impl IpfsStorageProvider for Pinata {
+    fn upload_file(&self, file: UploadFile) -> ProviderFuture<'_, StoredObject> {
+        Box::pin(async move { Ok(self.pin_file(file).await?.into()) })
+    }
+
+    fn upload_directory(&self, files: Vec<UploadFile>) -> ProviderFuture<'_, StoredObject> {
+        Box::pin(async move { Ok(self.pin_directory("upload", files).await?.into()) })
+    }
+
+    fn list(&self) -> ProviderFuture<'_, Vec<StoredObject>> {
+        Box::pin(async move {
+            let mut objects = Vec::new();
+            loop {
+                let query = PinListQuery {
+                    status: Some("pinned".to_string()),
+                    page_limit: Some(MAX_PINATA_PAGE_LIMIT),
+                    page_offset: Some(objects.len() as u32),
+                    ..Default::default()
+                };
+                let page = self.pin_list(&query).await?;
+                let is_last_page = page.rows.len() < MAX_PINATA_PAGE_LIMIT as usize;
+                objects.extend(page.rows.into_iter().map(StoredObject::from));
+                if is_last_page || objects.len() as u64 >= page.count {
+                    return Ok(objects);
+                }
+            }
+        })
+    }
+
+    fn delete<'a>(&'a self, cid: &'a Cid) -> ProviderFuture<'a, ()> {
+        Box::pin(async move { self.unpin(cid).await })
+    }
+
+    fn status<'a>(&'a self, cid: &'a Cid) -> ProviderFuture<'a, StoredObject> {
+        Box::pin(async move {
+            let query = PinListQuery {
+                hash_contains: Some(cid.to_string()),
+                status: Some("pinned".to_string()),
+                ..Default::default()
+            };
+            self.pin_list(&query)
+                .await?
+                .rows
+                .into_iter()
+                .find(|row| row.ipfs_pin_hash == *cid)
+                .map(StoredObject::from)
+                .ok_or_else(|| NFTStorageError::NotFound {
+                    cid: cid.to_string(),
+                })
+        })
+    }
+}
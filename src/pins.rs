@@ -0,0 +1,378 @@
+//! IPFS Pinning Services API, used to pin content already available on IPFS without uploading it again
+//!
+//! See https://ipfs.github.io/pinning-services-api-spec/ for the details of the endpoints
+use crate::{
+    parse_response,
+    types::{to_rfc3339, Cid, Timestamp},
+    NFTStorageError, NftStorage,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{collections::HashMap, fmt};
+
+/// max number of pins returned by a single list request
+pub const MAX_PIN_LIST_LIMIT: u32 = 1000;
+
+/// status of a pin request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PinningStatus {
+    /// waiting to be processed
+    Queued,
+    /// the content is being retrieved
+    Pinning,
+    /// the content is pinned
+    Pinned,
+    /// the content couldn't be retrieved
+    Failed,
+}
+
+impl fmt::Display for PinningStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let status = match self {
+            PinningStatus::Queued => "queued",
+            PinningStatus::Pinning => "pinning",
+            PinningStatus::Pinned => "pinned",
+            PinningStatus::Failed => "failed",
+        };
+        write!(f, "{}", status)
+    }
+}
+
+/// strategy used to match the `name` of the pins when listing them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TextMatchingStrategy {
+    /// case sensitive full match
+    Exact,
+    /// case insensitive full match
+    Iexact,
+    /// case sensitive partial match
+    Partial,
+    /// case insensitive partial match
+    Ipartial,
+}
+
+impl fmt::Display for TextMatchingStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let strategy = match self {
+            TextMatchingStrategy::Exact => "exact",
+            TextMatchingStrategy::Iexact => "iexact",
+            TextMatchingStrategy::Partial => "partial",
+            TextMatchingStrategy::Ipartial => "ipartial",
+        };
+        write!(f, "{}", strategy)
+    }
+}
+
+/// content to pin
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(default)]
+#[non_exhaustive]
+pub struct PinObject {
+    /// cid of the content
+    pub cid: Cid,
+    /// optional name used to find the pin
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// multiaddrs of the peers providing the content, they speed up the retrieval
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub origins: Vec<String>,
+    /// custom metadata of the pin
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub meta: HashMap<String, String>,
+}
+
+impl PinObject {
+    /// Create the pin of `cid`
+    pub fn new(cid: Cid) -> PinObject {
+        PinObject {
+            cid,
+            ..Default::default()
+        }
+    }
+
+    /// Set the name of the pin
+    pub fn name<S>(mut self, name: S) -> PinObject
+    where
+        S: Into<String>,
+    {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Add a multiaddr of a peer providing the content
+    pub fn origin<S>(mut self, origin: S) -> PinObject
+    where
+        S: Into<String>,
+    {
+        self.origins.push(origin.into());
+        self
+    }
+
+    /// Add a metadata entry
+    pub fn meta<K, V>(mut self, key: K, value: V) -> PinObject
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.meta.insert(key.into(), value.into());
+        self
+    }
+}
+
+/// status of a pin request
+///
+/// ```
+/// use nft_storage::pins::{PinningStatus, PinStatusResponse};
+///
+/// let pin: PinStatusResponse = serde_json::from_str(r#"{
+///     "requestid": "UniqueIdOfPinRequest",
+///     "status": "pinned",
+///     "created": "2020-07-27T17:32:28Z",
+///     "pin": { "cid": "QmbWqxBEKC3P8tqsKc98xmWNzrzDtRLMiMPL8wBuTGsMnR", "name": "my pin" },
+///     "delegates": ["/dnsaddr/pin-service.example.com"],
+///     "info": {}
+/// }"#).unwrap();
+///
+/// assert_eq!(pin.status, PinningStatus::Pinned);
+/// assert_eq!(pin.pin.name.as_deref(), Some("my pin"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct PinStatusResponse {
+    /// id of the pin request, used to get, replace or remove the pin
+    #[serde(rename = "requestid")]
+    pub request_id: String,
+    /// status of the pin
+    pub status: PinningStatus,
+    /// date the pin request was received
+    #[cfg_attr(
+        feature = "chrono",
+        serde(deserialize_with = "crate::types::deserialize_timestamp")
+    )]
+    pub created: Timestamp,
+    /// pinned content
+    pub pin: PinObject,
+    /// multiaddrs of the peers of the pinning service, connect to them to speed up the retrieval
+    #[serde(default)]
+    pub delegates: Vec<String>,
+    /// additional information about the pin
+    #[serde(default)]
+    pub info: HashMap<String, Value>,
+}
+
+/// page of the pins matching a [`PinListQuery`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(default)]
+#[non_exhaustive]
+pub struct PinResults {
+    /// total number of pins matching the query, it can be bigger than `results`
+    pub count: u64,
+    /// pins of the page
+    pub results: Vec<PinStatusResponse>,
+}
+
+/// filters used to list the pins, by default only the pinned ones are returned
+#[derive(Debug, Clone, Default)]
+pub struct PinListQuery {
+    /// return only the pins of these cids
+    pub cid: Vec<Cid>,
+    /// return only the pins with this name
+    pub name: Option<String>,
+    /// strategy used to match the `name`, the api uses `exact` if not set
+    pub match_strategy: Option<TextMatchingStrategy>,
+    /// return only the pins with these statuses
+    pub status: Vec<PinningStatus>,
+    /// return only the pins created before this date
+    pub before: Option<Timestamp>,
+    /// return only the pins created after this date
+    pub after: Option<Timestamp>,
+    /// max number of pins returned, between 1 and 1000
+    pub limit: Option<u32>,
+    /// return only the pins containing this metadata
+    pub meta: HashMap<String, String>,
+}
+
+impl PinListQuery {
+    /// query parameters of the list request, the lists are comma separated
+    fn to_query(&self) -> Result<Vec<(&'static str, String)>, NFTStorageError> {
+        let mut query = Vec::new();
+        if !self.cid.is_empty() {
+            let cids = self.cid.iter().map(Cid::as_str).collect::<Vec<_>>();
+            query.push(("cid", cids.join(",")));
+        }
+        if let Some(name) = &self.name {
+            query.push(("name", name.clone()));
+        }
+        if let Some(match_strategy) = self.match_strategy {
+            query.push(("match", match_strategy.to_string()));
+        }
+        if !self.status.is_empty() {
+            let status = self
+                .status
+                .iter()
+                .map(|s| s.to_string())
+                .collect::<Vec<_>>();
+            query.push(("status", status.join(",")));
+        }
+        if let Some(before) = &self.before {
+            query.push(("before", to_rfc3339(before)));
+        }
+        if let Some(after) = &self.after {
+            query.push(("after", to_rfc3339(after)));
+        }
+        if let Some(limit) = self.limit {
+            if limit == 0 || limit > MAX_PIN_LIST_LIMIT {
+                return Err(NFTStorageError::InvalidInput(format!(
+                    "limit must be between 1 and {}, got {}",
+                    MAX_PIN_LIST_LIMIT, limit
+                )));
+            }
+            query.push(("limit", limit.to_string()));
+        }
+        if !self.meta.is_empty() {
+            query.push(("meta", serde_json::to_string(&self.meta)?));
+        }
+        Ok(query)
+    }
+}
+
+/// Pinning Services API methods
+impl NftStorage {
+    /// Pin a cid already available on IPFS
+    ///
+    /// nft storage retrieves the content from the IPFS network, check the returned status with `get_pin` to know
+    /// when it is pinned
+    /// ```no_run
+    /// use nft_storage::{NftStorage, pins::*};
+    /// use anyhow::Result;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     // provide the url and as second argument the token generated from nft storage dashboard
+    ///     let nft_storage = NftStorage::new("https://api.nft.storage", "token generated from nft storage");
+    ///     // pin a cid by name
+    ///     let pin: PinStatusResponse = nft_storage.pin_cid("bafybeihflij24dndd6qo3aacbbysuzuygis7yurvrzxp3uk7bk5kfvfsdt", Some("my nft")).await?;
+    ///     println!("{} is {}", pin.request_id, pin.status);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn pin_cid<C>(
+        &self,
+        cid: C,
+        name: Option<&str>,
+    ) -> Result<PinStatusResponse, NFTStorageError>
+    where
+        C: TryInto<Cid>,
+        NFTStorageError: From<C::Error>,
+    {
+        let mut pin = PinObject::new(cid.try_into()?);
+        pin.name = name.map(|name| name.to_string());
+        self.pin(pin).await
+    }
+
+    /// Pin a cid setting its origins and metadata
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn pin(&self, pin: PinObject) -> Result<PinStatusResponse, NFTStorageError> {
+        let url = self.endpoint("pins");
+        self.request(self.client.post(url).json(&pin)).await
+    }
+
+    /// List the pin requests matching the `query`
+    /// ```no_run
+    /// use nft_storage::{NftStorage, pins::*};
+    /// use anyhow::Result;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     // provide the url and as second argument the token generated from nft storage dashboard
+    ///     let nft_storage = NftStorage::new("https://api.nft.storage", "token generated from nft storage");
+    ///     // list the pins still in progress
+    ///     let query = PinListQuery {
+    ///         status: vec![PinningStatus::Queued, PinningStatus::Pinning],
+    ///         ..Default::default()
+    ///     };
+    ///     let pins: PinResults = nft_storage.list_pins(query).await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn list_pins(&self, query: PinListQuery) -> Result<PinResults, NFTStorageError> {
+        let url = self.endpoint("pins");
+        let request = self.client.get(url).query(&query.to_query()?);
+        self.request(request).await
+    }
+
+    /// Get the status of the pin request `request_id`
+    ///
+    /// `request_id` must be a single path segment, `.`, `..` and the ids containing `/`, `\`, `?` or `#` return an
+    /// `InvalidInput` error without sending the request, like in `replace_pin` and `unpin`
+    /// ```
+    /// # #[cfg(feature = "test-util")]
+    /// # #[tokio::main]
+    /// # async fn main() -> anyhow::Result<()> {
+    /// use nft_storage::{test_util::MockTransport, NFTStorageError};
+    ///
+    /// let mock = MockTransport::new();
+    /// let nft_storage = mock.client();
+    /// for request_id in ["", " ", ".", "..", "../upload", "id/other", "id?limit=1", "id#fragment", "..\\upload"] {
+    ///     let result = nft_storage.get_pin(request_id).await;
+    ///     assert!(matches!(result, Err(NFTStorageError::InvalidInput(_))), "{}", request_id);
+    /// }
+    /// assert!(mock.requests().is_empty());
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "test-util"))]
+    /// # fn main() {}
+    /// ```
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(request_id = %request_id)))]
+    pub async fn get_pin(&self, request_id: &str) -> Result<PinStatusResponse, NFTStorageError> {
+        let url = self.pin_url(request_id)?;
+        self.request(self.client.get(url)).await
+    }
+
+    /// Replace the pin request `request_id` with a new pin, the old content is unpinned once the new one is pinned
+    ///
+    /// The returned status has a new request id
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(request_id = %request_id)))]
+    pub async fn replace_pin(
+        &self,
+        request_id: &str,
+        pin: PinObject,
+    ) -> Result<PinStatusResponse, NFTStorageError> {
+        let url = self.pin_url(request_id)?;
+        self.request(self.client.post(url).json(&pin)).await
+    }
+
+    /// Remove the pin request `request_id`
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(request_id = %request_id)))]
+    pub async fn unpin(&self, request_id: &str) -> Result<(), NFTStorageError> {
+        let url = self.pin_url(request_id)?;
+        let response = self.execute_with_retry(self.client.delete(url)).await?;
+        // the api responds with an empty body when the pin is removed
+        if response.status().is_success() {
+            return Ok(());
+        }
+        parse_response::<Value>(response).await.map(|_| ())
+    }
+
+    /// url of a single pin request
+    fn pin_url(&self, request_id: &str) -> Result<String, NFTStorageError> {
+        let request_id = request_id.trim();
+        // the id must stay a single path segment of the pins endpoint
+        let is_dot_segment = request_id == "." || request_id == "..";
+        if request_id.is_empty() || is_dot_segment || request_id.contains(['/', '\\', '?', '#']) {
+            return Err(NFTStorageError::InvalidInput(format!(
+                "invalid pin request id '{}'",
+                request_id
+            )));
+        }
+        Ok(self.endpoint(&format!("pins/{}", request_id)))
+    }
+}
@@ -0,0 +1,120 @@
+use crate::{
+    types::{Cid, NftValue, Timestamp, UploadFile},
+    NFTStorageError, NftStorage, MAX_LIST_LIMIT,
+};
+#[cfg(not(target_arch = "wasm32"))]
+use futures::future::BoxFuture;
+#[cfg(target_arch = "wasm32")]
+use futures::future::LocalBoxFuture;
+
+/// Future returned by the [`IpfsStorageProvider`] methods
+#[cfg(not(target_arch = "wasm32"))]
+pub type ProviderFuture<'a, T> = BoxFuture<'a, Result<T, NFTStorageError>>;
+/// Future returned by the [`IpfsStorageProvider`] methods, the browser futures aren't `Send`
+#[cfg(target_arch = "wasm32")]
+pub type ProviderFuture<'a, T> = LocalBoxFuture<'a, Result<T, NFTStorageError>>;
+
+/// content stored by a provider, the common part of the provider specific responses
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub struct StoredObject {
+    /// ipfs cid of the content
+    pub cid: Cid,
+    /// size of the content
+    pub size: u64,
+    /// date the content was stored
+    pub created: Timestamp,
+}
+
+impl StoredObject {
+    /// Create a stored object, used by the providers implemented outside of this crate
+    pub fn new(cid: Cid, size: u64, created: Timestamp) -> StoredObject {
+        StoredObject { cid, size, created }
+    }
+}
+
+impl From<NftValue> for StoredObject {
+    fn from(nft: NftValue) -> StoredObject {
+        StoredObject {
+            cid: nft.cid,
+            size: nft.size,
+            created: nft.created,
+        }
+    }
+}
+
+/// Operations supported by every ipfs storage provider
+///
+/// Application code written against this trait can switch provider changing only the client it creates
+/// ```no_run
+/// use nft_storage::{IpfsStorageProvider, NftStorage, StoredObject, types::UploadFile};
+/// use anyhow::Result;
+///
+/// async fn store(provider: &dyn IpfsStorageProvider) -> Result<StoredObject> {
+///     let file = UploadFile::new("hello.txt", b"hello".to_vec());
+///     Ok(provider.upload_file(file).await?)
+/// }
+///
+/// #[tokio::main]
+/// async fn main() -> Result<()> {
+///     let nft_storage = NftStorage::new("https://api.nft.storage", "token generated from nft storage");
+///     let stored = store(&nft_storage).await?;
+///     println!("{}", stored.cid);
+///
+///     Ok(())
+/// }
+/// ```
+pub trait IpfsStorageProvider: Send + Sync {
+    /// Upload a single file
+    fn upload_file(&self, file: UploadFile) -> ProviderFuture<'_, StoredObject>;
+
+    /// Upload the files in an ipfs directory
+    fn upload_directory(&self, files: Vec<UploadFile>) -> ProviderFuture<'_, StoredObject>;
+
+    /// List all the stored content
+    fn list(&self) -> ProviderFuture<'_, Vec<StoredObject>>;
+
+    /// Remove the content of `cid`
+    fn delete<'a>(&'a self, cid: &'a Cid) -> ProviderFuture<'a, ()>;
+
+    /// Get the content of `cid`, a `NotFound` error is returned if it isn't stored
+    fn status<'a>(&'a self, cid: &'a Cid) -> ProviderFuture<'a, StoredObject>;
+}
+
+impl IpfsStorageProvider for NftStorage {
+    fn upload_file(&self, file: UploadFile) -> ProviderFuture<'_, StoredObject> {
+        Box::pin(async move {
+            let response = NftStorage::upload_file(self, file.content).await?;
+            Ok(response.value.into())
+        })
+    }
+
+    fn upload_directory(&self, files: Vec<UploadFile>) -> ProviderFuture<'_, StoredObject> {
+        Box::pin(async move {
+            let response = NftStorage::upload_directory(self, files).await?;
+            Ok(response.value.into())
+        })
+    }
+
+    fn list(&self) -> ProviderFuture<'_, Vec<StoredObject>> {
+        Box::pin(async move {
+            let nfts = self.list_all_pages(MAX_LIST_LIMIT, false).await?;
+            Ok(nfts.into_iter().map(StoredObject::from).collect())
+        })
+    }
+
+    fn delete<'a>(&'a self, cid: &'a Cid) -> ProviderFuture<'a, ()> {
+        Box::pin(async move { self.delete_nft(cid).await.map(|_| ()) })
+    }
+
+    fn status<'a>(&'a self, cid: &'a Cid) -> ProviderFuture<'a, StoredObject> {
+        Box::pin(async move {
+            let response = self.check_nft(cid).await?;
+            Ok(StoredObject {
+                cid: response.value.cid,
+                size: response.value.pin.size,
+                created: response.value.pin.created,
+            })
+        })
+    }
+}
@@ -0,0 +1,197 @@
+use crate::{
+    retry::retry_after,
+    time::{sleep, Instant, SystemTime, UNIX_EPOCH},
+};
+use reqwest::Response;
+use std::{num::NonZeroU32, time::Duration};
+use tokio::sync::Mutex;
+
+/// max requests accepted by nft storage in `NFT_STORAGE_RATE_LIMIT_INTERVAL`
+pub const NFT_STORAGE_RATE_LIMIT_PERMITS: NonZeroU32 = match NonZeroU32::new(30) {
+    Some(permits) => permits,
+    None => panic!("the rate limit permits can't be zero"),
+};
+/// interval used by nft storage to rate limit the requests
+pub const NFT_STORAGE_RATE_LIMIT_INTERVAL: Duration = Duration::from_secs(10);
+
+/// headers with the requests allowed in the window, in order of preference
+const LIMIT_HEADERS: [&str; 2] = ["x-ratelimit-limit", "ratelimit-limit"];
+/// headers with the requests left in the window, in order of preference
+const REMAINING_HEADERS: [&str; 2] = ["x-ratelimit-remaining", "ratelimit-remaining"];
+/// headers with the reset of the window, in order of preference
+const RESET_HEADERS: [&str; 2] = ["x-ratelimit-reset", "ratelimit-reset"];
+/// the reset values bigger than this are unix timestamps, the smaller ones are seconds from the response
+const RESET_TIMESTAMP_THRESHOLD: u64 = 1_000_000_000;
+
+/// Rate limit state reported by the api in the headers of a response
+///
+/// It is read from the `x-ratelimit-limit`, `x-ratelimit-remaining`, `x-ratelimit-reset` headers (or their
+/// `ratelimit-*` version) and from `Retry-After`, see [`NftStorage::rate_limit_status`](crate::NftStorage::rate_limit_status)
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct RateLimitInfo {
+    /// requests allowed in the window
+    pub limit: Option<u32>,
+    /// requests left in the window
+    pub remaining: Option<u32>,
+    /// time from the response to the reset of the window
+    pub reset_after: Option<Duration>,
+    /// time to wait before retrying a rate limited request
+    pub retry_after: Option<Duration>,
+    /// when the response was received
+    received_at: Instant,
+}
+
+impl RateLimitInfo {
+    /// Read the rate limit headers of `response`, `None` if it doesn't contain any
+    pub(crate) fn from_response(response: &Response) -> Option<RateLimitInfo> {
+        let header = |names: &[&str]| {
+            names
+                .iter()
+                .find_map(|name| response.headers().get(*name))
+                .and_then(|value| value.to_str().ok())
+                // the draft standard headers can contain parameters after the value
+                .and_then(|value| value.split([';', ',']).next()?.trim().parse::<u64>().ok())
+        };
+        let reset_after = header(&RESET_HEADERS).map(|reset| {
+            if reset < RESET_TIMESTAMP_THRESHOLD {
+                return Duration::from_secs(reset);
+            }
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default();
+            Duration::from_secs(reset).saturating_sub(now)
+        });
+        let info = RateLimitInfo {
+            limit: header(&LIMIT_HEADERS).map(|limit| limit.min(u32::MAX as u64) as u32),
+            remaining: header(&REMAINING_HEADERS)
+                .map(|remaining| remaining.min(u32::MAX as u64) as u32),
+            reset_after,
+            retry_after: retry_after(response),
+            received_at: Instant::now(),
+        };
+        let empty = info.limit.is_none()
+            && info.remaining.is_none()
+            && info.reset_after.is_none()
+            && info.retry_after.is_none();
+        (!empty).then_some(info)
+    }
+
+    /// true if no requests are left in the window
+    pub fn is_exhausted(&self) -> bool {
+        self.remaining == Some(0)
+    }
+
+    /// Time left before the window is reset or the rate limited request can be retried, `None` if the response
+    /// didn't tell it
+    pub fn reset_in(&self) -> Option<Duration> {
+        let wait = match (self.reset_after, self.retry_after) {
+            (Some(reset_after), Some(retry_after)) => reset_after.max(retry_after),
+            (reset_after, retry_after) => reset_after.or(retry_after)?,
+        };
+        Some(wait.saturating_sub(self.received_at.elapsed()))
+    }
+}
+
+/// Token bucket rate limiter
+///
+/// It allows `permits` requests every `interval`, when no permits are left `acquire` waits until the bucket is refilled.
+/// The default limiter matches the nft storage limits of 30 requests every 10 seconds.
+///
+/// The limiter can be shared between tasks and clients with an `Arc`, they all draw from the same permits
+/// ```
+/// use nft_storage::RateLimiter;
+/// use std::{num::NonZeroU32, sync::Arc, time::Duration};
+/// use tokio::time::Instant;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let permits = NonZeroU32::new(30).unwrap();
+/// // the interval starts when the limiter is created
+/// let start = Instant::now();
+/// let rate_limiter = Arc::new(RateLimiter::new(permits, Duration::from_millis(500)));
+/// let tasks = (0..2).map(|_| {
+///     let rate_limiter = rate_limiter.clone();
+///     tokio::spawn(async move {
+///         for _ in 0..30 {
+///             rate_limiter.acquire().await;
+///         }
+///     })
+/// });
+/// for task in tasks.collect::<Vec<_>>() {
+///     task.await.unwrap();
+/// }
+/// // the first 30 permits use the whole budget, the others wait for the refill
+/// assert!(start.elapsed() >= Duration::from_millis(500));
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct RateLimiter {
+    /// permits available every interval
+    permits: u32,
+    /// interval after which the permits are refilled
+    interval: Duration,
+    /// current state of the bucket
+    state: Mutex<BucketState>,
+}
+
+/// permits left and time of the last refill
+#[derive(Debug)]
+struct BucketState {
+    /// permits left in the current interval
+    available: u32,
+    /// when the current interval started
+    refilled_at: Instant,
+}
+
+impl RateLimiter {
+    /// Create a rate limiter allowing `permits` requests every `interval`
+    ///
+    /// A limiter without permits would never let a request through, so zero isn't accepted
+    /// ```compile_fail
+    /// use nft_storage::RateLimiter;
+    /// use std::time::Duration;
+    ///
+    /// let rate_limiter = RateLimiter::new(0, Duration::from_secs(1));
+    /// ```
+    pub fn new(permits: NonZeroU32, interval: Duration) -> RateLimiter {
+        RateLimiter {
+            permits: permits.get(),
+            interval,
+            state: Mutex::new(BucketState {
+                available: permits.get(),
+                refilled_at: Instant::now(),
+            }),
+        }
+    }
+
+    /// Wait until a permit is available and consume it
+    pub async fn acquire(&self) {
+        loop {
+            let mut state = self.state.lock().await;
+            let now = Instant::now();
+            // refill the bucket if the interval is elapsed
+            if now.duration_since(state.refilled_at) >= self.interval {
+                state.available = self.permits;
+                state.refilled_at = now;
+            }
+            if state.available > 0 {
+                state.available -= 1;
+                return;
+            }
+            // wait for the next refill without holding the lock
+            let wait = self.interval - now.duration_since(state.refilled_at);
+            drop(state);
+            sleep(wait).await;
+        }
+    }
+}
+
+impl Default for RateLimiter {
+    fn default() -> RateLimiter {
+        RateLimiter::new(
+            NFT_STORAGE_RATE_LIMIT_PERMITS,
+            NFT_STORAGE_RATE_LIMIT_INTERVAL,
+        )
+    }
+}
@@ -0,0 +1,144 @@
+use crate::{parse_response, NFTStorageError, NftStorage};
+use reqwest::{multipart::Form, Method, StatusCode, Url};
+use serde_json::Value;
+
+/// Body of a request sent with [`NftStorage::raw_request`]
+#[derive(Debug)]
+pub enum RawBody {
+    /// json body sent as `application/json`
+    Json(Value),
+    /// raw bytes sent as they are
+    Bytes(Vec<u8>),
+    /// multipart form
+    Form(Form),
+}
+
+/// Requests to the endpoints not supported by the client
+impl NftStorage {
+    /// Send a request to an endpoint of the api using the client url, credentials, hooks and retry policy
+    ///
+    /// `path` is joined to the api url, an absolute url is accepted only if it points to the api, an `InvalidInput`
+    /// error is returned without sending the token to another host otherwise.
+    /// The error responses return an `ApiError` like the other methods, an empty successful body is returned as `null`
+    /// and a non json one as a string
+    /// ```no_run
+    /// use nft_storage::{NftStorage, RawBody};
+    /// use reqwest::Method;
+    /// use serde_json::json;
+    /// use anyhow::Result;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     // provide the url and as second argument the token generated from nft storage dashboard
+    ///     let nft_storage = NftStorage::new("https://api.nft.storage", "token generated from nft storage");
+    ///     let (status, body) = nft_storage
+    ///         .raw_request(Method::POST, "/user/did", Some(RawBody::Json(json!({ "did": "did:key:z6Mk..." }))))
+    ///         .await?;
+    ///     println!("{} {}", status, body);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// The url must have the scheme, host and port of the api and start with all the segments of its path
+    /// ```
+    /// # #[cfg(feature = "test-util")]
+    /// # #[tokio::main]
+    /// # async fn main() -> anyhow::Result<()> {
+    /// use nft_storage::{test_util::MockTransport, NFTStorageError, NftStorage};
+    /// use reqwest::Method;
+    /// use serde_json::json;
+    ///
+    /// let mock = MockTransport::new();
+    /// mock.push_json(200, json!({ "ok": true }));
+    /// mock.push_json(200, json!({ "ok": true }));
+    /// let nft_storage = NftStorage::builder("https://api.example/v1", "token")
+    ///     .transport(mock.clone())
+    ///     .build()?;
+    /// nft_storage.raw_request(Method::GET, "/user/account", None).await?;
+    /// nft_storage.raw_request(Method::GET, "https://api.example/v1/user/did", None).await?;
+    /// let urls = mock.requests().iter().map(|request| request.url.to_string()).collect::<Vec<_>>();
+    /// assert_eq!(urls, ["https://api.example/v1/user/account", "https://api.example/v1/user/did"]);
+    ///
+    /// for path in [
+    ///     "https://api.example/v10",
+    ///     "https://api.example/v1evil/user",
+    ///     "http://api.example/v1/user",
+    ///     "https://api.example:8443/v1/user",
+    ///     "https://evil.example/v1/user",
+    ///     "../admin",
+    /// ] {
+    ///     let result = nft_storage.raw_request(Method::GET, path, None).await;
+    ///     assert!(matches!(result, Err(NFTStorageError::InvalidInput(_))), "{}", path);
+    /// }
+    /// assert_eq!(mock.requests().len(), 2);
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "test-util"))]
+    /// # fn main() {}
+    /// ```
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, fields(method = %method, path = %path))
+    )]
+    pub async fn raw_request(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<RawBody>,
+    ) -> Result<(StatusCode, Value), NFTStorageError> {
+        let url = self.raw_url(path)?;
+        let mut request = self.client.request(method, url);
+        request = match body {
+            Some(RawBody::Json(json)) => request.json(&json),
+            Some(RawBody::Bytes(bytes)) => request.body(bytes),
+            Some(RawBody::Form(form)) => request.multipart(form),
+            None => request,
+        };
+        let response = self.execute_with_retry(request).await?;
+        let status = response.status();
+        if !status.is_success() {
+            // the error responses are converted like in the other methods
+            return parse_response::<Value>(response)
+                .await
+                .map(|body| (status, body));
+        }
+        let text = response.text().await?;
+        let body = if text.trim().is_empty() {
+            Value::Null
+        } else {
+            serde_json::from_str(&text).unwrap_or(Value::String(text))
+        };
+        Ok((status, body))
+    }
+
+    /// join `path` to the api url checking that the url doesn't point to another host or outside the api path
+    fn raw_url(&self, path: &str) -> Result<String, NFTStorageError> {
+        let api =
+            Url::parse(&self.url).map_err(|_| NFTStorageError::InvalidUrl(self.url.clone()))?;
+        let url = match Url::parse(path) {
+            Ok(absolute) => absolute,
+            // the dot segments of a relative path are resolved while parsing, so they are checked too
+            Err(_) => Url::parse(&self.endpoint(path)).map_err(|e| {
+                NFTStorageError::InvalidInput(format!("invalid path '{}', {}", path, e))
+            })?,
+        };
+        if url.origin() != api.origin() || !starts_with_segments(&url, &api) {
+            return Err(NFTStorageError::InvalidInput(format!(
+                "'{}' is not an url of the api {}, the token is sent only to the api",
+                path, self.url
+            )));
+        }
+        Ok(url.to_string())
+    }
+}
+
+/// check that the path of `url` starts with all the segments of the `base` path, so `/v10` isn't under `/v1`
+fn starts_with_segments(url: &Url, base: &Url) -> bool {
+    let mut segments = url.path_segments().into_iter().flatten();
+    base.path_segments()
+        .into_iter()
+        .flatten()
+        .filter(|segment| !segment.is_empty())
+        .all(|segment| segments.next() == Some(segment))
+}
@@ -0,0 +1,121 @@
+use reqwest::{header::RETRY_AFTER, Response, StatusCode};
+use std::time::Duration;
+
+/// Policy used to retry the requests rate limited or failed because of a server error
+///
+/// The delay between attempts grows exponentially starting from `base_delay` up to `max_delay`, when the response
+/// contains the `Retry-After` header its value is used instead, still capped at `max_delay`.
+///
+/// Requests with a streamed or multipart body can't be replayed, so they are sent only once.
+/// ```
+/// use nft_storage::RetryPolicy;
+/// use std::time::Duration;
+///
+/// let retry_policy = RetryPolicy {
+///     max_retries: 5,
+///     base_delay: Duration::from_secs(1),
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// max number of retries after the first attempt
+    pub max_retries: u32,
+    /// delay before the first retry
+    pub base_delay: Duration,
+    /// max delay between two attempts
+    pub max_delay: Duration,
+    /// status codes that trigger a retry
+    pub retry_on: Vec<u16>,
+}
+
+impl RetryPolicy {
+    /// Policy that never retries a request
+    pub fn none() -> RetryPolicy {
+        RetryPolicy {
+            max_retries: 0,
+            ..Default::default()
+        }
+    }
+
+    /// Check if a response with `status` should be retried
+    pub fn should_retry(&self, status: StatusCode) -> bool {
+        self.retry_on.contains(&status.as_u16())
+    }
+
+    /// Delay to wait before the retry number `attempt`, starting from 0
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt);
+        self.base_delay
+            .checked_mul(factor)
+            .unwrap_or(self.max_delay)
+            .min(self.max_delay)
+    }
+
+    /// Delay to wait before the retry number `attempt`, the one requested by the server with `retry_after` if any
+    ///
+    /// The requested delay is capped at `max_delay`, so a misbehaving server can't stall the client
+    /// ```
+    /// # #[cfg(feature = "test-util")]
+    /// # #[tokio::main]
+    /// # async fn main() -> anyhow::Result<()> {
+    /// use nft_storage::{test_util::{fixtures, MockTransport}, NftStorage, RetryPolicy};
+    /// use std::time::{Duration, Instant};
+    ///
+    /// let retry_policy = RetryPolicy {
+    ///     base_delay: Duration::from_millis(10),
+    ///     max_delay: Duration::from_millis(100),
+    ///     ..Default::default()
+    /// };
+    /// assert_eq!(retry_policy.retry_delay(0, None), Duration::from_millis(10));
+    /// assert_eq!(retry_policy.retry_delay(0, Some(Duration::from_millis(50))), Duration::from_millis(50));
+    /// assert_eq!(retry_policy.retry_delay(0, Some(Duration::from_secs(3600))), Duration::from_millis(100));
+    ///
+    /// // the server asks to wait one hour
+    /// let mock = MockTransport::new();
+    /// mock.push_response(503, vec![("retry-after".to_string(), "3600".to_string())], "{}");
+    /// mock.push_response(200, vec![], fixtures::UPLOAD_RESPONSE);
+    /// let nft_storage = NftStorage::builder("https://api.nft.storage", "token")
+    ///     .retry_policy(retry_policy)
+    ///     .transport(mock.clone())
+    ///     .build()?;
+    /// let start = Instant::now();
+    /// nft_storage.upload_file(b"hello".to_vec()).await?;
+    /// assert!(start.elapsed() < Duration::from_secs(10));
+    /// assert_eq!(mock.requests().len(), 2);
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "test-util"))]
+    /// # fn main() {}
+    /// ```
+    pub fn retry_delay(&self, attempt: u32, retry_after: Option<Duration>) -> Duration {
+        match retry_after {
+            Some(retry_after) => retry_after.min(self.max_delay),
+            None => self.delay(attempt),
+        }
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> RetryPolicy {
+        RetryPolicy {
+            max_retries: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            retry_on: vec![429, 500, 502, 503, 504],
+        }
+    }
+}
+
+/// get the delay requested by the server with the `Retry-After` header in seconds
+pub(crate) fn retry_after(response: &Response) -> Option<Duration> {
+    response
+        .headers()
+        .get(RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse::<u64>()
+        .ok()
+        .map(Duration::from_secs)
+}
@@ -0,0 +1,9 @@
+//! helpers for the locks shared between the clients and the tasks
+use std::sync::{Mutex, MutexGuard};
+
+/// lock `mutex` even if a thread panicked while holding it
+///
+/// the guarded values are replaced or updated with a single operation, so a panic can't leave them inconsistent
+pub(crate) fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
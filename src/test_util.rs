@@ -0,0 +1,198 @@
+//! Utilities to test the code using [`NftStorage`] without a network, enabled by the `test-util` feature
+//!
+//! [`MockTransport`] replays canned responses without any server, while [`FakeNftStorage`] runs a local server
+//! emulating the api with an in-memory store.
+mod fake;
+pub mod fixtures;
+
+use crate::{HttpTransport, NFTStorageError, NftStorage, RetryPolicy, TransportFuture};
+pub use fake::{FakeNftStorage, FakePart, FakeUpload, FAKE_TOKEN};
+use reqwest::{
+    header::{HeaderMap, CONTENT_TYPE},
+    Method, Request, Response, Url,
+};
+use serde_json::Value;
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+/// Request sent through a [`MockTransport`]
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    /// http method of the request
+    pub method: Method,
+    /// full url of the request, including the query
+    pub url: Url,
+    /// headers of the request, the `authorization` header contains the api token
+    pub headers: HeaderMap,
+    /// body of the request, it is `None` for streamed and multipart bodies
+    pub body: Option<Vec<u8>>,
+}
+
+impl RecordedRequest {
+    /// Parse the body of the request as json
+    pub fn json(&self) -> Option<Value> {
+        serde_json::from_slice(self.body.as_ref()?).ok()
+    }
+}
+
+/// Response replayed by a [`MockTransport`]
+#[derive(Debug, Clone)]
+struct MockResponse {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+/// requests received and responses left to replay
+#[derive(Debug, Default)]
+struct MockState {
+    responses: VecDeque<MockResponse>,
+    requests: Vec<RecordedRequest>,
+}
+
+/// Transport recording the requests and replaying canned responses in the order they are queued
+///
+/// When no response is left the request fails with `InvalidInput`. The clones share the same state, so the
+/// requests can be inspected after moving a clone in the client.
+/// ```
+/// use nft_storage::test_util::MockTransport;
+/// use serde_json::json;
+///
+/// # #[tokio::main]
+/// # async fn main() -> anyhow::Result<()> {
+/// let transport = MockTransport::new();
+/// transport.push_json(200, json!({
+///     "ok": true,
+///     "value": { "cid": "bafkreidvbhs33ighmljlvr7zbv2ywwzcmp5adtf4kqvlly67cy56bdtmve", "size": 11 }
+/// }));
+/// let nft_storage = transport.client();
+///
+/// let response = nft_storage.upload_file(b"hello world".to_vec()).await?;
+/// assert_eq!(response.value.size, 11);
+///
+/// let requests = transport.requests();
+/// assert_eq!(requests[0].url.path(), "/upload");
+/// assert_eq!(requests[0].body.as_deref(), Some(&b"hello world"[..]));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MockTransport {
+    state: Arc<Mutex<MockState>>,
+}
+
+impl MockTransport {
+    /// Create a transport with no responses queued
+    pub fn new() -> MockTransport {
+        MockTransport::default()
+    }
+
+    /// Queue a json response with the given `status`
+    pub fn push_json(&self, status: u16, body: Value) -> &MockTransport {
+        self.push_response(
+            status,
+            vec![(CONTENT_TYPE.to_string(), "application/json".to_string())],
+            body.to_string().into_bytes(),
+        )
+    }
+
+    /// Queue a response with the given `status`, `headers` and raw `body`
+    ///
+    /// Use it to test how the errors are handled, like a proxy returning an html page
+    /// ```
+    /// use nft_storage::{test_util::MockTransport, NFTStorageError};
+    /// use serde_json::json;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let transport = MockTransport::new();
+    /// transport
+    ///     .push_json(400, json!({ "ok": false, "error": { "message": "invalid file" } }))
+    ///     .push_response(200, vec![], "<html>bad gateway</html>")
+    ///     .push_json(200, json!({ "ok": true, "value": "bafkreidvbhs33ighmljlvr7zbv2ywwzcmp5adtf4kqvlly67cy56bdtmve" }));
+    /// let nft_storage = transport.client();
+    ///
+    /// let error = nft_storage.upload_file(b"hello".to_vec()).await.unwrap_err();
+    /// assert!(matches!(error, NFTStorageError::ApiError { status: 400, .. }));
+    /// let error = nft_storage.upload_file(b"hello".to_vec()).await.unwrap_err();
+    /// assert!(matches!(error, NFTStorageError::UnexpectedResponse { status: 200, .. }));
+    /// // a json body with another shape
+    /// let error = nft_storage.upload_file(b"hello".to_vec()).await.unwrap_err();
+    /// assert!(matches!(error, NFTStorageError::InvalidJson(_)));
+    /// # }
+    /// ```
+    pub fn push_response<B>(
+        &self,
+        status: u16,
+        headers: Vec<(String, String)>,
+        body: B,
+    ) -> &MockTransport
+    where
+        B: Into<Vec<u8>>,
+    {
+        self.lock().responses.push_back(MockResponse {
+            status,
+            headers,
+            body: body.into(),
+        });
+        self
+    }
+
+    /// Requests received so far, in the order they were sent
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.lock().requests.clone()
+    }
+
+    /// Create a client using this transport, without retries and rate limiter
+    pub fn client(&self) -> NftStorage {
+        NftStorage::builder("http://nft-storage.mock", "mock-token")
+            .gateway("http://gateway.mock/ipfs/")
+            .retry_policy(RetryPolicy::none())
+            .without_rate_limit()
+            .transport(self.clone())
+            .build()
+            .expect("the mock client is valid")
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, MockState> {
+        crate::sync::lock(&self.state)
+    }
+}
+
+impl HttpTransport for MockTransport {
+    fn execute(&self, request: Request) -> TransportFuture<'_> {
+        let recorded = RecordedRequest {
+            method: request.method().clone(),
+            url: request.url().clone(),
+            headers: request.headers().clone(),
+            body: request
+                .body()
+                .and_then(|body| body.as_bytes())
+                .map(|body| body.to_vec()),
+        };
+        let mut state = self.lock();
+        state.requests.push(recorded);
+        let response = state.responses.pop_front();
+        drop(state);
+
+        Box::pin(async move {
+            let mock = response.ok_or_else(|| {
+                NFTStorageError::InvalidInput(format!(
+                    "no mock response queued for {} {}",
+                    request.method(),
+                    request.url()
+                ))
+            })?;
+            let mut response = http::Response::builder().status(mock.status);
+            for (name, value) in mock.headers {
+                response = response.header(name, value);
+            }
+            let response = response
+                .body(mock.body)
+                .map_err(|e| NFTStorageError::InvalidInput(e.to_string()))?;
+            Ok(Response::from(response))
+        })
+    }
+}
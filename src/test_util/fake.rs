@@ -0,0 +1,552 @@
+use crate::{
+    cid::compute_cid_v1_raw, sync::lock, types::Cid, NFTStorageError, NftStorage, RetryPolicy,
+};
+use hyper::{
+    body::to_bytes,
+    header::{AUTHORIZATION, CONTENT_TYPE, RETRY_AFTER},
+    service::{make_service_fn, service_fn},
+    Body, Method, Request, Response, Server, StatusCode,
+};
+use serde_json::{json, Value};
+use std::{
+    collections::{HashMap, VecDeque},
+    convert::Infallible,
+    net::SocketAddr,
+    sync::{Arc, Mutex, MutexGuard},
+    time::Duration,
+};
+use tokio::sync::oneshot;
+
+/// api token accepted by the fake server
+pub const FAKE_TOKEN: &str = "fake-token";
+
+/// creation time of the first stored upload, the next ones are created one second later
+const FIRST_CREATED: u64 = 1638435153;
+
+/// Part of a multipart upload received by [`FakeNftStorage`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FakePart {
+    /// form field name, `file` for the directory uploads
+    pub name: String,
+    /// file name of the part
+    pub file_name: Option<String>,
+    /// content type of the part
+    pub content_type: Option<String>,
+    /// content of the part
+    pub content: Vec<u8>,
+}
+
+/// Upload received by [`FakeNftStorage`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FakeUpload {
+    /// cid assigned to the upload
+    pub cid: Cid,
+    /// content type of the request
+    pub content_type: Option<String>,
+    /// `X-Name` header of the request, percent-encoded as it was sent
+    pub name: Option<String>,
+    /// raw body of the request
+    pub body: Vec<u8>,
+    /// parts of a multipart upload, empty for the other uploads
+    pub parts: Vec<FakePart>,
+}
+
+/// nft stored by the fake server
+#[derive(Debug, Clone)]
+struct FakeEntry {
+    created: u64,
+    value: Value,
+}
+
+/// stored nfts, received uploads and failures to simulate
+#[derive(Debug, Default)]
+struct FakeState {
+    nfts: HashMap<String, FakeEntry>,
+    uploads: Vec<FakeUpload>,
+    failures: VecDeque<(StatusCode, Option<u64>)>,
+    requests: usize,
+    latency: Duration,
+    in_flight: usize,
+    max_in_flight: usize,
+}
+
+/// In-process server emulating the nft storage api
+///
+/// It serves `POST /upload`, `GET /`, `GET /check/{cid}`, `GET /{cid}` and `DELETE /{cid}` from an in-memory store
+/// keyed by the cid of the uploads, so code using [`NftStorage`] can be tested end to end without a network.
+/// `GET /ipfs/{cid}` works like a gateway returning the body of the raw uploads as it was received.
+///
+/// The cids are computed from the uploaded content: raw uploads get their real CIDv1, while multipart and CAR uploads
+/// get a deterministic cid of the parts that doesn't match the one computed by ipfs.
+/// The server stops when it is dropped.
+/// ```
+/// use nft_storage::{test_util::FakeNftStorage, types::UploadFile};
+///
+/// # #[tokio::main]
+/// # async fn main() -> anyhow::Result<()> {
+/// let fake = FakeNftStorage::start().await?;
+/// let nft_storage = fake.client();
+///
+/// let files = vec![UploadFile::new("image.png", b"not really a png".to_vec())];
+/// let stored = nft_storage.store_nft_directory(files, "My NFT", "My description").await?;
+/// assert!(nft_storage.check_nft(&stored.value.cid).await?.ok);
+///
+/// // the image directory and the metadata.json directory
+/// let uploads = fake.uploads();
+/// assert_eq!(uploads.len(), 2);
+/// assert_eq!(uploads[1].parts[0].file_name.as_deref(), Some("metadata.json"));
+///
+/// // simulate a server error, the request fails once the retries are exhausted
+/// fake.fail_next(500, 4);
+/// assert_eq!(nft_storage.get_nft(&stored.value.cid).await.unwrap_err().status(), Some(500));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct FakeNftStorage {
+    addr: SocketAddr,
+    state: Arc<Mutex<FakeState>>,
+    shutdown: Option<oneshot::Sender<()>>,
+}
+
+impl FakeNftStorage {
+    /// Start the server on a random local port, it must be called inside a tokio runtime
+    pub async fn start() -> Result<FakeNftStorage, NFTStorageError> {
+        let state = Arc::new(Mutex::new(FakeState::default()));
+        let service_state = state.clone();
+        let make_service = make_service_fn(move |_| {
+            let state = service_state.clone();
+            async move { Ok::<_, Infallible>(service_fn(move |request| handle(state.clone(), request))) }
+        });
+        let server = Server::try_bind(&SocketAddr::from(([127, 0, 0, 1], 0)))
+            .map_err(|e| NFTStorageError::InvalidInput(e.to_string()))?
+            .serve(make_service);
+        let addr = server.local_addr();
+        let (shutdown, stopped) = oneshot::channel::<()>();
+        tokio::spawn(server.with_graceful_shutdown(async {
+            stopped.await.ok();
+        }));
+
+        Ok(FakeNftStorage {
+            addr,
+            state,
+            shutdown: Some(shutdown),
+        })
+    }
+
+    /// Url of the fake api
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// Url of the fake gateway, see [`FakeNftStorage::client`]
+    pub fn gateway_url(&self) -> String {
+        format!("{}/ipfs/", self.url())
+    }
+
+    /// Create a client for the fake api downloading from the fake gateway, the retries don't wait and no rate limiter
+    /// is used
+    pub fn client(&self) -> NftStorage {
+        NftStorage::builder(self.url(), FAKE_TOKEN.to_string())
+            .gateway(self.gateway_url())
+            .retry_policy(RetryPolicy {
+                base_delay: Duration::ZERO,
+                ..Default::default()
+            })
+            .without_rate_limit()
+            .build()
+            .expect("the fake client is valid")
+    }
+
+    /// Answer the next `times` requests with `status`, a 429 also contains `Retry-After: 0`
+    ///
+    /// The failed requests are not handled, so nothing is stored or deleted
+    pub fn fail_next(&self, status: u16, times: usize) {
+        let status = StatusCode::from_u16(status).expect("valid status code");
+        let retry_after = (status == StatusCode::TOO_MANY_REQUESTS).then_some(0);
+        let mut state = self.lock();
+        state
+            .failures
+            .extend(std::iter::repeat_n((status, retry_after), times));
+    }
+
+    /// Wait `latency` before answering every request, used to test the timeouts and the concurrency
+    pub fn set_latency(&self, latency: Duration) {
+        self.lock().latency = latency;
+    }
+
+    /// Uploads received so far, in the order they were sent
+    pub fn uploads(&self) -> Vec<FakeUpload> {
+        self.lock().uploads.clone()
+    }
+
+    /// Cids of the stored nfts
+    pub fn stored(&self) -> Vec<Cid> {
+        self.lock()
+            .nfts
+            .keys()
+            .map(|cid| Cid(cid.clone()))
+            .collect()
+    }
+
+    /// Number of requests received, including the failed ones
+    pub fn request_count(&self) -> usize {
+        self.lock().requests
+    }
+
+    /// Max number of requests handled at the same time so far
+    pub fn max_in_flight(&self) -> usize {
+        self.lock().max_in_flight
+    }
+
+    fn lock(&self) -> MutexGuard<'_, FakeState> {
+        lock(&self.state)
+    }
+}
+
+impl Drop for FakeNftStorage {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            shutdown.send(()).ok();
+        }
+    }
+}
+
+/// counts a request as in flight until it is dropped
+struct InFlight(Arc<Mutex<FakeState>>);
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        lock(&self.0).in_flight -= 1;
+    }
+}
+
+/// route the request to the emulated endpoint
+async fn handle(
+    state: Arc<Mutex<FakeState>>,
+    request: Request<Body>,
+) -> Result<Response<Body>, Infallible> {
+    let (failure, latency) = {
+        let mut state = lock(&state);
+        state.requests += 1;
+        state.in_flight += 1;
+        state.max_in_flight = state.max_in_flight.max(state.in_flight);
+        (state.failures.pop_front(), state.latency)
+    };
+    let _in_flight = InFlight(state.clone());
+    if !latency.is_zero() {
+        tokio::time::sleep(latency).await;
+    }
+    if let Some((status, retry_after)) = failure {
+        let mut response = error(status, "simulated failure");
+        if let Some(retry_after) = retry_after {
+            response
+                .headers_mut()
+                .insert(RETRY_AFTER, retry_after.into());
+        }
+        return Ok(response);
+    }
+
+    // the gateway is public
+    if request.method() == Method::GET {
+        if let Some(cid) = request.uri().path().strip_prefix("/ipfs/") {
+            return Ok(gateway(&lock(&state), cid));
+        }
+    }
+
+    let authorized = request
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        == Some(&format!("Bearer {}", FAKE_TOKEN));
+    if !authorized {
+        return Ok(error(StatusCode::UNAUTHORIZED, "invalid api token"));
+    }
+
+    let method = request.method().clone();
+    let path = request.uri().path().to_string();
+    let query = request.uri().query().unwrap_or_default().to_string();
+    let content_type = request
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.to_string());
+    let name = request
+        .headers()
+        .get("X-Name")
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.to_string());
+    let body = match to_bytes(request.into_body()).await {
+        Ok(body) => body.to_vec(),
+        Err(e) => return Ok(error(StatusCode::BAD_REQUEST, &e.to_string())),
+    };
+
+    let mut state = lock(&state);
+    let response = match (method, path.as_str()) {
+        (Method::POST, "/upload") => upload(&mut state, content_type, name, body),
+        (Method::GET, "/") => list(&state, &query),
+        (Method::GET, path) if path.starts_with("/check/") => {
+            match state.nfts.get(&path["/check/".len()..]) {
+                Some(entry) => ok(json!({
+                    "cid": entry.value["cid"],
+                    "pin": entry.value["pin"],
+                    "deals": entry.value["deals"],
+                })),
+                None => error(StatusCode::NOT_FOUND, "NFT not found"),
+            }
+        }
+        (Method::GET, path) => match state.nfts.get(&path[1..]) {
+            Some(entry) => ok(entry.value.clone()),
+            None => error(StatusCode::NOT_FOUND, "NFT not found"),
+        },
+        (Method::DELETE, path) => match state.nfts.remove(&path[1..]) {
+            Some(_) => json_response(StatusCode::OK, json!({ "ok": true })),
+            None => error(StatusCode::NOT_FOUND, "NFT not found"),
+        },
+        _ => error(StatusCode::NOT_FOUND, "endpoint not found"),
+    };
+    Ok(response)
+}
+
+/// store the upload, uploading the same content again returns the existing nft
+fn upload(
+    state: &mut FakeState,
+    content_type: Option<String>,
+    name: Option<String>,
+    body: Vec<u8>,
+) -> Response<Body> {
+    let boundary = content_type.as_deref().and_then(multipart_boundary);
+    let (cid, parts) = match boundary {
+        Some(boundary) => {
+            let parts = match parse_multipart(&body, &boundary) {
+                Some(parts) if !parts.is_empty() => parts,
+                _ => return error(StatusCode::BAD_REQUEST, "invalid multipart body"),
+            };
+            // hash the names and the contents so the cid doesn't depend on the boundary
+            let mut content = Vec::new();
+            for part in &parts {
+                content.extend(part.file_name.as_deref().unwrap_or_default().as_bytes());
+                content.push(0);
+                content.extend(&part.content);
+            }
+            (compute_cid_v1_raw(&content), parts)
+        }
+        None => (compute_cid_v1_raw(&body), Vec::new()),
+    };
+
+    let created = FIRST_CREATED + state.uploads.len() as u64;
+    let (file_type, size, files) = if parts.is_empty() {
+        let file_type = content_type
+            .clone()
+            .unwrap_or_else(|| "application/octet-stream".to_string());
+        (file_type, body.len(), Vec::new())
+    } else {
+        let files = parts
+            .iter()
+            .map(|part| {
+                json!({
+                    "name": part.file_name.clone().unwrap_or_default(),
+                    "type": part.content_type.clone().unwrap_or_default(),
+                })
+            })
+            .collect::<Vec<_>>();
+        let size = parts.iter().map(|part| part.content.len()).sum();
+        ("directory".to_string(), size, files)
+    };
+    let entry = state
+        .nfts
+        .entry(cid.to_string())
+        .or_insert_with(|| FakeEntry {
+            created,
+            value: json!({
+                "cid": cid,
+                "size": size,
+                "created": timestamp(created),
+                "type": file_type,
+                "scope": "session",
+                "pin": {
+                    "cid": cid,
+                    "created": timestamp(created),
+                    "size": size,
+                    "status": "pinned",
+                },
+                "files": files,
+                "deals": [],
+            }),
+        })
+        .value
+        .clone();
+    state.uploads.push(FakeUpload {
+        cid,
+        content_type,
+        name,
+        body,
+        parts,
+    });
+    ok(entry)
+}
+
+/// serve the body of the last raw upload of `cid`, the directories aren't supported
+fn gateway(state: &FakeState, cid: &str) -> Response<Body> {
+    let upload = state
+        .uploads
+        .iter()
+        .rev()
+        .find(|upload| upload.cid.as_str() == cid && upload.parts.is_empty());
+    match upload {
+        Some(upload) => {
+            let mut response = Response::new(Body::from(upload.body.clone()));
+            let content_type = upload
+                .content_type
+                .as_deref()
+                .and_then(|value| value.parse().ok());
+            if let Some(content_type) = content_type {
+                response.headers_mut().insert(CONTENT_TYPE, content_type);
+            }
+            response
+        }
+        None => {
+            let mut response = Response::new(Body::from("not found"));
+            *response.status_mut() = StatusCode::NOT_FOUND;
+            response
+        }
+    }
+}
+
+/// list the nfts from the newest, supporting the `before` and `limit` parameters
+fn list(state: &FakeState, query: &str) -> Response<Body> {
+    let mut before = None;
+    let mut limit = 10;
+    for (key, value) in query.split('&').filter_map(|pair| pair.split_once('=')) {
+        match key {
+            // the colons are the only encoded characters of the timestamps
+            "before" => before = Some(value.replace("%3A", ":")),
+            "limit" => match value.parse::<usize>() {
+                Ok(value) if (1..=100).contains(&value) => limit = value,
+                _ => return error(StatusCode::BAD_REQUEST, "invalid limit"),
+            },
+            _ => {}
+        }
+    }
+    let mut entries = state
+        .nfts
+        .values()
+        .filter(|entry| match &before {
+            Some(before) => timestamp(entry.created).as_str() < before.as_str(),
+            None => true,
+        })
+        .collect::<Vec<_>>();
+    entries.sort_by_key(|entry| std::cmp::Reverse(entry.created));
+    let values = entries
+        .into_iter()
+        .take(limit)
+        .map(|entry| entry.value.clone())
+        .collect::<Vec<_>>();
+    ok(Value::Array(values))
+}
+
+fn ok(value: Value) -> Response<Body> {
+    json_response(StatusCode::OK, json!({ "ok": true, "value": value }))
+}
+
+/// error with the same body of the nft storage api
+fn error(status: StatusCode, message: &str) -> Response<Body> {
+    json_response(
+        status,
+        json!({
+            "ok": false,
+            "error": { "name": "HTTPError", "message": message },
+        }),
+    )
+}
+
+fn json_response(status: StatusCode, body: Value) -> Response<Body> {
+    let mut response = Response::new(Body::from(body.to_string()));
+    *response.status_mut() = status;
+    response.headers_mut().insert(
+        CONTENT_TYPE,
+        "application/json".parse().expect("valid header"),
+    );
+    response
+}
+
+/// rfc3339 timestamp of the unix time `seconds`, formatted like the client sends `before` so they can be compared
+fn timestamp(seconds: u64) -> String {
+    let days = seconds / 86400;
+    let time = seconds % 86400;
+    // convert the days to a civil date, see http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719468;
+    let era = z / 146097;
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.000Z",
+        year,
+        month,
+        day,
+        time / 3600,
+        time % 3600 / 60,
+        time % 60
+    )
+}
+
+fn multipart_boundary(content_type: &str) -> Option<String> {
+    if !content_type.starts_with("multipart/form-data") {
+        return None;
+    }
+    content_type
+        .split(';')
+        .find_map(|param| param.trim().strip_prefix("boundary="))
+        .map(|boundary| boundary.trim_matches('"').to_string())
+}
+
+/// split a multipart body in its parts
+fn parse_multipart(body: &[u8], boundary: &str) -> Option<Vec<FakePart>> {
+    let delimiter = format!("--{}", boundary).into_bytes();
+    let mut rest = &body[find(body, &delimiter)? + delimiter.len()..];
+    let mut parts = Vec::new();
+    // the last delimiter is followed by `--`
+    while !rest.starts_with(b"--") {
+        let section_end = find(rest, &delimiter)?;
+        let section = rest[..section_end].strip_prefix(b"\r\n")?;
+        let section = section.strip_suffix(b"\r\n").unwrap_or(section);
+        let headers_end = find(section, b"\r\n\r\n")?;
+        let headers = std::str::from_utf8(&section[..headers_end]).ok()?;
+
+        let mut part = FakePart {
+            name: String::new(),
+            file_name: None,
+            content_type: None,
+            content: section[headers_end + 4..].to_vec(),
+        };
+        for header in headers.split("\r\n") {
+            let (name, value) = header.split_once(':')?;
+            if name.eq_ignore_ascii_case("content-type") {
+                part.content_type = Some(value.trim().to_string());
+            } else if name.eq_ignore_ascii_case("content-disposition") {
+                for param in value.split(';').filter_map(|param| param.split_once('=')) {
+                    let param_value = param.1.trim().trim_matches('"').to_string();
+                    match param.0.trim() {
+                        "name" => part.name = param_value,
+                        "filename" => part.file_name = Some(param_value),
+                        _ => {}
+                    }
+                }
+            }
+        }
+        parts.push(part);
+        rest = &rest[section_end + delimiter.len()..];
+    }
+    Some(parts)
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
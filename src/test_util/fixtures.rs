@@ -0,0 +1,166 @@
+//! Responses captured from the nft storage api, useful to test the deserialization or to replay with a `MockTransport`
+//! ```
+//! use nft_storage::{test_util::fixtures, types::*};
+//!
+//! let list: ListNftResponse = serde_json::from_str(fixtures::LIST_RESPONSE).unwrap();
+//! assert_eq!(list.value[0].pin.status, "pinned");
+//! assert_eq!(list.value[0].deals[0].chain_deal_id, 2329071);
+//!
+//! let check: CheckCidNftResponse = serde_json::from_str(fixtures::CHECK_RESPONSE).unwrap();
+//! assert_eq!(check.value.pin.size, 132614);
+//! assert_eq!(check.value.deals[0].miner, "f01278");
+//! assert_eq!(check.value.deals[1].status, "queued");
+//!
+//! let upload: StoreNftResponse = serde_json::from_str(fixtures::UPLOAD_RESPONSE).unwrap();
+//! assert_eq!(upload.value.files[0].name, "image.png");
+//! ```
+
+/// response of `GET /` with a directory upload stored in a filecoin deal
+pub const LIST_RESPONSE: &str = r#"{
+  "ok": true,
+  "value": [
+    {
+      "cid": "bafybeiflbavrum45ekg5qxbecvpn5bfcvuk45txcmgsabfebtkv44cn6vq",
+      "created": "2021-12-02T08:52:33.461+00:00",
+      "type": "directory",
+      "scope": "session",
+      "files": [
+        { "name": "image.png", "type": "image/png" },
+        { "name": "metadata.json", "type": "application/json" }
+      ],
+      "size": 1147081,
+      "pin": {
+        "cid": "bafybeiflbavrum45ekg5qxbecvpn5bfcvuk45txcmgsabfebtkv44cn6vq",
+        "created": "2021-12-02T08:52:33.461+00:00",
+        "size": 1147081,
+        "status": "pinned"
+      },
+      "deals": [
+        {
+          "batchRootCid": "bafybeiek3xkeixdjzg2mpxbjkcqrvrffplalqy6jpqg3pcrgcbzqfkzeea",
+          "lastChanged": "2021-12-04T13:48:51.148+00:00",
+          "miner": "f01278",
+          "network": "mainnet",
+          "pieceCid": "baga6ea4seaqao7s73y24kcutaosvacpdjgfe5pw76ooefnyqw4ynr3d2y6x2mpq",
+          "status": "active",
+          "statusText": "containing sector active as of 2021-12-04 13:48:30 at epoch 1366553",
+          "dealActivation": "2021-12-05T18:29:00+00:00",
+          "dealExpiration": "2023-04-29T18:29:00+00:00",
+          "chainDealID": 2329071,
+          "datamodelSelector": "Links/4/Hash/Links/36/Hash/Links/0/Hash"
+        }
+      ]
+    }
+  ]
+}"#;
+
+/// response of `GET /check/{cid}` with an active and a queued deal
+pub const CHECK_RESPONSE: &str = r#"{
+  "ok": true,
+  "value": {
+    "cid": "bafkreidivzimqfqtoqxkrpge6bjyhlvxqs3rhe73owtmdulaxr5do5in7u",
+    "pin": {
+      "cid": "bafkreidivzimqfqtoqxkrpge6bjyhlvxqs3rhe73owtmdulaxr5do5in7u",
+      "created": "2021-03-12T17:03:07.787+00:00",
+      "size": 132614,
+      "status": "pinned"
+    },
+    "deals": [
+      {
+        "batchRootCid": "bafybeiek3xkeixdjzg2mpxbjkcqrvrffplalqy6jpqg3pcrgcbzqfkzeea",
+        "lastChanged": "2021-03-18T11:51:03.584+00:00",
+        "miner": "f01278",
+        "network": "mainnet",
+        "pieceCid": "baga6ea4seaqao7s73y24kcutaosvacpdjgfe5pw76ooefnyqw4ynr3d2y6x2mpq",
+        "status": "active",
+        "statusText": "containing sector active as of 2021-03-18 11:46:50 at epoch 610353",
+        "dealActivation": "2021-03-20T09:51:00+00:00",
+        "dealExpiration": "2022-08-13T09:51:00+00:00",
+        "chainDealID": 1325424,
+        "datamodelSelector": "Links/100/Hash/Links/0/Hash/Links/0/Hash"
+      },
+      {
+        "batchRootCid": "bafybeiek3xkeixdjzg2mpxbjkcqrvrffplalqy6jpqg3pcrgcbzqfkzeea",
+        "lastChanged": "2021-03-18T11:51:03.584+00:00",
+        "miner": "f0678914",
+        "network": "mainnet",
+        "pieceCid": "baga6ea4seaqao7s73y24kcutaosvacpdjgfe5pw76ooefnyqw4ynr3d2y6x2mpq",
+        "status": "queued",
+        "statusText": "",
+        "datamodelSelector": "Links/100/Hash/Links/0/Hash/Links/0/Hash"
+      }
+    ]
+  }
+}"#;
+
+/// response of `POST /upload` for a directory upload
+pub const UPLOAD_RESPONSE: &str = r#"{
+  "ok": true,
+  "value": {
+    "cid": "bafybeiflbavrum45ekg5qxbecvpn5bfcvuk45txcmgsabfebtkv44cn6vq",
+    "created": "2021-12-02T08:52:33.461+00:00",
+    "type": "directory",
+    "scope": "session",
+    "files": [{ "name": "image.png", "type": "image/png" }],
+    "size": 1147081,
+    "pin": {
+      "cid": "bafybeiflbavrum45ekg5qxbecvpn5bfcvuk45txcmgsabfebtkv44cn6vq",
+      "created": "2021-12-02T08:52:33.461+00:00",
+      "size": 1147081,
+      "status": "queued"
+    },
+    "deals": []
+  }
+}"#;
+
+/// response of `POST /upload` for a single file sent as the raw body
+pub const FILE_UPLOAD_RESPONSE: &str = r#"{
+  "ok": true,
+  "value": {
+    "cid": "bafkreidivzimqfqtoqxkrpge6bjyhlvxqs3rhe73owtmdulaxr5do5in7u",
+    "created": "2021-03-12T17:03:07.787+00:00",
+    "type": "image/jpeg",
+    "scope": "session",
+    "files": [],
+    "size": 132614,
+    "pin": {
+      "cid": "bafkreidivzimqfqtoqxkrpge6bjyhlvxqs3rhe73owtmdulaxr5do5in7u",
+      "created": "2021-03-12T17:03:07.787+00:00",
+      "size": 132614,
+      "status": "queued"
+    },
+    "deals": []
+  }
+}"#;
+
+/// response of `POST /upload` for a directory containing only the metadata.json of an nft
+pub const METADATA_UPLOAD_RESPONSE: &str = r#"{
+  "ok": true,
+  "value": {
+    "cid": "bafybeihflij24dndd6qo3aacbbysuzuygis7yurvrzxp3uk7bk5kfvfsfg",
+    "created": "2021-12-02T08:52:34.112+00:00",
+    "type": "directory",
+    "scope": "session",
+    "files": [{ "name": "metadata.json", "type": "application/json" }],
+    "size": 342,
+    "pin": {
+      "cid": "bafybeihflij24dndd6qo3aacbbysuzuygis7yurvrzxp3uk7bk5kfvfsfg",
+      "created": "2021-12-02T08:52:34.112+00:00",
+      "size": 342,
+      "status": "queued"
+    },
+    "deals": []
+  }
+}"#;
+
+/// response of `POST /upload` while the api is in read only maintenance mode, the reads keep working
+pub const MAINTENANCE_RESPONSE: &str = r#"{
+  "ok": false,
+  "error": { "name": "MAINTENANCE", "code": "ERROR_MAINTENANCE", "message": "API undergoing maintenance, please try again later" }
+}"#;
+
+/// response of a request with an invalid api token
+pub const UNAUTHORIZED_RESPONSE: &str = r#"{
+  "ok": false,
+  "error": { "name": "HTTPError", "message": "API Key is missing, make sure the `Authorization` header has a value in the following format `Bearer {token}`." }
+}"#;
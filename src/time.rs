@@ -0,0 +1,30 @@
+//! timers working both on the native targets and in the browser, where the tokio timers aren't available
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) use std::time::{SystemTime, UNIX_EPOCH};
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) use tokio::time::{sleep, timeout, Instant};
+#[cfg(target_arch = "wasm32")]
+pub(crate) use web_time::{Instant, SystemTime, UNIX_EPOCH};
+
+#[cfg(target_arch = "wasm32")]
+use futures::future::{select, Either};
+#[cfg(target_arch = "wasm32")]
+use std::{future::Future, time::Duration};
+
+/// wait for `duration`
+#[cfg(target_arch = "wasm32")]
+pub(crate) async fn sleep(duration: Duration) {
+    gloo_timers::future::sleep(duration).await
+}
+
+/// wait for `future` at most `duration`, an error is returned if the time is elapsed
+#[cfg(target_arch = "wasm32")]
+pub(crate) async fn timeout<F>(duration: Duration, future: F) -> Result<F::Output, ()>
+where
+    F: Future,
+{
+    match select(Box::pin(future), Box::pin(sleep(duration))).await {
+        Either::Left((output, _)) => Ok(output),
+        Either::Right(_) => Err(()),
+    }
+}
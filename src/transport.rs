@@ -0,0 +1,64 @@
+use crate::NFTStorageError;
+#[cfg(not(target_arch = "wasm32"))]
+use futures::future::BoxFuture;
+#[cfg(target_arch = "wasm32")]
+use futures::future::LocalBoxFuture;
+use reqwest::{Client, Request, Response};
+use std::fmt::Debug;
+
+/// Future returned by [`HttpTransport::execute`]
+#[cfg(not(target_arch = "wasm32"))]
+pub type TransportFuture<'a> = BoxFuture<'a, Result<Response, NFTStorageError>>;
+/// Future returned by [`HttpTransport::execute`], the browser futures aren't `Send`
+#[cfg(target_arch = "wasm32")]
+pub type TransportFuture<'a> = LocalBoxFuture<'a, Result<Response, NFTStorageError>>;
+
+/// Transport used to send the requests built by [`NftStorage`](crate::NftStorage)
+///
+/// Every request of the client, api calls and gateway downloads, is sent through the transport, so it can be
+/// replaced to test the code using the client without a network, or wrapped to add logging or other middleware.
+/// The retries and the rate limiter are applied by the client before calling the transport.
+/// ```
+/// use nft_storage::{HttpTransport, NftStorage, ReqwestTransport, TransportFuture};
+/// use reqwest::Request;
+///
+/// /// print every request before sending it
+/// #[derive(Debug)]
+/// struct LoggingTransport(ReqwestTransport);
+///
+/// impl HttpTransport for LoggingTransport {
+///     fn execute(&self, request: Request) -> TransportFuture<'_> {
+///         println!("{} {}", request.method(), request.url());
+///         self.0.execute(request)
+///     }
+/// }
+///
+/// let nft_storage = NftStorage::builder("https://api.nft.storage", "token generated from nft storage")
+///     .transport(LoggingTransport(ReqwestTransport::default()))
+///     .build()
+///     .unwrap();
+/// ```
+pub trait HttpTransport: Debug + Send + Sync {
+    /// Send the `request` returning its response
+    fn execute(&self, request: Request) -> TransportFuture<'_>;
+}
+
+/// Default transport sending the requests with a `reqwest::Client`
+#[derive(Debug, Clone, Default)]
+pub struct ReqwestTransport {
+    /// reqwest client instance
+    client: Client,
+}
+
+impl ReqwestTransport {
+    /// Create a transport sending the requests with `client`
+    pub fn new(client: Client) -> ReqwestTransport {
+        ReqwestTransport { client }
+    }
+}
+
+impl HttpTransport for ReqwestTransport {
+    fn execute(&self, request: Request) -> TransportFuture<'_> {
+        Box::pin(async move { Ok(self.client.execute(request).await?) })
+    }
+}
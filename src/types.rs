@@ -1,8 +1,197 @@
+use crate::{pins::PinningStatus, NFTStorageError};
+#[cfg(feature = "chrono")]
+use chrono::{DateTime, NaiveDateTime, SecondsFormat, Utc};
+use futures::FutureExt;
+use reqwest::multipart::Part;
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::{collections::HashMap, fmt, future::Future, time::Duration};
+
+/// timestamp returned by the api, parsed from rfc3339 when the `chrono` feature is enabled
+#[cfg(feature = "chrono")]
+pub type Timestamp = DateTime<Utc>;
+
+/// timestamp returned by the api, kept as the raw rfc3339 string when the `chrono` feature is disabled
+#[cfg(not(feature = "chrono"))]
+pub type Timestamp = String;
+
+/// format the timestamp as rfc3339 like the api expects it
+#[cfg(feature = "chrono")]
+pub(crate) fn to_rfc3339(timestamp: &Timestamp) -> String {
+    timestamp.to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+/// format the timestamp as rfc3339 like the api expects it
+#[cfg(not(feature = "chrono"))]
+pub(crate) fn to_rfc3339(timestamp: &Timestamp) -> String {
+    timestamp.clone()
+}
+
+/// tolerant deserializer for the api timestamps
+///
+/// It accepts rfc3339 with or without fractional seconds and the timestamps without offset which are read as utc,
+/// `null` and empty strings are read as the default timestamp
+#[cfg(feature = "chrono")]
+pub(crate) fn deserialize_timestamp<'de, D>(deserializer: D) -> Result<Timestamp, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let value = Option::<String>::deserialize(deserializer)?.unwrap_or_default();
+    let value = value.trim();
+    if value.is_empty() {
+        return Ok(Timestamp::default());
+    }
+    if let Ok(timestamp) = DateTime::parse_from_rfc3339(value) {
+        return Ok(timestamp.with_timezone(&Utc));
+    }
+    NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%.f")
+        .map(|timestamp| timestamp.and_utc())
+        .map_err(|e| serde::de::Error::custom(format!("invalid timestamp '{}', {}", value, e)))
+}
+
+/// ipfs content identifier
+///
+/// Creating it from a string checks that it is a valid CIDv0 (`Qm...`) or CIDv1 (`baf...`), while the cids of the
+/// api responses are deserialized as they are so a malformed one doesn't break the whole response
+/// ```
+/// use nft_storage::types::Cid;
+///
+/// let cid = Cid::new("QmbWqxBEKC3P8tqsKc98xmWNzrzDtRLMiMPL8wBuTGsMnR").unwrap();
+/// assert_eq!(cid.version(), Some(0));
+/// assert_eq!(cid.to_v1().unwrap().version(), Some(1));
+/// // a gateway url is not a cid
+/// assert!(Cid::new("https://ipfs.io/ipfs/QmbWqxBEKC3P8tqsKc98xmWNzrzDtRLMiMPL8wBuTGsMnR").is_err());
+/// assert!("".parse::<Cid>().is_err());
+/// assert!("bafy".parse::<Cid>().is_err());
+///
+/// let v1 = "bafybeiduiecxoeiqs3gyc6r7v3lymmhserldnpw62qjnhmqsulqjxjmtzi";
+/// let cid = v1.parse::<Cid>().unwrap();
+/// assert_eq!(cid.version(), Some(1));
+/// assert_eq!(cid.to_string(), v1);
+/// assert_eq!(cid.to_v1().unwrap(), cid);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize, Default)]
+#[serde(transparent)]
+pub struct Cid(pub(crate) String);
+
+impl Cid {
+    /// Create a cid checking that it is valid
+    pub fn new<S>(cid: S) -> Result<Cid, NFTStorageError>
+    where
+        S: Into<String>,
+    {
+        let cid = cid.into();
+        // urls and paths can be decoded by some multibase encodings, so only plain cids are accepted
+        if !cid.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return Err(NFTStorageError::InvalidCid(format!(
+                "'{}', only alphanumeric characters are allowed",
+                cid
+            )));
+        }
+        match ::cid::Cid::try_from(cid.as_str()) {
+            Ok(_) => Ok(Cid(cid)),
+            Err(e) => Err(NFTStorageError::InvalidCid(format!("'{}', {}", cid, e))),
+        }
+    }
+
+    /// the cid as a string
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// cid version, 0 or 1, `None` if the api returned a malformed cid
+    pub fn version(&self) -> Option<u64> {
+        ::cid::Cid::try_from(self.0.as_str())
+            .ok()
+            .map(|cid| cid.version().into())
+    }
+
+    /// Convert the cid to version 1, the CIDv1 are returned as they are
+    pub fn to_v1(&self) -> Result<Cid, NFTStorageError> {
+        ::cid::Cid::try_from(self.0.as_str())
+            .and_then(|cid| cid.into_v1())
+            .map(|cid| Cid(cid.to_string()))
+            .map_err(|e| NFTStorageError::InvalidCid(format!("'{}', {}", self.0, e)))
+    }
+
+    /// Convert the cid to version 1 encoded in lowercase base32, the form required by the subdomain gateways
+    ///
+    /// The CIDv0 are converted and the CIDv1 encoded with another multibase, like base58btc (`z...`), are encoded
+    /// again, the cids returned by the api are already in this form
+    /// ```
+    /// use nft_storage::types::Cid;
+    ///
+    /// let base32 = "bafybeiduiecxoeiqs3gyc6r7v3lymmhserldnpw62qjnhmqsulqjxjmtzi";
+    /// let v0 = Cid::new("QmWATWQ7fVPP2EFGu71UkfnqhYXDYH566qy47CnJDgvs8u").unwrap();
+    /// let base58 = Cid::new("zdj7WdFhsqBWxrytwZCdqy6d3dQKkifFpVDyANan1Ay38VmJq").unwrap();
+    ///
+    /// assert_eq!(v0.to_v1_base32().unwrap().as_str(), base32);
+    /// assert_eq!(base58.to_v1_base32().unwrap().as_str(), base32);
+    /// assert_eq!(Cid::new(base32).unwrap().to_v1_base32().unwrap().as_str(), base32);
+    /// ```
+    pub fn to_v1_base32(&self) -> Result<Cid, NFTStorageError> {
+        ::cid::Cid::try_from(self.0.as_str())
+            .and_then(|cid| cid.into_v1())
+            .and_then(|cid| cid.to_string_of_base(::cid::multibase::Base::Base32Lower))
+            .map(Cid)
+            .map_err(|e| NFTStorageError::InvalidCid(format!("'{}', {}", self.0, e)))
+    }
+}
+
+impl fmt::Display for Cid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl AsRef<str> for Cid {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl TryFrom<&str> for Cid {
+    type Error = NFTStorageError;
+
+    fn try_from(cid: &str) -> Result<Cid, NFTStorageError> {
+        Cid::new(cid)
+    }
+}
+
+impl std::str::FromStr for Cid {
+    type Err = NFTStorageError;
+
+    fn from_str(cid: &str) -> Result<Cid, NFTStorageError> {
+        Cid::new(cid)
+    }
+}
+
+impl TryFrom<String> for Cid {
+    type Error = NFTStorageError;
+
+    fn try_from(cid: String) -> Result<Cid, NFTStorageError> {
+        Cid::new(cid)
+    }
+}
+
+impl TryFrom<&String> for Cid {
+    type Error = NFTStorageError;
+
+    fn try_from(cid: &String) -> Result<Cid, NFTStorageError> {
+        Cid::new(cid.as_str())
+    }
+}
+
+impl From<&Cid> for Cid {
+    fn from(cid: &Cid) -> Cid {
+        cid.clone()
+    }
+}
 
 /// list nft response from nft storage api
-#[derive(Debug, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
 #[serde(default)]
+#[non_exhaustive]
 pub struct ListNftResponse {
     /// status of the request
     pub ok: bool,
@@ -10,17 +199,979 @@ pub struct ListNftResponse {
     pub value: Vec<NftValue>,
 }
 
+/// page of nfts listed by `list_lenient`, the entries that can't be deserialized are reported instead of failing
+/// the whole page
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct LenientListResponse {
+    /// listed nfts, without the malformed entries
+    pub response: ListNftResponse,
+    /// index in the page of every malformed entry with its deserialization error
+    pub failures: Vec<(usize, serde_json::Error)>,
+}
+
+/// list response with the nfts kept as json, they are converted one at a time by `into_lenient`
+#[derive(Debug, Deserialize, Default)]
+#[serde(default)]
+pub(crate) struct RawListResponse {
+    /// status of the request
+    ok: bool,
+    /// nfts as returned by the api
+    value: Vec<Value>,
+}
+
+impl RawListResponse {
+    /// Convert every entry separately, the failures are collected with their index in the page
+    pub(crate) fn into_lenient(self) -> LenientListResponse {
+        let mut value = Vec::with_capacity(self.value.len());
+        let mut failures = Vec::new();
+        for (index, nft) in self.value.into_iter().enumerate() {
+            match serde_json::from_value::<NftValue>(nft) {
+                Ok(nft) => value.push(nft),
+                Err(e) => failures.push((index, e)),
+            }
+        }
+        LenientListResponse {
+            response: ListNftResponse { ok: self.ok, value },
+            failures,
+        }
+    }
+}
+
+/// error object of a failed api request, the api responds with `{"ok": false, "error": {"name", "message"}}`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(from = "RawApiErrorBody")]
+#[non_exhaustive]
+pub struct ApiErrorBody {
+    /// status of the request, always false for a failure
+    pub ok: bool,
+    /// name of the error, like `HTTPError`
+    pub name: String,
+    /// description of the error
+    pub message: String,
+}
+
+/// failure body as returned by the api, flattened into `ApiErrorBody`
+#[derive(Deserialize)]
+struct RawApiErrorBody {
+    #[serde(default)]
+    ok: bool,
+    error: RawApiError,
+}
+
+/// error object nested in the failure body
+#[derive(Deserialize)]
+struct RawApiError {
+    name: String,
+    #[serde(default)]
+    message: String,
+}
+
+impl From<RawApiErrorBody> for ApiErrorBody {
+    fn from(body: RawApiErrorBody) -> Self {
+        ApiErrorBody {
+            ok: body.ok,
+            name: body.error.name,
+            message: body.error.message,
+        }
+    }
+}
+
 /// response after an nft was stored
-#[derive(Debug, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[non_exhaustive]
 pub struct StoreNftResponse {
     /// status of the request
     pub ok: bool,
     /// stored nft data
     pub value: NftValue,
+    /// generated metadata.json content, present only when storing an nft
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<Value>,
+    /// true when the content was compressed with gzip before uploading it, the cid is the one of the compressed
+    /// bytes and the gateways serve them compressed
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub compressed: bool,
+    /// true when the `pin` and `deals` of the value were refreshed after the upload and the pin reached a final
+    /// status, see `UploadOptions::wait_for_status`
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub status_fresh: bool,
+}
+
+impl StoreNftResponse {
+    /// Token uri of the stored metadata, see [`NftValue::token_uri`]
+    pub fn token_uri(&self) -> Option<String> {
+        self.value.token_uri()
+    }
+
+    /// Token uri of the stored metadata through the `gateway`, see [`NftValue::gateway_token_uri`]
+    pub fn gateway_token_uri(&self, gateway: &str) -> Option<String> {
+        self.value.gateway_token_uri(gateway)
+    }
+}
+
+/// options used when storing an nft in a directory
+///
+/// ```
+/// use nft_storage::types::NftDirectoryOptions;
+/// use serde_json::json;
+///
+/// let mut options = NftDirectoryOptions {
+///     metadata_file_name: Some("0.json".to_string()),
+///     ..Default::default()
+/// };
+/// options.extra_fields.insert("external_url".to_string(), json!("https://example.com/nft/0"));
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct NftDirectoryOptions {
+    /// upload the metadata in a directory together with the files, so a single cid contains the whole nft
+    pub single_directory: bool,
+    /// name of the metadata file, `metadata.json` if not set
+    pub metadata_file_name: Option<String>,
+    /// fields added to the generated metadata like `external_url` or `attributes`, they can't replace `name`,
+    /// `description` and `files`
+    pub extra_fields: Map<String, Value>,
+}
+
+/// result of [`crate::NftStorage::store_nft_detailed`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[non_exhaustive]
+pub struct StoreNftResult {
+    /// upload of the nft file, the `image` of the metadata points to it
+    pub asset_response: StoreNftResponse,
+    /// upload of the metadata, its `metadata` contains the uploaded document
+    pub metadata_response: StoreNftResponse,
+    /// uploaded metadata, serializing it with `serde_json::to_vec` gives the uploaded bytes
+    pub metadata: NftMetadata,
+}
+
+/// response after an nft was stored in a directory
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[non_exhaustive]
+pub struct StoreNftDirectoryResponse {
+    /// upload of the files, the `files` of the metadata point to its directory
+    pub files: StoreNftResponse,
+    /// upload of `metadata.json`, its directory contains also the files when `single_directory` is set
+    pub metadata: StoreNftResponse,
+    /// generated metadata.json content
+    pub generated_metadata: Value,
+}
+
+/// files of a directory upload sharing the same content, see `upload_directory_deduplicated`
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub struct DedupReport {
+    /// number of distinct contents in the directory
+    pub unique_files: usize,
+    /// names of the files with the same content, only the groups of at least two files are reported
+    pub duplicate_groups: Vec<Vec<String>>,
+    /// bytes not sent because the same content was already in the upload
+    pub bytes_saved: u64,
+}
+
+/// response of `upload_directory_deduplicated`
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct DeduplicatedUpload {
+    /// the directory upload
+    pub upload: StoreNftResponse,
+    /// files sharing the same content
+    pub report: DedupReport,
+    /// path of every requested file in the directory, `<dir cid>/<name>`, keyed by the requested file name
+    pub paths: HashMap<String, String>,
+}
+
+/// result of [`crate::NftStorage::upload_directory_detailed`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[non_exhaustive]
+pub struct DirectoryUploadResult {
+    /// cid of the uploaded directory
+    pub dir_cid: Cid,
+    /// files stored in the directory
+    pub files: Vec<StoredFile>,
+}
+
+/// options used when uploading a json document
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct JsonUploadOptions {
+    /// upload the document in a directory with this file name, so the name is preserved
+    pub file_name: Option<String>,
+    /// serialize the document with indentation, it changes the cid of the document
+    pub pretty: bool,
+}
+
+/// response after a json document was uploaded
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[non_exhaustive]
+pub struct UploadJsonResponse {
+    /// response of the upload
+    pub upload: StoreNftResponse,
+    /// `ipfs://` url of the document, including the file name when it is uploaded in a directory
+    pub ipfs_uri: String,
+}
+
+/// a file stored in a directory upload
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[non_exhaustive]
+pub struct StoredFile {
+    /// file name, it is the path of the file within the directory
+    pub name: String,
+    /// file mime type
+    pub mime: String,
+    /// file size, `None` when the api doesn't report it so it isn't confused with an empty file
+    pub size: Option<u64>,
+    /// `ipfs://<dir cid>/<name>` url
+    pub ipfs_uri: String,
+    /// url of the file on the gateway of the client
+    pub gateway_url: String,
+}
+
+impl StoreNftDirectoryResponse {
+    /// Token uri of the generated metadata file, like `ipfs://<cid>/metadata.json`
+    pub fn token_uri(&self) -> Option<String> {
+        self.metadata.token_uri()
+    }
+
+    /// Token uri of the generated metadata file through the `gateway`
+    pub fn gateway_token_uri(&self, gateway: &str) -> Option<String> {
+        self.metadata.gateway_token_uri(gateway)
+    }
+}
+
+/// optional ERC-721 metadata fields used when storing an nft
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct Erc721Options {
+    /// url to view the item on your site
+    pub external_url: Option<String>,
+    /// url to a multi-media attachment for the item
+    pub animation_url: Option<String>,
+    /// background color of the item as a six-character hexadecimal without a pre-pended #
+    pub background_color: Option<String>,
+}
+
+/// nft metadata following the OpenSea metadata standard, it is stored as `metadata.json`
+///
+/// ```
+/// use nft_storage::types::NftMetadata;
+///
+/// let metadata = NftMetadata::new("My NFT name", "My NFT description")
+///     .external_url("https://example.com/nft/1")
+///     .attribute("Eyes", "Blue")
+///     .attribute("Level", 5);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct NftMetadata {
+    /// name of the item
+    pub name: String,
+    /// description of the item
+    pub description: String,
+    /// ipfs uri of the item image, it is filled when storing the nft
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub image: String,
+    /// url to view the item on your site
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub external_url: Option<String>,
+    /// url to a multi-media attachment for the item
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub animation_url: Option<String>,
+    /// background color of the item as a six-character hexadecimal without a pre-pended #
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub background_color: Option<String>,
+    /// item attributes displayed by marketplaces
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub attributes: Vec<Attribute>,
+}
+
+impl NftMetadata {
+    /// Create the metadata with the item `name` and `description`
+    pub fn new<N, D>(name: N, description: D) -> NftMetadata
+    where
+        N: Into<String>,
+        D: Into<String>,
+    {
+        NftMetadata {
+            name: name.into(),
+            description: description.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Set the item image uri
+    pub fn image<S>(mut self, image: S) -> NftMetadata
+    where
+        S: Into<String>,
+    {
+        self.image = image.into();
+        self
+    }
+
+    /// Set the url to view the item on your site
+    pub fn external_url<S>(mut self, external_url: S) -> NftMetadata
+    where
+        S: Into<String>,
+    {
+        self.external_url = Some(external_url.into());
+        self
+    }
+
+    /// Set the url to a multi-media attachment for the item
+    pub fn animation_url<S>(mut self, animation_url: S) -> NftMetadata
+    where
+        S: Into<String>,
+    {
+        self.animation_url = Some(animation_url.into());
+        self
+    }
+
+    /// Set the background color of the item
+    pub fn background_color<S>(mut self, background_color: S) -> NftMetadata
+    where
+        S: Into<String>,
+    {
+        self.background_color = Some(background_color.into());
+        self
+    }
+
+    /// Add an attribute to the item
+    pub fn attribute<S, V>(mut self, trait_type: S, value: V) -> NftMetadata
+    where
+        S: Into<String>,
+        V: Into<Value>,
+    {
+        self.attributes.push(Attribute {
+            trait_type: trait_type.into(),
+            value: value.into(),
+            display_type: None,
+        });
+        self
+    }
+
+    /// Add an attribute to the item specifying how marketplaces should display it, e.g. `boost_number` or `date`
+    pub fn attribute_with_display_type<S, V, T>(
+        mut self,
+        trait_type: S,
+        value: V,
+        display_type: T,
+    ) -> NftMetadata
+    where
+        S: Into<String>,
+        V: Into<Value>,
+        T: Into<String>,
+    {
+        self.attributes.push(Attribute {
+            trait_type: trait_type.into(),
+            value: value.into(),
+            display_type: Some(display_type.into()),
+        });
+        self
+    }
+}
+
+/// limits checked on the nft metadata before storing it, see `NftStorageBuilder::metadata_limits`
+///
+/// ```
+/// use nft_storage::types::{MetadataLimits, NftMetadata};
+///
+/// let limits = MetadataLimits::default();
+/// assert!(NftMetadata::new("My NFT", "My NFT description").validate(&limits).is_ok());
+/// assert!(NftMetadata::new("  ", "My NFT description").validate(&limits).is_err());
+/// assert!(NftMetadata::new("My\u{0}NFT", "My NFT description").validate(&limits).is_err());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MetadataLimits {
+    /// max number of characters of the name
+    pub max_name_len: usize,
+    /// max number of characters of the description
+    pub max_description_len: usize,
+}
+
+impl Default for MetadataLimits {
+    fn default() -> MetadataLimits {
+        MetadataLimits {
+            max_name_len: 256,
+            max_description_len: 10_000,
+        }
+    }
+}
+
+impl NftMetadata {
+    /// Check that the name isn't empty and that the name and the description are within `limits`
+    ///
+    /// Control characters and the unicode replacement character, left by a lossy utf-8 conversion, are rejected,
+    /// the description can contain new lines and tabs
+    pub fn validate(&self, limits: &MetadataLimits) -> Result<(), NFTStorageError> {
+        if self.name.trim().is_empty() {
+            return Err(NFTStorageError::InvalidInput(
+                "the nft name is empty".to_string(),
+            ));
+        }
+        validate_text("name", &self.name, limits.max_name_len, &[])?;
+        validate_text(
+            "description",
+            &self.description,
+            limits.max_description_len,
+            &['\n', '\r', '\t'],
+        )
+    }
+}
+
+/// check the length of a metadata field and its characters
+fn validate_text(
+    field: &str,
+    text: &str,
+    max_len: usize,
+    allowed_controls: &[char],
+) -> Result<(), NFTStorageError> {
+    let len = text.chars().count();
+    if len > max_len {
+        return Err(NFTStorageError::InvalidInput(format!(
+            "the nft {} is {} characters long, max allowed is {}",
+            field, len, max_len
+        )));
+    }
+    let invalid = text.chars().enumerate().find(|(_, c)| {
+        *c == char::REPLACEMENT_CHARACTER || (c.is_control() && !allowed_controls.contains(c))
+    });
+    match invalid {
+        Some((position, c)) => Err(NFTStorageError::InvalidInput(format!(
+            "the nft {} contains the invalid character {:?} at position {}",
+            field, c, position
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// nft attribute (trait) following the OpenSea metadata standard
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct Attribute {
+    /// name of the trait
+    pub trait_type: String,
+    /// value of the trait, it can be a string or a number
+    pub value: Value,
+    /// how the trait should be displayed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display_type: Option<String>,
+}
+
+/// max royalty of a Metaplex nft, 10000 basis points are 100%
+pub const MAX_SELLER_FEE_BASIS_POINTS: u16 = 10000;
+
+/// nft metadata following the Metaplex token standard used by Solana
+///
+/// ```
+/// use nft_storage::types::MetaplexMetadata;
+///
+/// let metadata = MetaplexMetadata::new("My NFT name", "MNFT", "My NFT description")
+///     .seller_fee_basis_points(500)
+///     .creator("7fUbGpDCNT5jC3n4sQtt4zXoBH8G7VdQKQcbNzKmDhSw", 80)
+///     .creator("CH1cZTvvE9MDmBLRoVtQSkHdYAw1PmHEdaxvJPa1Vs8H", 20)
+///     .category("image")
+///     .attribute("Eyes", "Blue");
+/// assert!(metadata.validate().is_ok());
+///
+/// let json = serde_json::to_value(&metadata).unwrap();
+/// assert_eq!(json["seller_fee_basis_points"], 500);
+/// assert_eq!(json["properties"]["creators"][1]["share"], 20);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct MetaplexMetadata {
+    /// name of the item
+    pub name: String,
+    /// symbol of the collection
+    pub symbol: String,
+    /// description of the item
+    pub description: String,
+    /// royalty paid to the creators on secondary sales in basis points, 500 is 5%
+    pub seller_fee_basis_points: u16,
+    /// ipfs uri of the item image, it is filled when storing the nft
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub image: String,
+    /// url to a multi-media attachment for the item
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub animation_url: Option<String>,
+    /// url to view the item on your site
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub external_url: Option<String>,
+    /// item attributes displayed by marketplaces
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub attributes: Vec<Attribute>,
+    /// files and creators of the item
+    pub properties: MetaplexProperties,
+}
+
+/// `properties` of the Metaplex metadata
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct MetaplexProperties {
+    /// files of the item, the stored image is added first
+    pub files: Vec<MetaplexFile>,
+    /// `image`, `video`, `audio`, `vr` or `html`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+    /// creators sharing the royalties
+    pub creators: Vec<MetaplexCreator>,
+}
+
+/// file of a Metaplex nft
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct MetaplexFile {
+    /// uri of the file
+    pub uri: String,
+    /// mime type of the file
+    #[serde(rename = "type")]
+    pub file_type: String,
+}
+
+/// creator of a Metaplex nft
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct MetaplexCreator {
+    /// solana address of the creator
+    pub address: String,
+    /// percentage of the royalties paid to the creator
+    pub share: u8,
+}
+
+impl MetaplexMetadata {
+    /// Create the metadata with the item `name`, the collection `symbol` and the item `description`
+    pub fn new<N, S, D>(name: N, symbol: S, description: D) -> MetaplexMetadata
+    where
+        N: Into<String>,
+        S: Into<String>,
+        D: Into<String>,
+    {
+        MetaplexMetadata {
+            name: name.into(),
+            symbol: symbol.into(),
+            description: description.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Set the royalty in basis points, it can't be bigger than 10000
+    pub fn seller_fee_basis_points(mut self, seller_fee_basis_points: u16) -> MetaplexMetadata {
+        self.seller_fee_basis_points = seller_fee_basis_points;
+        self
+    }
+
+    /// Add a creator receiving `share` percent of the royalties
+    pub fn creator<S>(mut self, address: S, share: u8) -> MetaplexMetadata
+    where
+        S: Into<String>,
+    {
+        self.properties.creators.push(MetaplexCreator {
+            address: address.into(),
+            share,
+        });
+        self
+    }
+
+    /// Set the category of the item, e.g. `image` or `video`
+    pub fn category<S>(mut self, category: S) -> MetaplexMetadata
+    where
+        S: Into<String>,
+    {
+        self.properties.category = Some(category.into());
+        self
+    }
+
+    /// Add a file to `properties.files`
+    pub fn file<U, T>(mut self, uri: U, file_type: T) -> MetaplexMetadata
+    where
+        U: Into<String>,
+        T: Into<String>,
+    {
+        self.properties.files.push(MetaplexFile {
+            uri: uri.into(),
+            file_type: file_type.into(),
+        });
+        self
+    }
+
+    /// Set the url to view the item on your site
+    pub fn external_url<S>(mut self, external_url: S) -> MetaplexMetadata
+    where
+        S: Into<String>,
+    {
+        self.external_url = Some(external_url.into());
+        self
+    }
+
+    /// Set the url to a multi-media attachment for the item
+    pub fn animation_url<S>(mut self, animation_url: S) -> MetaplexMetadata
+    where
+        S: Into<String>,
+    {
+        self.animation_url = Some(animation_url.into());
+        self
+    }
+
+    /// Add an attribute to the item
+    pub fn attribute<S, V>(mut self, trait_type: S, value: V) -> MetaplexMetadata
+    where
+        S: Into<String>,
+        V: Into<Value>,
+    {
+        self.attributes.push(Attribute {
+            trait_type: trait_type.into(),
+            value: value.into(),
+            display_type: None,
+        });
+        self
+    }
+
+    /// Check the royalty and the creator shares, they must sum to 100 when there are creators
+    pub fn validate(&self) -> Result<(), NFTStorageError> {
+        if self.seller_fee_basis_points > MAX_SELLER_FEE_BASIS_POINTS {
+            return Err(NFTStorageError::InvalidInput(format!(
+                "seller_fee_basis_points must be at most {}, got {}",
+                MAX_SELLER_FEE_BASIS_POINTS, self.seller_fee_basis_points
+            )));
+        }
+        let creators = &self.properties.creators;
+        let shares: u32 = creators.iter().map(|c| c.share as u32).sum();
+        if !creators.is_empty() && shares != 100 {
+            return Err(NFTStorageError::InvalidInput(format!(
+                "the creator shares must sum to 100, got {}",
+                shares
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// response after a Metaplex nft was stored
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[non_exhaustive]
+pub struct StoreMetaplexResponse {
+    /// ipfs cid of the image
+    pub image_cid: Cid,
+    /// ipfs cid of the metadata json, its `ipfs://` uri is the token uri
+    pub metadata_cid: Cid,
+    /// stored metadata
+    pub metadata: MetaplexMetadata,
+}
+
+/// item of a collection stored with `store_collection`, `image` of the metadata is set when storing it
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct CollectionItem {
+    /// image of the item, its name is the file name in the images directory
+    pub file: UploadFile,
+    /// metadata of the item
+    pub metadata: NftMetadata,
+}
+
+impl CollectionItem {
+    /// Create an item from its image and metadata
+    pub fn new(file: UploadFile, metadata: NftMetadata) -> CollectionItem {
+        CollectionItem { file, metadata }
+    }
+}
+
+/// options used when storing a collection
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CollectionOptions {
+    /// token id of the first item, the metadata files are named from it
+    pub start_index: u64,
+    /// name the metadata files `<token id>.json` instead of `<token id>`
+    pub json_extension: bool,
+}
+
+impl Default for CollectionOptions {
+    fn default() -> CollectionOptions {
+        CollectionOptions {
+            start_index: 0,
+            json_extension: true,
+        }
+    }
+}
+
+/// stored item of a collection
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[non_exhaustive]
+pub struct CollectionItemReport {
+    /// token id of the item
+    pub token_id: u64,
+    /// `ipfs://` uri of the image
+    pub image_uri: String,
+    /// `ipfs://` uri of the metadata, the token uri of the item
+    pub token_uri: String,
+    /// stored metadata
+    pub metadata: NftMetadata,
+}
+
+/// response after a collection was stored
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[non_exhaustive]
+pub struct StoreCollectionResponse {
+    /// ipfs cid of the images directory
+    pub images_cid: Cid,
+    /// ipfs cid of the metadata directory
+    pub metadata_cid: Cid,
+    /// base uri of the tokens `ipfs://<metadata_cid>/`, the token uri is the base uri followed by the token id
+    pub base_uri: String,
+    /// stored items in the same order they were provided
+    pub items: Vec<CollectionItemReport>,
+}
+
+/// response of the `/store` endpoint
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(default)]
+#[non_exhaustive]
+pub struct StoreResponse {
+    /// status of the request
+    pub ok: bool,
+    /// stored token data
+    pub value: StoreValue,
+}
+
+/// token data stored with the `/store` endpoint
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(default)]
+#[non_exhaustive]
+pub struct StoreValue {
+    /// ipfs cid of the token metadata
+    pub ipnft: Cid,
+    /// token uri of the metadata.json file `ipfs://<ipnft>/metadata.json`
+    pub url: String,
+    /// stored metadata where files are replaced by their ipfs urls
+    pub data: Value,
+}
+
+impl StoreValue {
+    /// Token uri of the metadata.json file, `ipfs://<ipnft>/metadata.json`
+    pub fn token_uri(&self) -> String {
+        format!("ipfs://{}/metadata.json", self.ipnft)
+    }
+
+    /// Token uri of the metadata.json file through the `gateway`, like
+    /// `https://nftstorage.link/ipfs/<ipnft>/metadata.json`
+    pub fn gateway_token_uri(&self, gateway: &str) -> String {
+        format!(
+            "{}/{}/metadata.json",
+            gateway.trim_end_matches('/'),
+            self.ipnft
+        )
+    }
+}
+
+/// a file to upload with its name
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct UploadFile {
+    /// file name
+    pub name: String,
+    /// file bytes
+    pub content: Vec<u8>,
+    /// file mime type, e.g. `image/png`
+    pub mime: Option<String>,
+}
+
+impl UploadFile {
+    /// Create a file to upload from its `name` and `content`
+    pub fn new<S>(name: S, content: Vec<u8>) -> UploadFile
+    where
+        S: Into<String>,
+    {
+        UploadFile {
+            name: name.into(),
+            content,
+            mime: None,
+        }
+    }
+
+    /// Set the file mime type
+    pub fn mime<S>(mut self, mime: S) -> UploadFile
+    where
+        S: Into<String>,
+    {
+        self.mime = Some(mime.into());
+        self
+    }
+
+    /// Mime type of the file, the one set with `mime` or the one detected by [`detect_mime`]
+    pub fn content_type(&self) -> Option<String> {
+        self.mime
+            .clone()
+            .or_else(|| detect_mime(&self.name, &self.content))
+    }
+
+    /// Convert the file into a multipart form part
+    pub(crate) fn into_part(self) -> Result<Part, reqwest::Error> {
+        self.into_part_with(Part::bytes::<Vec<u8>>)
+    }
+
+    /// Convert the file into a multipart form part creating the part from the content with `to_part`
+    pub(crate) fn into_part_with<F>(self, to_part: F) -> Result<Part, reqwest::Error>
+    where
+        F: FnOnce(Vec<u8>) -> Part,
+    {
+        let mime = self.content_type();
+        let part = to_part(self.content).file_name(self.name);
+        match mime {
+            Some(mime) => part.mime_str(&mime),
+            None => Ok(part),
+        }
+    }
+}
+
+/// Detect the mime type of a file from its first bytes, falling back to the extension of `name`
+///
+/// `None` is returned when the type is unknown
+/// ```
+/// use nft_storage::types::detect_mime;
+///
+/// // png signature
+/// let png = [0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0x00];
+/// assert_eq!(detect_mime("image", &png).as_deref(), Some("image/png"));
+/// // the extension is used when the content is unknown
+/// assert_eq!(detect_mime("metadata.json", b"{}").as_deref(), Some("application/json"));
+/// assert_eq!(detect_mime("file", b"hello"), None);
+/// ```
+pub fn detect_mime(name: &str, content: &[u8]) -> Option<String> {
+    infer::get(content)
+        .map(|kind| kind.mime_type().to_string())
+        .or_else(|| {
+            mime_guess::from_path(name)
+                .first()
+                .map(|mime| mime.essence_str().to_string())
+        })
+}
+
+/// options used when uploading a local directory
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct DirectoryOptions {
+    /// upload also the files of the sub directories preserving their relative path
+    pub recursive: bool,
+    /// upload also the hidden files and directories, the ones starting with `.`
+    pub include_hidden: bool,
+}
+
+/// options used to list a page of nfts
+///
+/// By default the latest 10 nfts are returned with their links
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListOptions {
+    /// return the nfts created before this timestamp
+    pub before: Option<Timestamp>,
+    /// max number of nfts to return, between 1 and 100
+    pub limit: Option<u32>,
+    /// return only the nfts containing just the metadata.json file
+    pub only_metadata: bool,
+    /// fill the `links` of the nfts, disable it when only the cids are needed to skip building them
+    pub decorate_links: bool,
+    /// return only the nfts with this kind of content, see [`NftValue::content_kind`]
+    pub content_kind: Option<ContentKind>,
+}
+
+impl Default for ListOptions {
+    fn default() -> ListOptions {
+        ListOptions {
+            before: None,
+            limit: None,
+            only_metadata: false,
+            decorate_links: true,
+            content_kind: None,
+        }
+    }
+}
+
+/// options used to filter the listed nfts, only the nfts matching all the provided options are returned
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ListFilter {
+    /// return only the nfts containing just the metadata.json file
+    pub only_metadata: bool,
+    /// pin status of the nft, e.g. `pinned`
+    pub pin_status: Option<String>,
+    /// min size of the nft in bytes
+    pub min_size: Option<u64>,
+    /// max size of the nft in bytes
+    pub max_size: Option<u64>,
+    /// text contained in the name of at least one file
+    pub file_name_contains: Option<String>,
+    /// mime type of the nft or of at least one of its files
+    pub mime_type: Option<String>,
+    /// if true return only the nfts with filecoin deals, if false only the ones without deals
+    pub has_deals: Option<bool>,
+    /// kind of content of the nft, see [`NftValue::content_kind`]
+    pub content_kind: Option<ContentKind>,
+}
+
+impl ListFilter {
+    /// Keep only the nfts matching all the filter options
+    ///
+    /// ```
+    /// use nft_storage::types::{ListFilter, NftValue};
+    /// use serde_json::json;
+    ///
+    /// let nfts: Vec<NftValue> = serde_json::from_value(json!([
+    ///     { "cid": "bafkreia", "size": 10, "files": [{ "name": "metadata.json", "type": "application/json" }] },
+    ///     { "cid": "bafkreib", "size": 2000, "type": "image/png", "deals": [{ "status": "active" }] },
+    /// ]))
+    /// .unwrap();
+    ///
+    /// let metadata = ListFilter { only_metadata: true, ..Default::default() };
+    /// assert_eq!(metadata.apply(nfts.clone())[0].cid.as_str(), "bafkreia");
+    ///
+    /// let big_with_deals = ListFilter { min_size: Some(1000), has_deals: Some(true), ..Default::default() };
+    /// assert_eq!(big_with_deals.apply(nfts.clone())[0].cid.as_str(), "bafkreib");
+    ///
+    /// let none = ListFilter { mime_type: Some("video/mp4".to_string()), ..Default::default() };
+    /// assert!(none.apply(nfts).is_empty());
+    /// ```
+    pub fn apply(&self, nfts: Vec<NftValue>) -> Vec<NftValue> {
+        nfts.into_iter().filter(|nft| self.matches(nft)).collect()
+    }
+
+    /// Check if the nft matches all the filter options
+    pub fn matches(&self, nft: &NftValue) -> bool {
+        if self.only_metadata && !nft.is_metadata() {
+            return false;
+        }
+        if let Some(pin_status) = &self.pin_status {
+            if &nft.pin.status != pin_status {
+                return false;
+            }
+        }
+        let size = nft.size;
+        if self.min_size.is_some_and(|min_size| size < min_size) {
+            return false;
+        }
+        if self.max_size.is_some_and(|max_size| size > max_size) {
+            return false;
+        }
+        if let Some(text) = &self.file_name_contains {
+            if !nft.files.iter().any(|f| f.name.contains(text.as_str())) {
+                return false;
+            }
+        }
+        if let Some(mime_type) = &self.mime_type {
+            if &nft.file_type != mime_type && !nft.files.iter().any(|f| &f.file_type == mime_type) {
+                return false;
+            }
+        }
+        if let Some(has_deals) = self.has_deals {
+            if nft.deals.is_empty() == has_deals {
+                return false;
+            }
+        }
+        if self
+            .content_kind
+            .is_some_and(|content_kind| nft.content_kind() != content_kind)
+        {
+            return false;
+        }
+        true
+    }
 }
 
 /// query nft from nft storage api
-#[derive(Debug, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[non_exhaustive]
 pub struct GetNftResponse {
     /// status of the request
     pub ok: bool,
@@ -28,15 +1179,387 @@ pub struct GetNftResponse {
     pub value: NftValue,
 }
 
+/// nft returned by `get_nft_with_metadata` with its metadata read from a gateway
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[non_exhaustive]
+pub struct GetNftDetailed {
+    /// response of the api
+    pub info: GetNftResponse,
+    /// parsed metadata, `None` if the nft has no metadata or it isn't a json object
+    pub metadata: Option<NftMetadata>,
+    /// metadata json as it is stored, `None` if the nft has no metadata or it isn't json
+    pub raw_metadata: Option<Value>,
+}
+
 /// response of a deleted nft
-#[derive(Debug, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[non_exhaustive]
 pub struct DeleteNftResponse {
     /// status of the request
     pub ok: bool,
 }
 
+/// storage used by the account, computed from the listed nfts
+///
+/// The `Display` implementation prints a short report
+/// ```
+/// use nft_storage::types::UsageSummary;
+///
+/// let mut usage = UsageSummary::default();
+/// usage.total_uploads = 2;
+/// usage.total_bytes = 1536;
+/// assert!(usage.to_string().starts_with("2 uploads, 1.50 KiB (1536 bytes)"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub struct UsageSummary {
+    /// number of stored nfts
+    pub total_uploads: u64,
+    /// sum of the size of the stored nfts
+    pub total_bytes: u64,
+    /// nfts with the `pinned` status
+    pub pinned: u64,
+    /// nfts with the `pinning` status
+    pub pinning: u64,
+    /// nfts with the `queued` status
+    pub queued: u64,
+    /// nfts with the `failed` status
+    pub failed: u64,
+    /// creation date of the oldest nft
+    pub oldest: Option<Timestamp>,
+    /// creation date of the newest nft
+    pub newest: Option<Timestamp>,
+}
+
+impl UsageSummary {
+    /// add an nft to the summary
+    pub(crate) fn add(&mut self, nft: &NftValue) {
+        self.total_uploads += 1;
+        self.total_bytes += nft.size;
+        match nft.pin.status.as_str() {
+            "pinned" => self.pinned += 1,
+            "pinning" => self.pinning += 1,
+            "queued" => self.queued += 1,
+            "failed" => self.failed += 1,
+            _ => {}
+        }
+        // `Timestamp` is `Copy` only with chrono
+        let created = &nft.created;
+        match &self.oldest {
+            Some(oldest) if oldest <= created => {}
+            _ => self.oldest = Some(created.to_owned()),
+        }
+        match &self.newest {
+            Some(newest) if newest >= created => {}
+            _ => self.newest = Some(created.to_owned()),
+        }
+    }
+}
+
+impl fmt::Display for UsageSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} uploads, {} ({} bytes)",
+            self.total_uploads,
+            format_bytes(self.total_bytes),
+            self.total_bytes
+        )?;
+        writeln!(
+            f,
+            "pinned: {}, pinning: {}, queued: {}, failed: {}",
+            self.pinned, self.pinning, self.queued, self.failed
+        )?;
+        match (&self.oldest, &self.newest) {
+            (Some(oldest), Some(newest)) => write!(f, "from {} to {}", oldest, newest),
+            _ => write!(f, "no uploads"),
+        }
+    }
+}
+
+/// format `bytes` with the biggest binary unit
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["KiB", "MiB", "GiB", "TiB", "PiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.2} {}", value, UNITS[unit])
+}
+
+/// summary of the deleted nfts
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct DeleteAllSummary {
+    /// cids of the deleted nfts
+    pub deleted: Vec<Cid>,
+    /// cids of the nfts that couldn't be deleted with the error
+    pub failed: Vec<(Cid, NFTStorageError)>,
+    /// cids of the nfts rejected by the `confirm` predicate
+    pub skipped: Vec<Cid>,
+    /// cids of the nfts that would be deleted, filled only in dry-run mode
+    pub would_delete: Vec<Cid>,
+}
+
+/// predicate deciding if an nft should be deleted
+pub type DeleteConfirm = Box<dyn Fn(&NftValue) -> bool + Send + Sync>;
+
+/// options used when deleting all nfts
+pub struct DeleteAllOptions {
+    /// only list the nfts that would be deleted without deleting them
+    pub dry_run: bool,
+    /// called for every nft, when it returns false the nft is skipped
+    pub confirm: Option<DeleteConfirm>,
+    /// delete only the nfts created before this timestamp
+    pub before: Option<Timestamp>,
+    /// max listings in a row returning only nfts already processed before giving up
+    pub max_stalled_passes: usize,
+    /// max deletions running at the same time
+    pub concurrency: usize,
+    /// stop before the next deletion, the deletions already started are completed
+    pub cancel: Option<CancelSignal>,
+}
+
+impl Default for DeleteAllOptions {
+    fn default() -> DeleteAllOptions {
+        DeleteAllOptions {
+            dry_run: false,
+            confirm: None,
+            before: None,
+            max_stalled_passes: 3,
+            concurrency: 1,
+            cancel: None,
+        }
+    }
+}
+
+impl fmt::Debug for DeleteAllOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DeleteAllOptions")
+            .field("dry_run", &self.dry_run)
+            .field(
+                "confirm",
+                &self.confirm.as_ref().map(|_| "Fn(&NftValue) -> bool"),
+            )
+            .field("before", &self.before)
+            .field("max_stalled_passes", &self.max_stalled_passes)
+            .field("concurrency", &self.concurrency)
+            .field("cancel", &self.cancel)
+            .finish()
+    }
+}
+
+/// Signal stopping a long running operation at the next step, like `delete_all_nft_with_options`
+///
+/// It wraps a future completing when the operation must stop, like the `cancelled` future of a tokio-util
+/// `CancellationToken`. The operation returns a `Cancelled` error with the work completed so far
+/// ```
+/// use nft_storage::types::{CancelSignal, DeleteAllOptions};
+///
+/// let (cancel, cancelled) = tokio::sync::oneshot::channel::<()>();
+/// let options = DeleteAllOptions {
+///     // dropping `cancel` stops the deletion too
+///     cancel: Some(CancelSignal::new(async move {
+///         let _ = cancelled.await;
+///     })),
+///     ..Default::default()
+/// };
+/// ```
+pub struct CancelSignal {
+    /// future completing when the operation is cancelled
+    future: std::pin::Pin<Box<dyn Future<Output = ()> + Send>>,
+    /// the future completed, it can't be polled again
+    cancelled: bool,
+}
+
+impl CancelSignal {
+    /// Create a signal cancelling the operation when `future` completes
+    pub fn new<F>(future: F) -> CancelSignal
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        CancelSignal {
+            future: Box::pin(future),
+            cancelled: false,
+        }
+    }
+
+    /// Check without waiting if the operation has been cancelled
+    pub fn is_cancelled(&mut self) -> bool {
+        if !self.cancelled {
+            self.cancelled = self.future.as_mut().now_or_never().is_some();
+        }
+        self.cancelled
+    }
+}
+
+impl fmt::Debug for CancelSignal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CancelSignal")
+            .field("cancelled", &self.cancelled)
+            .finish()
+    }
+}
+
+/// work completed by an operation stopped with a [`CancelSignal`] or by the deadline of its [`RequestOptions`],
+/// returned in the `Cancelled` and `DeadlineExceeded` errors
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum PartialResult {
+    /// nothing was completed
+    Empty,
+    /// uploads completed by a store operation before stopping, like the file of `store_nft`
+    Stored(Vec<StoreNftResponse>),
+    /// nfts deleted by `delete_all_nft_with_options`
+    DeleteAll(DeleteAllSummary),
+    /// results of `upload_files_concurrent_with_options` in the order of the files, `None` if the upload wasn't started
+    Uploads(Vec<Option<Result<StoreNftResponse, NFTStorageError>>>),
+}
+
+impl fmt::Display for PartialResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PartialResult::Empty => write!(f, "nothing completed"),
+            PartialResult::Stored(uploads) => write!(f, "{} uploads completed", uploads.len()),
+            PartialResult::DeleteAll(summary) => write!(
+                f,
+                "{} nfts deleted and {} failed",
+                summary.deleted.len(),
+                summary.failed.len()
+            ),
+            PartialResult::Uploads(results) => write!(
+                f,
+                "{} of {} uploads completed",
+                results.iter().flatten().count(),
+                results.len()
+            ),
+        }
+    }
+}
+
+/// options used when uploading many files with `upload_files_concurrent_with_options`
+#[derive(Debug)]
+pub struct ConcurrentUploadOptions {
+    /// max uploads running at the same time
+    pub concurrency: usize,
+    /// rate limiter awaited before every upload, it is shared with the other clients
+    pub rate_limiter: Option<std::sync::Arc<crate::RateLimiter>>,
+    /// stop before the next upload, the uploads already started are completed
+    pub cancel: Option<CancelSignal>,
+}
+
+impl Default for ConcurrentUploadOptions {
+    fn default() -> ConcurrentUploadOptions {
+        ConcurrentUploadOptions {
+            concurrency: 1,
+            rate_limiter: None,
+            cancel: None,
+        }
+    }
+}
+
+/// callback receiving the progress of an upload
+pub type UploadProgressCallback = Box<dyn Fn(UploadProgress) + Send + Sync>;
+
+/// how a directory upload handles the files with the same name
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DedupeStrategy {
+    /// keep only the last file with the name
+    Overwrite,
+    /// return a `DuplicateFileName` error
+    #[default]
+    Error,
+    /// rename the duplicates adding a counter before the extension, `image (1).png`
+    AutoRename,
+}
+
+/// options used when uploading files
+#[derive(Default)]
+pub struct UploadOptions {
+    /// called while the files are sent, at least once at the start and once at the end
+    pub on_progress: Option<UploadProgressCallback>,
+    /// how the files of a directory with the same name are handled, the names are compared after trimming them
+    pub dedupe_strategy: DedupeStrategy,
+    /// accept the file names containing `/` or `\` creating nested directories
+    pub allow_nested_paths: bool,
+    /// compress the text and json files with gzip before uploading them, the other types are sent as they are.
+    ///
+    /// The stored bytes change so the cid is the one of the compressed content, see [`crate::compress`].
+    /// Only `upload_file_with_options` supports it, the directory uploads return an `InvalidInput` error
+    #[cfg(feature = "gzip")]
+    pub compress: bool,
+    /// after the upload poll its status for at most this duration, the last observed `pin` and `deals` are merged in
+    /// the response.
+    ///
+    /// The pin is still queued right after an upload, the response is returned also when the wait elapses with
+    /// `status_fresh` false
+    pub wait_for_status: Option<Duration>,
+}
+
+impl fmt::Debug for UploadOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug = f.debug_struct("UploadOptions");
+        debug
+            .field(
+                "on_progress",
+                &self.on_progress.as_ref().map(|_| "Fn(UploadProgress)"),
+            )
+            .field("dedupe_strategy", &self.dedupe_strategy)
+            .field("allow_nested_paths", &self.allow_nested_paths);
+        #[cfg(feature = "gzip")]
+        debug.field("compress", &self.compress);
+        debug.field("wait_for_status", &self.wait_for_status);
+        debug.finish()
+    }
+}
+
+/// progress of an upload
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct UploadProgress {
+    /// bytes of the files sent so far
+    pub bytes_sent: u64,
+    /// size of all the files
+    pub total_bytes: u64,
+}
+
+/// progress of the deletion of all nfts
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct DeleteProgress {
+    /// cid of the processed nft
+    pub cid: Cid,
+    /// position of the processed nft, starting from 0
+    pub index: usize,
+    /// nfts found so far to delete, the ones skipped by the confirm predicate aren't counted
+    pub total: usize,
+    /// nfts skipped so far by the confirm predicate
+    pub skipped: usize,
+    /// true if the nft was deleted
+    pub deleted: bool,
+}
+
+/// progress of an upload split in many CAR shards
+#[cfg(feature = "car")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct CarUploadProgress {
+    /// bytes of the file contained in the uploaded shards
+    pub bytes_sent: u64,
+    /// size of the file
+    pub total_bytes: u64,
+    /// shards uploaded so far
+    pub shards_completed: usize,
+}
+
 /// check if an nft exist response from nft storage api
-#[derive(Debug, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[non_exhaustive]
 pub struct CheckCidNftResponse {
     /// status of the request
     pub ok: bool,
@@ -44,17 +1567,231 @@ pub struct CheckCidNftResponse {
     pub value: CheckNFTValue,
 }
 
+/// timeout and deadline of the api requests of a client, see `NftStorage::with_request_options`
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RequestOptions {
+    /// max duration of every request, it replaces the timeout of the client
+    pub timeout: Option<Duration>,
+    /// no request is sent after this instant, the operations making more requests share it as the budget of all of
+    /// them
+    pub deadline: Option<std::time::Instant>,
+}
+
+/// options of the requests reading the nfts, see `get_nft_with_options`
+#[cfg(feature = "cache")]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReadOptions {
+    /// request the nft to the api even if its response is cached
+    pub bypass_cache: bool,
+}
+
+/// options used by `wait_for_pin` to poll the pin status
+///
+/// The delay between two polls starts from `poll_interval` and is multiplied by `backoff_factor` after every poll
+/// up to `max_poll_interval`, a factor of 1 polls at a fixed interval
+/// ```
+/// use nft_storage::{pins::PinningStatus, types::WaitOptions};
+/// use std::time::Duration;
+///
+/// let options = WaitOptions {
+///     timeout: Duration::from_secs(60),
+///     fail_on: vec![PinningStatus::Failed],
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct WaitOptions {
+    /// max time to wait for the content to be pinned
+    pub timeout: Duration,
+    /// delay before the second poll
+    pub poll_interval: Duration,
+    /// max delay between two polls
+    pub max_poll_interval: Duration,
+    /// factor applied to the delay after every poll
+    pub backoff_factor: f64,
+    /// statuses that stop the wait with a `PinFailed` error
+    pub fail_on: Vec<PinningStatus>,
+}
+
+impl WaitOptions {
+    /// Delay to wait after the poll number `attempt`, starting from 0
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let factor = self.backoff_factor.max(1.0).powi(attempt as i32);
+        self.poll_interval
+            .mul_f64(factor.min(u32::MAX as f64))
+            .min(self.max_poll_interval)
+    }
+}
+
+impl Default for WaitOptions {
+    fn default() -> WaitOptions {
+        WaitOptions {
+            timeout: Duration::from_secs(10 * 60),
+            poll_interval: Duration::from_secs(2),
+            max_poll_interval: Duration::from_secs(30),
+            backoff_factor: 1.5,
+            fail_on: vec![PinningStatus::Failed],
+        }
+    }
+}
+
+/// format of the listing written by `export_listing`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// comma separated values with a header, the fields are quoted when needed
+    Csv,
+    /// one json object per line
+    Ndjson,
+}
+
+/// parse `csv` or `ndjson`, ignoring the case
+impl std::str::FromStr for ExportFormat {
+    type Err = NFTStorageError;
+
+    fn from_str(format: &str) -> Result<ExportFormat, NFTStorageError> {
+        match format.to_ascii_lowercase().as_str() {
+            "csv" => Ok(ExportFormat::Csv),
+            "ndjson" => Ok(ExportFormat::Ndjson),
+            _ => Err(NFTStorageError::InvalidInput(format!(
+                "unknown export format '{}', expected csv or ndjson",
+                format
+            ))),
+        }
+    }
+}
+
+/// options used by `upload_and_verify`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifyOptions {
+    /// gateways used to read the content back, the client gateway is used when it is empty
+    pub gateways: Vec<String>,
+    /// time to wait for every gateway
+    pub timeout: Duration,
+    /// read the content back from a gateway also when its cid can be computed locally
+    pub force_readback: bool,
+}
+
+impl Default for VerifyOptions {
+    fn default() -> VerifyOptions {
+        VerifyOptions {
+            gateways: Vec::new(),
+            timeout: Duration::from_secs(60),
+            force_readback: false,
+        }
+    }
+}
+
+/// how the content of an upload was verified
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum VerificationMethod {
+    /// the cid was computed locally from the content, for files up to `cid::MAX_RAW_BLOCK_SIZE`
+    LocalCid,
+    /// the content was downloaded back from a gateway and its sha2-256 digest compared
+    GatewayReadback,
+}
+
+/// upload returned by `upload_and_verify`
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct VerifiedUpload {
+    /// response of the upload
+    pub response: StoreNftResponse,
+    /// false when the content couldn't be read back from any gateway, the error is in `readback_error`
+    pub verified: bool,
+    /// how the content was verified
+    pub method: VerificationMethod,
+    /// gateway that returned the content
+    pub gateway: Option<String>,
+    /// error of the gateways when the content couldn't be read back
+    pub readback_error: Option<NFTStorageError>,
+}
+
+/// active filecoin deals of a cid, returned by `check_deals` and `wait_for_deal`
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct DealStatus {
+    /// cid of the checked content
+    pub cid: Cid,
+    /// deals that are active or published on chain, with their miner, id and expiration
+    pub active_deals: Vec<Deals>,
+    /// number of active deals required
+    pub min_active_deals: usize,
+}
+
+impl DealStatus {
+    /// Whether the content has at least `min_active_deals` active deals
+    pub fn is_ready(&self) -> bool {
+        self.active_deals.len() >= self.min_active_deals
+    }
+}
+
+/// result of `upload_file_if_absent`
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum UploadIfAbsentResponse {
+    /// the content wasn't stored and it has been uploaded
+    Uploaded(StoreNftResponse),
+    /// the content was already stored, nothing has been uploaded
+    AlreadyStored(CheckCidNftResponse),
+    /// the file is bigger than `cid::MAX_RAW_BLOCK_SIZE`, its cid can't be computed locally so it has been uploaded
+    /// without checking if it was already stored
+    UploadedUnchecked(StoreNftResponse),
+}
+
+impl UploadIfAbsentResponse {
+    /// Whether the file has been uploaded
+    pub fn is_uploaded(&self) -> bool {
+        !matches!(self, UploadIfAbsentResponse::AlreadyStored(_))
+    }
+}
+
 /// main obj that hold all the response data
-#[derive(Debug, Serialize, Deserialize, Default)]
+///
+/// ```
+/// use nft_storage::types::NftValue;
+///
+/// // entry captured from a real listing response
+/// let nft: NftValue = serde_json::from_str(r#"{
+///     "cid": "bafybeiflbavrum45ekg5qxbecvpn5bfcvuk45txcmgsabfebtkv44cn6vq",
+///     "created": "2021-12-02T08:52:33.461+00:00",
+///     "type": "directory",
+///     "scope": "session",
+///     "files": [{ "name": "metadata.json", "type": "application/json" }],
+///     "size": 1147081,
+///     "pin": {
+///         "cid": "bafybeiflbavrum45ekg5qxbecvpn5bfcvuk45txcmgsabfebtkv44cn6vq",
+///         "created": "2021-12-02T08:52:33.461+00:00",
+///         "size": 1147081,
+///         "status": "queued"
+///     },
+///     "deals": []
+/// }"#).unwrap();
+///
+/// assert_eq!(nft.file_type, "directory");
+/// assert_eq!(nft.scope, "session");
+/// assert_eq!(nft.created.to_rfc3339(), "2021-12-02T08:52:33.461+00:00");
+/// assert_eq!(nft.files[0].name, "metadata.json");
+/// assert_eq!(nft.files[0].file_type, "application/json");
+///
+/// // files bigger than 4GB
+/// let nft: NftValue = serde_json::from_str(r#"{ "size": 4294967296 }"#).unwrap();
+/// assert_eq!(nft.size, 4294967296);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
 #[serde(default)]
+#[non_exhaustive]
 pub struct NftValue {
     /// ipfs cid (file hash)
-    pub cid: String,
+    pub cid: Cid,
     /// file size
-    pub size: i32,
+    pub size: u64,
     /// date uploaded
-    pub created: String,
+    #[cfg_attr(feature = "chrono", serde(deserialize_with = "deserialize_timestamp"))]
+    pub created: Timestamp,
     /// type of the file (mime type)
+    #[serde(rename = "type")]
     pub file_type: String,
     pub scope: String,
     /// filecoin pin data
@@ -64,15 +1801,176 @@ pub struct NftValue {
     /// filecoin deals data
     pub deals: Vec<Deals>,
     /// ipfs links to view file
-    pub link: Vec<String>,
+    pub links: GatewayLinks,
+    /// ipfs links to view every file of a directory upload
+    pub file_links: Vec<FileLinks>,
+}
+
+impl NftValue {
+    /// Token uri of the nft metadata, `ipfs://<cid>/metadata.json`, used by the marketplaces
+    ///
+    /// The metadata file is `metadata.json` or the only json file of a directory, like `0.json`, the uri is
+    /// `ipfs://<cid>` when the upload is a single json file. `None` is returned when the upload isn't metadata
+    /// ```
+    /// use nft_storage::types::NftValue;
+    /// use serde_json::json;
+    ///
+    /// let nft: NftValue = serde_json::from_value(json!({
+    ///     "cid": "bafybeia",
+    ///     "files": [{ "name": "image.png", "type": "image/png" }, { "name": "metadata.json", "type": "application/json" }],
+    /// }))
+    /// .unwrap();
+    /// assert_eq!(nft.token_uri().as_deref(), Some("ipfs://bafybeia/metadata.json"));
+    /// assert_eq!(
+    ///     nft.gateway_token_uri("https://nftstorage.link/ipfs/").as_deref(),
+    ///     Some("https://nftstorage.link/ipfs/bafybeia/metadata.json")
+    /// );
+    ///
+    /// let nft: NftValue = serde_json::from_value(json!({ "cid": "bafybeib", "files": [{ "name": "0.json" }] })).unwrap();
+    /// assert_eq!(nft.token_uri().as_deref(), Some("ipfs://bafybeib/0.json"));
+    ///
+    /// let nft: NftValue = serde_json::from_value(json!({ "cid": "bafkreic", "type": "application/json" })).unwrap();
+    /// assert_eq!(nft.token_uri().as_deref(), Some("ipfs://bafkreic"));
+    ///
+    /// // an image isn't metadata
+    /// let nft: NftValue = serde_json::from_value(json!({ "cid": "bafkreid", "type": "image/png" })).unwrap();
+    /// assert_eq!(nft.token_uri(), None);
+    /// ```
+    pub fn token_uri(&self) -> Option<String> {
+        let path = self.token_path()?;
+        Some(format!("ipfs://{}{}", self.cid, path))
+    }
+
+    /// Token uri of the nft metadata through the `gateway`, like `https://nftstorage.link/ipfs/<cid>/metadata.json`
+    ///
+    /// The metadata file is found like in `token_uri`
+    pub fn gateway_token_uri(&self, gateway: &str) -> Option<String> {
+        let path = self.token_path()?;
+        Some(format!(
+            "{}/{}{}",
+            gateway.trim_end_matches('/'),
+            self.cid,
+            path
+        ))
+    }
+
+    /// Kind of content of the upload, used to tell the directories from the single files
+    ///
+    /// The uploads whose first file is `metadata.json`, the ones returned by `only_metadata`, are `MetadataJson`.
+    /// The other uploads with more files or with the `directory` type are `Directory`, the ones with a mime type
+    /// and at most one file are `SingleFile`
+    /// ```
+    /// # #[cfg(feature = "test-util")]
+    /// # fn main() {
+    /// use nft_storage::{test_util::fixtures, types::*};
+    ///
+    /// let kind = |fixture| serde_json::from_str::<StoreNftResponse>(fixture).unwrap().value.content_kind();
+    /// assert_eq!(kind(fixtures::FILE_UPLOAD_RESPONSE), ContentKind::SingleFile);
+    /// assert_eq!(kind(fixtures::UPLOAD_RESPONSE), ContentKind::Directory);
+    /// assert_eq!(kind(fixtures::METADATA_UPLOAD_RESPONSE), ContentKind::MetadataJson);
+    ///
+    /// // an image and its metadata
+    /// let list: ListNftResponse = serde_json::from_str(fixtures::LIST_RESPONSE).unwrap();
+    /// assert_eq!(list.value[0].content_kind(), ContentKind::Directory);
+    ///
+    /// // nothing tells what the upload contains
+    /// assert_eq!(NftValue::default().content_kind(), ContentKind::Unknown);
+    /// # }
+    /// # #[cfg(not(feature = "test-util"))]
+    /// # fn main() {}
+    /// ```
+    pub fn content_kind(&self) -> ContentKind {
+        if self.is_metadata() {
+            ContentKind::MetadataJson
+        } else if self.files.len() > 1 || self.file_type == "directory" {
+            ContentKind::Directory
+        } else if !self.file_type.is_empty() {
+            ContentKind::SingleFile
+        } else {
+            ContentKind::Unknown
+        }
+    }
+
+    /// Whether the upload contains just the metadata.json file
+    pub(crate) fn is_metadata(&self) -> bool {
+        // we always know that there is only one file in the files array if we store a metadata nft
+        self.files
+            .first()
+            .is_some_and(|f| f.name == "metadata.json")
+    }
+
+    /// path of the metadata file in the upload, empty when the upload is the metadata itself
+    pub(crate) fn token_path(&self) -> Option<String> {
+        if self.files.iter().any(|f| f.name == "metadata.json") {
+            return Some("/metadata.json".to_string());
+        }
+        let mut json_files = self
+            .files
+            .iter()
+            .filter(|f| f.name.to_ascii_lowercase().ends_with(".json"));
+        match (json_files.next(), json_files.next()) {
+            (Some(file), None) => Some(format!("/{}", file.name)),
+            // more json files, the metadata one can't be chosen
+            (Some(_), Some(_)) => None,
+            (None, _)
+                if self.files.is_empty() && self.file_type.starts_with("application/json") =>
+            {
+                Some(String::new())
+            }
+            (None, _) => None,
+        }
+    }
+}
+
+/// Print the cid and its preferred link, only the cid when the links weren't decorated
+/// ```
+/// use nft_storage::types::NftValue;
+/// use serde_json::json;
+///
+/// let cid = "bafybeiflbavrum45ekg5qxbecvpn5bfcvuk45txcmgsabfebtkv44cn6vq";
+/// let nft: NftValue = serde_json::from_value(json!({ "cid": cid })).unwrap();
+/// assert_eq!(nft.to_string(), cid);
+///
+/// let nft: NftValue = serde_json::from_value(json!({
+///     "cid": cid,
+///     "links": {
+///         "dweb": format!("https://{}.ipfs.dweb.link", cid),
+///         "custom": [format!("https://nftstorage.link/ipfs/{}", cid)],
+///     },
+/// }))
+/// .unwrap();
+/// assert_eq!(nft.to_string(), format!("{} (https://nftstorage.link/ipfs/{})", cid, cid));
+/// ```
+impl fmt::Display for NftValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.links == GatewayLinks::default() {
+            return write!(f, "{}", self.cid);
+        }
+        write!(f, "{} ({})", self.cid, self.links.preferred())
+    }
+}
+
+/// kind of content of an upload, see [`NftValue::content_kind`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContentKind {
+    /// a single file uploaded as it is
+    SingleFile,
+    /// an ipfs directory containing one or more files
+    Directory,
+    /// the metadata.json of an nft
+    MetadataJson,
+    /// the upload doesn't report its type or files
+    Unknown,
 }
 
 /// data that holds data about queried nft when checking when it exists on nft storage
-#[derive(Debug, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
 #[serde(default)]
+#[non_exhaustive]
 pub struct CheckNFTValue {
     /// ipfs cid (file hash)
-    pub cid: String,
+    pub cid: Cid,
     /// filecoin pin data
     pub pin: Pin,
     /// filecoin deals data
@@ -80,37 +1978,225 @@ pub struct CheckNFTValue {
 }
 
 /// filecoin pin data
-#[derive(Debug, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
 #[serde(default)]
+#[non_exhaustive]
 pub struct Pin {
     /// ipfs cid (file hash)
-    pub cid: String,
+    pub cid: Cid,
     /// pin status of the nft
     pub status: String,
     /// creation date
-    pub created: String,
+    #[cfg_attr(feature = "chrono", serde(deserialize_with = "deserialize_timestamp"))]
+    pub created: Timestamp,
     /// size of teh file
-    pub size: i32,
+    pub size: u64,
+}
+
+impl Deals {
+    /// Whether the deal is `active` or `published` on chain
+    pub fn is_active(&self) -> bool {
+        self.status.eq_ignore_ascii_case("active") || self.status.eq_ignore_ascii_case("published")
+    }
+}
+
+impl Pin {
+    /// Parsed `status`, `None` if the api returned an unknown status
+    pub fn pinning_status(&self) -> Option<PinningStatus> {
+        serde_json::from_value(Value::String(self.status.clone())).ok()
+    }
 }
 
 /// file information
-#[derive(Debug, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
 #[serde(default)]
+#[non_exhaustive]
 pub struct Files {
     /// file name
     pub name: String,
     /// file mime type
+    #[serde(rename = "type")]
     pub file_type: String,
+    /// file size, `None` when the api doesn't report it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+}
+
+/// ipfs links to view a single file of a directory upload
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(default)]
+#[non_exhaustive]
+pub struct FileLinks {
+    /// file name
+    pub name: String,
+    /// links to view the file
+    pub links: GatewayLinks,
+}
+
+/// links to view a cid through the ipfs gateways
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(default)]
+#[non_exhaustive]
+pub struct GatewayLinks {
+    /// dweb.link gateway url
+    pub dweb: String,
+    /// ipfs.io gateway url
+    pub ipfs_io: String,
+    /// `ipfs://` url
+    pub ipfs_uri: String,
+    /// urls of the gateways registered on the client with `NftStorageBuilder::gateways` or
+    /// `NftStorageBuilder::link_gateway`, in the same order
+    pub custom: Vec<String>,
+}
+
+impl GatewayLinks {
+    /// Create the links of `cid`, the optional `path` is appended to every link
+    ///
+    /// `custom_gateways` are gateway urls like `https://<subdomain>.nftstorage.link/ipfs`, the cid is added after them
+    pub fn new(cid: &str, path: &str, custom_gateways: &[String]) -> GatewayLinks {
+        let gateways = custom_gateways
+            .iter()
+            .map(Gateway::path)
+            .collect::<Vec<_>>();
+        GatewayLinks::with_gateways(cid, path, &gateways)
+    }
+
+    /// Create the links of `cid` using the path or subdomain style `gateways` for the `custom` links
+    pub fn with_gateways(cid: &str, path: &str, gateways: &[Gateway]) -> GatewayLinks {
+        GatewayLinks {
+            dweb: Gateway::subdomain("https://dweb.link").url(cid, path),
+            ipfs_io: Gateway::path("https://ipfs.io/ipfs").url(cid, path),
+            ipfs_uri: format!("ipfs://{}{}", cid, link_path(path)),
+            custom: gateways
+                .iter()
+                .map(|gateway| gateway.url(cid, path))
+                .collect(),
+        }
+    }
+
+    /// Link of the first custom gateway, the dweb.link one when no gateway is configured
+    pub fn preferred(&self) -> &str {
+        self.custom.first().unwrap_or(&self.dweb)
+    }
+}
+
+/// how a gateway addresses the content
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GatewayStyle {
+    /// the cid is in the path, `https://host/ipfs/<cid>`
+    Path,
+    /// the cid is in the subdomain, `https://<cid>.ipfs.host`, it must be a CIDv1
+    Subdomain,
+}
+
+/// ipfs gateway used to build the links of the nfts
+///
+/// The cids are normalized to base32 CIDv1 with [`Cid::to_v1_base32`] in the subdomain links, because the subdomains
+/// are case insensitive. A cid that can't be converted or that is too long for a dns label is linked in the path of
+/// the gateway instead
+/// ```
+/// use nft_storage::types::Gateway;
+///
+/// let path = Gateway::path("https://nftstorage.link/ipfs/");
+/// let subdomain = Gateway::subdomain("https://nftstorage.link");
+/// let v0 = "QmWATWQ7fVPP2EFGu71UkfnqhYXDYH566qy47CnJDgvs8u";
+/// let v1 = "bafybeiduiecxoeiqs3gyc6r7v3lymmhserldnpw62qjnhmqsulqjxjmtzi";
+///
+/// assert_eq!(path.url(v1, "image.png"), format!("https://nftstorage.link/ipfs/{}/image.png", v1));
+/// assert_eq!(path.url(v0, ""), format!("https://nftstorage.link/ipfs/{}", v0));
+/// assert_eq!(subdomain.url(v1, "/image.png"), format!("https://{}.ipfs.nftstorage.link/image.png", v1));
+/// // the CIDv0 and the base58btc CIDv1 are converted
+/// assert_eq!(subdomain.url(v0, ""), format!("https://{}.ipfs.nftstorage.link", v1));
+/// let base58 = "zdj7WdFhsqBWxrytwZCdqy6d3dQKkifFpVDyANan1Ay38VmJq";
+/// assert_eq!(subdomain.url(base58, ""), format!("https://{}.ipfs.nftstorage.link", v1));
+/// // an invalid cid falls back to the path
+/// assert_eq!(subdomain.url("not-a-cid", ""), "https://nftstorage.link/ipfs/not-a-cid");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Gateway {
+    /// url of the gateway, the `/ipfs` prefix is included only for the path style
+    base_url: String,
+    /// how the cid is added to the url
+    style: GatewayStyle,
+}
+
+/// max length of a dns label, the subdomain containing the cid
+const MAX_DNS_LABEL_LEN: usize = 63;
+
+impl Gateway {
+    /// Path style gateway, `base_url` must end before the cid like `https://nftstorage.link/ipfs`
+    pub fn path<S>(base_url: S) -> Gateway
+    where
+        S: AsRef<str>,
+    {
+        Gateway {
+            base_url: base_url.as_ref().trim_end_matches('/').to_string(),
+            style: GatewayStyle::Path,
+        }
+    }
+
+    /// Subdomain style gateway, `base_url` is the origin of the gateway like `https://nftstorage.link`
+    pub fn subdomain<S>(base_url: S) -> Gateway
+    where
+        S: AsRef<str>,
+    {
+        Gateway {
+            base_url: base_url.as_ref().trim_end_matches('/').to_string(),
+            style: GatewayStyle::Subdomain,
+        }
+    }
+
+    /// Url of the gateway
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// How the gateway addresses the content
+    pub fn style(&self) -> GatewayStyle {
+        self.style
+    }
+
+    /// Url of `cid` on the gateway, the optional `path` is appended
+    pub fn url(&self, cid: &str, path: &str) -> String {
+        let path = link_path(path);
+        if self.style == GatewayStyle::Path {
+            return format!("{}/{}{}", self.base_url, cid, path);
+        }
+        let v1 = Cid(cid.to_string())
+            .to_v1_base32()
+            .ok()
+            .filter(|v1| v1.0.len() <= MAX_DNS_LABEL_LEN);
+        match (v1, self.base_url.split_once("://")) {
+            (Some(v1), Some((scheme, host))) => {
+                format!("{}://{}.ipfs.{}{}", scheme, v1, host, path)
+            }
+            (Some(v1), None) => format!("https://{}.ipfs.{}{}", v1, self.base_url, path),
+            // the subdomain gateways serve also the path style urls
+            (None, _) => format!("{}/ipfs/{}{}", self.base_url, cid, path),
+        }
+    }
+}
+
+/// `path` starting with a `/`, empty if there is no path
+fn link_path(path: &str) -> String {
+    let path = path.trim_start_matches('/');
+    if path.is_empty() {
+        String::new()
+    } else {
+        format!("/{}", path)
+    }
 }
 
 /// filecoin deals data
-#[derive(Debug, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
 #[serde(default)]
+#[non_exhaustive]
 pub struct Deals {
     #[serde(rename = "batchRootCid")]
     pub batch_root_cid: String,
     #[serde(rename = "lastChanged")]
-    pub last_changed: String,
+    #[cfg_attr(feature = "chrono", serde(deserialize_with = "deserialize_timestamp"))]
+    pub last_changed: Timestamp,
     pub miner: String,
     #[serde(rename = "pieceCid")]
     pub piece_cid: String,
@@ -118,11 +2204,13 @@ pub struct Deals {
     #[serde(rename = "statusText")]
     pub status_text: String,
     #[serde(rename = "chainDealID")]
-    pub chain_deal_id: i32,
+    pub chain_deal_id: u64,
     #[serde(rename = "dealActivation")]
-    pub deal_activation: String,
+    #[cfg_attr(feature = "chrono", serde(deserialize_with = "deserialize_timestamp"))]
+    pub deal_activation: Timestamp,
     #[serde(rename = "dealExpiration")]
-    pub deal_expiration: String,
+    #[cfg_attr(feature = "chrono", serde(deserialize_with = "deserialize_timestamp"))]
+    pub deal_expiration: Timestamp,
     #[serde(rename = "datamodelSelector")]
     pub data_model_selector: String,
 }
@@ -0,0 +1,349 @@
+//! web3.storage client, available with the `web3-storage` feature
+//!
+//! The classic web3.storage api is almost the same of nft storage, the client shares the transport, retries,
+//! rate limiter and hooks of [`NftStorage`], see [`Web3Storage::from_client`].
+//! Both clients implement [`IpfsStorageProvider`], so the same file can be sent to either of them
+//! ```no_run
+//! use nft_storage::{web3_storage::Web3Storage, types::UploadFile, IpfsStorageProvider, NftStorage};
+//! use anyhow::Result;
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<()> {
+//!     let providers: Vec<Box<dyn IpfsStorageProvider>> = vec![
+//!         Box::new(NftStorage::new("https://api.nft.storage", "token generated from nft storage")),
+//!         Box::new(Web3Storage::new("token generated from web3 storage")?),
+//!     ];
+//!     let file = UploadFile::new("hello.txt", b"hello".to_vec());
+//!     for provider in providers {
+//!         let stored = provider.upload_file(file.clone()).await?;
+//!         println!("{} {} bytes", stored.cid, stored.size);
+//!     }
+//!
+//!     Ok(())
+//! }
+//! ```
+use crate::{
+    parse_response,
+    provider::{IpfsStorageProvider, ProviderFuture, StoredObject},
+    types::{to_rfc3339, Cid, Timestamp, UploadFile},
+    with_name, NFTStorageError, NftStorage,
+};
+use reqwest::{header::CONTENT_TYPE, multipart::Form};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// default web3.storage api endpoint
+pub const WEB3_STORAGE_API_URL: &str = "https://api.web3.storage";
+
+/// web3.storage ipfs gateway
+pub const WEB3_STORAGE_GATEWAY_URL: &str = "https://w3s.link/ipfs/";
+
+/// max number of uploads returned by a single list request
+pub const MAX_UPLOADS_PAGE_SIZE: u32 = 1000;
+
+/// response of `/upload` and `/car`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(default)]
+#[non_exhaustive]
+pub struct UploadResponse {
+    /// cid of the uploaded content
+    pub cid: Cid,
+}
+
+/// upload returned by `/user/uploads` and `/status/{cid}`
+///
+/// ```
+/// use nft_storage::web3_storage::Upload;
+///
+/// let upload: Upload = serde_json::from_str(r#"{
+///     "cid": "bafkreidivzimqfqtoqxkrpge6bjyhlvxqs3rhe73owtmdulaxr5do5in7u",
+///     "dagSize": 132614,
+///     "created": "2021-03-12T17:03:07.787Z",
+///     "pins": [{ "peerId": "12D3KooWMbibcXHwkSjgV7VZ8TMfDKi6pZvmi97P83ZwHm9LEsvV", "status": "Pinned" }],
+///     "deals": []
+/// }"#).unwrap();
+///
+/// assert_eq!(upload.dag_size, 132614);
+/// assert_eq!(upload.pins[0].status, "Pinned");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(default, rename_all = "camelCase")]
+#[non_exhaustive]
+pub struct Upload {
+    /// cid of the uploaded content
+    pub cid: Cid,
+    /// name of the upload, the status endpoint doesn't return it
+    pub name: String,
+    /// size of the whole dag
+    pub dag_size: u64,
+    /// date uploaded
+    #[cfg_attr(
+        feature = "chrono",
+        serde(deserialize_with = "crate::types::deserialize_timestamp")
+    )]
+    pub created: Timestamp,
+    /// date of the last change
+    #[cfg_attr(
+        feature = "chrono",
+        serde(deserialize_with = "crate::types::deserialize_timestamp")
+    )]
+    pub updated: Timestamp,
+    /// ipfs cluster pins of the content
+    pub pins: Vec<UploadPin>,
+    /// filecoin deals of the content
+    pub deals: Vec<UploadDeal>,
+}
+
+/// pin of an upload on a node of the ipfs cluster
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(default, rename_all = "camelCase")]
+#[non_exhaustive]
+pub struct UploadPin {
+    /// libp2p peer id of the node
+    pub peer_id: String,
+    /// name of the node
+    pub peer_name: String,
+    /// region of the node
+    pub region: String,
+    /// `Pinned`, `Pinning`, `PinQueued`, `Unpinned` or `PinError`
+    pub status: String,
+    /// date of the last status change
+    #[cfg_attr(
+        feature = "chrono",
+        serde(deserialize_with = "crate::types::deserialize_timestamp")
+    )]
+    pub updated: Timestamp,
+}
+
+/// filecoin deal of an upload
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(default, rename_all = "camelCase")]
+#[non_exhaustive]
+pub struct UploadDeal {
+    /// on chain deal id, missing while the deal is queued
+    pub deal_id: Option<u64>,
+    /// address of the storage provider
+    pub storage_provider: String,
+    /// `Queued`, `Published` or `Active`
+    pub status: String,
+    /// cid of the filecoin piece containing the upload
+    pub piece_cid: String,
+    /// cid of the aggregate containing the upload
+    pub data_cid: String,
+    /// date the deal was activated
+    #[cfg_attr(
+        feature = "chrono",
+        serde(deserialize_with = "crate::types::deserialize_timestamp")
+    )]
+    pub activation: Timestamp,
+    /// date of the last status change
+    #[cfg_attr(
+        feature = "chrono",
+        serde(deserialize_with = "crate::types::deserialize_timestamp")
+    )]
+    pub updated: Timestamp,
+}
+
+impl From<Upload> for StoredObject {
+    fn from(upload: Upload) -> StoredObject {
+        StoredObject::new(upload.cid, upload.dag_size, upload.created)
+    }
+}
+
+/// filters used to list the uploads
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ListUploadsQuery {
+    /// return only the uploads created before this date
+    pub before: Option<Timestamp>,
+    /// max number of uploads returned, between 1 and 1000
+    pub size: Option<u32>,
+}
+
+/// web3.storage client
+///
+/// Cloning it is cheap, the clones share the connection pool and the rate limiter
+#[derive(Debug, Clone)]
+pub struct Web3Storage {
+    /// client running the requests, its url is the web3.storage api
+    inner: NftStorage,
+}
+
+impl Web3Storage {
+    /// Create a new web3.storage client from the token generated from the web3.storage dashboard
+    pub fn new<S>(token: S) -> Result<Web3Storage, NFTStorageError>
+    where
+        S: Into<String>,
+    {
+        let inner = NftStorage::builder(WEB3_STORAGE_API_URL.to_string(), token.into())
+            .gateway(WEB3_STORAGE_GATEWAY_URL)
+            .build()?;
+        Ok(Web3Storage::from_client(inner))
+    }
+
+    /// Create a web3.storage client from an already configured client, its url must be the web3.storage api
+    /// ```no_run
+    /// use nft_storage::{web3_storage::{Web3Storage, WEB3_STORAGE_API_URL}, NftStorage};
+    /// use std::time::Duration;
+    ///
+    /// let client = NftStorage::builder(WEB3_STORAGE_API_URL, "token generated from web3 storage")
+    ///     .timeout(Duration::from_secs(60 * 60))
+    ///     .build()
+    ///     .unwrap();
+    /// let web3_storage = Web3Storage::from_client(client);
+    /// ```
+    pub fn from_client(inner: NftStorage) -> Web3Storage {
+        Web3Storage { inner }
+    }
+
+    /// The client used to run the requests
+    pub fn inner(&self) -> &NftStorage {
+        &self.inner
+    }
+
+    /// Upload a single file, its name is used as the name of the upload
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn upload(&self, file: UploadFile) -> Result<UploadResponse, NFTStorageError> {
+        let content_type = file.content_type();
+        let url = self.inner.endpoint("upload");
+        let mut request = self.inner.client.post(url);
+        if let Some(content_type) = content_type {
+            request = request.header(CONTENT_TYPE, content_type);
+        }
+        let request = with_name(request, &file.name).body(file.content);
+        self.inner.request(request).await
+    }
+
+    /// Upload the files in a directory named `name`, the file names can contain `/` to create sub directories
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn upload_directory(
+        &self,
+        name: &str,
+        files: Vec<UploadFile>,
+    ) -> Result<UploadResponse, NFTStorageError> {
+        if files.is_empty() {
+            return Err(NFTStorageError::InvalidInput(
+                "at least one file is required".to_string(),
+            ));
+        }
+        let mut form = Form::new();
+        for file in files {
+            form = form.part("file", file.into_part()?);
+        }
+        let url = self.inner.endpoint("upload");
+        let request = with_name(self.inner.client.post(url), name).multipart(form);
+        self.inner.request(request).await
+    }
+
+    /// List a page of uploads, the newest first
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn list_uploads(
+        &self,
+        query: &ListUploadsQuery,
+    ) -> Result<Vec<Upload>, NFTStorageError> {
+        let mut params = vec![
+            ("sortBy", "Date".to_string()),
+            ("sortOrder", "Desc".to_string()),
+        ];
+        if let Some(before) = &query.before {
+            params.push(("before", to_rfc3339(before)));
+        }
+        if let Some(size) = query.size {
+            if size == 0 || size > MAX_UPLOADS_PAGE_SIZE {
+                return Err(NFTStorageError::InvalidInput(format!(
+                    "size must be between 1 and {}, got {}",
+                    MAX_UPLOADS_PAGE_SIZE, size
+                )));
+            }
+            params.push(("size", size.to_string()));
+        }
+        let url = self.inner.endpoint("user/uploads");
+        let request = self.inner.client.get(url).query(&params);
+        self.inner.request(request).await
+    }
+
+    /// Get the pins and the deals of `cid`
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, fields(cid = tracing::field::Empty))
+    )]
+    pub async fn status<C>(&self, cid: C) -> Result<Upload, NFTStorageError>
+    where
+        C: TryInto<Cid>,
+        NFTStorageError: From<C::Error>,
+    {
+        let cid: Cid = cid.try_into()?;
+        #[cfg(feature = "tracing")]
+        tracing::Span::current().record("cid", cid.as_str());
+        let url = self.inner.endpoint(&format!("status/{}", cid));
+        self.inner.request(self.inner.client.get(url)).await
+    }
+
+    /// Remove the upload of `cid` from the account
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, fields(cid = tracing::field::Empty))
+    )]
+    pub async fn delete<C>(&self, cid: C) -> Result<(), NFTStorageError>
+    where
+        C: TryInto<Cid>,
+        NFTStorageError: From<C::Error>,
+    {
+        let cid: Cid = cid.try_into()?;
+        #[cfg(feature = "tracing")]
+        tracing::Span::current().record("cid", cid.as_str());
+        let url = self.inner.endpoint(&format!("user/uploads/{}", cid));
+        let response = self
+            .inner
+            .execute_with_retry(self.inner.client.delete(url))
+            .await?;
+        // the api responds with the deleted cid
+        parse_response::<Value>(response).await.map(|_| ())
+    }
+}
+
+impl IpfsStorageProvider for Web3Storage {
+    fn upload_file(&self, file: UploadFile) -> ProviderFuture<'_, StoredObject> {
+        // the upload response has only the cid, the size and the date are read from the status
+        Box::pin(async move {
+            let cid = self.upload(file).await?.cid;
+            Ok(self.status(cid).await?.into())
+        })
+    }
+
+    fn upload_directory(&self, files: Vec<UploadFile>) -> ProviderFuture<'_, StoredObject> {
+        Box::pin(async move {
+            let cid = Web3Storage::upload_directory(self, "upload", files)
+                .await?
+                .cid;
+            Ok(self.status(cid).await?.into())
+        })
+    }
+
+    fn list(&self) -> ProviderFuture<'_, Vec<StoredObject>> {
+        Box::pin(async move {
+            let mut objects: Vec<StoredObject> = Vec::new();
+            let mut before = None;
+            loop {
+                let query = ListUploadsQuery {
+                    before,
+                    size: Some(MAX_UPLOADS_PAGE_SIZE),
+                };
+                let page = self.list_uploads(&query).await?;
+                let is_last_page = page.len() < MAX_UPLOADS_PAGE_SIZE as usize;
+                objects.extend(page.into_iter().map(StoredObject::from));
+                match objects.last() {
+                    Some(last) if !is_last_page => before = Some(last.created.to_owned()),
+                    _ => return Ok(objects),
+                }
+            }
+        })
+    }
+
+    fn delete<'a>(&'a self, cid: &'a Cid) -> ProviderFuture<'a, ()> {
+        Box::pin(async move { Web3Storage::delete(self, cid).await })
+    }
+
+    fn status<'a>(&'a self, cid: &'a Cid) -> ProviderFuture<'a, StoredObject> {
+        Box::pin(async move { Ok(Web3Storage::status(self, cid).await?.into()) })
+    }
+}